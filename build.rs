@@ -0,0 +1,23 @@
+fn main() {
+    #[cfg(any(feature = "grpc", feature = "proto"))]
+    std::env::set_var(
+        "PROTOC",
+        protoc_bin_vendored::protoc_bin_path().expect("vendored protoc is unavailable"),
+    );
+    #[cfg(feature = "grpc")]
+    {
+        // the generated connect() needs the 2021 prelude, clients can connect a Channel instead
+        tonic_build::configure()
+            .build_transport(false)
+            .compile_protos(&["proto/rwc.proto"], &["proto"])
+            .expect("failed to compile proto/rwc.proto");
+    }
+    #[cfg(feature = "proto")]
+    {
+        tonic_build::configure()
+            .build_client(false)
+            .build_server(false)
+            .compile_protos(&["proto/counts.proto"], &["proto"])
+            .expect("failed to compile proto/counts.proto");
+    }
+}
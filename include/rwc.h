@@ -0,0 +1,58 @@
+/*
+ * C API for rwc's counters. Build the shared library with:
+ *
+ *     cargo rustc --release --lib --features rwc-ffi --crate-type cdylib
+ */
+#ifndef RWC_H
+#define RWC_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RWC_OK 0
+#define RWC_ERR_NULL 1
+#define RWC_ERR_IO 2
+#define RWC_ERR_UTF8 3
+#define RWC_ERR_PATH 4
+#define RWC_ERR_OTHER 5
+
+/* Value stored in rwc_counts for counts which weren't computed. */
+#define RWC_COUNT_NA UINT64_MAX
+
+typedef struct rwc_count_options {
+    bool bytes;
+    bool chars;
+    bool words;
+    bool lines;
+} rwc_count_options;
+
+typedef struct rwc_counts {
+    uint64_t bytes;
+    uint64_t chars;
+    uint64_t words;
+    uint64_t lines;
+} rwc_counts;
+
+/* Count the file at path. Returns RWC_OK and fills out_counts on success. */
+int rwc_count_file(const char *path, rwc_count_options options, rwc_counts *out_counts);
+
+/* Count len bytes starting at data. Returns RWC_OK and fills out_counts on success. */
+int rwc_count_buffer(const uint8_t *data, size_t len, rwc_count_options options,
+                     rwc_counts *out_counts);
+
+/*
+ * Message for the last error returned on the calling thread, or NULL. The string is owned by
+ * rwc and is valid until the next failing call on the same thread.
+ */
+const char *rwc_last_error_message(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RWC_H */
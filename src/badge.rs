@@ -0,0 +1,120 @@
+//! `--badge`, which prints a shields.io endpoint badge of the total of one count.
+
+use crate::count::{CountKind, Counts, Total};
+use crate::error::Error;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// The schema of https://shields.io/badges/endpoint-badge.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Endpoint {
+    schema_version: u8,
+    label: &'static str,
+    message: String,
+    color: &'static str,
+}
+
+/// A short total like `999`, `1.2k`, `42k`, or `3.5M`.
+pub fn format_total(n: u64) -> String {
+    const SUFFIXES: [&str; 4] = ["", "k", "M", "G"];
+    let mut scaled = n as f64;
+    let mut unit = 0;
+    while scaled >= 1000.0 && unit + 1 < SUFFIXES.len() {
+        scaled /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        return n.to_string();
+    }
+    // a single decimal below 100, e.g. 1.2k but 123k
+    let mut rounded = if scaled >= 100.0 {
+        scaled.round()
+    } else {
+        (scaled * 10.0).round() / 10.0
+    };
+    if rounded >= 1000.0 && unit + 1 < SUFFIXES.len() {
+        rounded = 1.0;
+        unit += 1;
+    }
+    if rounded.fract() == 0.0 {
+        format!("{:.0}{}", rounded, SUFFIXES[unit])
+    } else {
+        format!("{:.1}{}", rounded, SUFFIXES[unit])
+    }
+}
+
+/// The endpoint JSON for the total of kind over results. Any error fails the badge rather than
+/// publishing a wrong total.
+pub fn badge_json(
+    kind: CountKind,
+    results: Vec<(Result<Counts, Error>, PathBuf)>,
+) -> Result<String, Error> {
+    let mut total = Total::default();
+    let mut errors = Vec::new();
+    for (res, _) in results {
+        match res {
+            Ok(counts) => total = total + counts.get(kind),
+            Err(err) => errors.push(err),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+    if total.overflowed {
+        return Err(Error::CUSTOM(format!(
+            "the total {} overflowed",
+            kind.name()
+        )));
+    }
+    serde_json::to_string(&Endpoint {
+        schema_version: 1,
+        label: kind.name(),
+        message: format_total(total.val),
+        color: "blue",
+    })
+    .map_err(|err| Error::CUSTOM(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::Count;
+
+    #[test]
+    fn test_format_total() {
+        assert_eq!("0", format_total(0));
+        assert_eq!("999", format_total(999));
+        assert_eq!("1k", format_total(1000));
+        assert_eq!("1.2k", format_total(1234));
+        assert_eq!("42k", format_total(42_000));
+        assert_eq!("85k", format_total(85_049));
+        assert_eq!("123k", format_total(123_456));
+        assert_eq!("1M", format_total(999_999));
+        assert_eq!("3.5M", format_total(3_456_789));
+        assert_eq!("2G", format_total(2_000_000_000));
+    }
+
+    #[test]
+    fn test_badge_json() {
+        let counts = |lines| Counts {
+            bytes: Count { val: None },
+            chars: Count { val: None },
+            words: Count { val: None },
+            lines: Count { val: Some(lines) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts(40_000)), PathBuf::from("a")),
+            (Ok(counts(2_000)), PathBuf::from("b")),
+        ];
+        assert_eq!(
+            r#"{"schemaVersion":1,"label":"lines","message":"42k","color":"blue"}"#,
+            badge_json(CountKind::Lines, results).unwrap()
+        );
+
+        let results = vec![(Err(Error::UTF8()), PathBuf::from("a"))];
+        assert!(badge_json(CountKind::Lines, results).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! `--batch`, which keeps one process counting the paths it's sent on standard input, printing a
+//! csv row for each as soon as it's counted, so build systems don't pay for startup per file.
+
+use crate::cli::Options;
+use crate::count::Row;
+use crate::error::Error;
+use crate::exit;
+use crate::interrupt;
+use crate::print::{csv_header, csv_record, csv_row, csv_totals};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+/// A line of input, either a path to count or a directive starting with `:`. A path starting with
+/// `:` has to be written like `./:name`.
+#[derive(Debug, PartialEq, Eq)]
+enum Record {
+    Path(PathBuf),
+    /// `:totals`, print a totals row for the rows since the start or the last `:reset`.
+    Totals,
+    /// `:reset`, start the totals over.
+    Reset,
+}
+
+/// The record in line, None for a blank line.
+fn parse_record(line: Vec<u8>) -> Result<Option<Record>, Error> {
+    let line = String::from_utf8(line)?;
+    // commands typed at a console or written by echo end in CRLF on Windows
+    let line = line.trim_end_matches('\r');
+    match line {
+        "" => Ok(None),
+        ":totals" => Ok(Some(Record::Totals)),
+        ":reset" => Ok(Some(Record::Reset)),
+        _ if line.starts_with(':') => Err(Error::CUSTOM(format!(
+            "unknown directive {}, expected :totals or :reset",
+            line
+        ))),
+        _ => Ok(Some(Record::Path(PathBuf::from(line)))),
+    }
+}
+
+/// Count each path in input, separated by separator, with count and print the csv rows to w
+/// as they're done, with a `partial` column whatever the rows are. Returns the exit code for all
+/// the rows.
+pub fn run<R, W, F>(
+    opts: &Options,
+    input: R,
+    mut w: W,
+    separator: u8,
+    count: F,
+) -> Result<i32, Error>
+where
+    R: BufRead,
+    W: Write,
+    F: Fn(PathBuf) -> Vec<Row>,
+{
+    writeln!(w, "{}", csv_record(&csv_header(opts, true), opts))?;
+    w.flush()?;
+    let mut all = Vec::new();
+    let mut since_reset = Vec::new();
+    for line in input.split(separator) {
+        let rows = match parse_record(line?) {
+            Ok(None) => continue,
+            Ok(Some(Record::Path(path))) => {
+                // a Ctrl-C only stops the path being counted when it came, not the ones after
+                interrupt::reset();
+                count(path)
+            }
+            Ok(Some(Record::Totals)) => {
+                writeln!(
+                    w,
+                    "{}",
+                    csv_record(&csv_totals(&since_reset, opts, true), opts)
+                )?;
+                w.flush()?;
+                continue;
+            }
+            Ok(Some(Record::Reset)) => {
+                all.append(&mut since_reset);
+                continue;
+            }
+            Err(err) => vec![(Err(err), PathBuf::from("Batch"))],
+        };
+        for (res, path) in &rows {
+            writeln!(w, "{}", csv_record(&csv_row(res, path, opts, true), opts))?;
+        }
+        w.flush()?;
+        since_reset.extend(rows);
+    }
+    all.append(&mut since_reset);
+    Ok(exit::for_results(&all))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use crate::count::{CountOptions, CountablePath};
+    use std::path::Path;
+    use structopt::StructOpt;
+
+    #[test]
+    fn test_parse_record() {
+        assert_eq!(None, parse_record(b"\r".to_vec()).unwrap());
+        assert_eq!(
+            Some(Record::Totals),
+            parse_record(b":totals".to_vec()).unwrap()
+        );
+        assert_eq!(
+            Some(Record::Path(PathBuf::from("./:totals"))),
+            parse_record(b"./:totals".to_vec()).unwrap()
+        );
+        assert!(parse_record(b":quit".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let opts = Options::from(&Cli::from_iter(&["rwc", "-l"]));
+        let count = |path: PathBuf| {
+            let res = Path::new("test_data")
+                .join(&path)
+                .count_with(CountOptions::new().lines());
+            vec![(res, path)]
+        };
+        let input = &b"default.txt\nmissing.txt\n:totals\n:reset\n\n:totals\n:bogus\n"[..];
+        let mut out = Vec::new();
+        let code = run(&opts, input, &mut out, b'\n', count).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(6, lines.len());
+        assert_eq!(vec!["path,lines,partial", "default.txt,20681,"], lines[..2]);
+        assert!(lines[2].starts_with("missing.txt,IO Error: "));
+        assert_eq!(vec!["Totals*,20681,", "Totals,0,"], lines[3..5]);
+        assert!(lines[5].starts_with("Batch,\"Error: unknown directive :bogus, expected"));
+        assert_eq!(exit::SOME_FAILED, code);
+    }
+}
@@ -0,0 +1,122 @@
+//! `--git-blame`, which attributes the lines of git-tracked files to the authors who last
+//! changed them.
+
+use crate::count::{CountOptions, Countable, Counts, Row};
+use crate::error::Error;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The lines of path by author, from `git blame --line-porcelain`, each ending in `\n`.
+fn blame(path: &Path) -> Result<BTreeMap<String, Vec<u8>>, Error> {
+    let dir = match path.parent() {
+        Some(dir) if dir.as_os_str().is_empty() => Path::new("."),
+        Some(dir) => dir,
+        None => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::CUSTOM(String::from("not a file")))?;
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(name)
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::CUSTOM(format!(
+            "git blame failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(parse_blame(&output.stdout))
+}
+
+/// Every line of a blamed file is a header naming its author among others, then the line itself
+/// after a tab.
+fn parse_blame(porcelain: &[u8]) -> BTreeMap<String, Vec<u8>> {
+    let mut lines: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    let mut author = String::new();
+    for line in porcelain.split(|&b| b == b'\n') {
+        if let Some(name) = line.strip_prefix(b"author ") {
+            author = String::from_utf8_lossy(name).into_owned();
+        } else if let Some(content) = line.strip_prefix(b"\t") {
+            let text = lines.entry(author.clone()).or_default();
+            text.extend_from_slice(content);
+            text.push(b'\n');
+        }
+    }
+    lines
+}
+
+/// A row for each author of the lines of paths with the sum of the counts of their lines,
+/// labelled with the author and sorted by name, followed by a row for each file which couldn't
+/// be blamed. Lines are counted with a trailing `\n` even if the file lacks one.
+pub fn count_blame(paths: &[PathBuf], opts: &CountOptions) -> Vec<Row> {
+    let blamed: Vec<_> = paths
+        .par_iter()
+        .map(|path| {
+            let by_author = blame(path)?
+                .into_iter()
+                .map(|(author, text)| Ok((author, text.as_slice().count_with(opts.clone())?)))
+                .collect::<Result<Vec<(String, Counts)>, Error>>()?;
+            Ok(by_author)
+        })
+        .collect();
+
+    let mut by_author: BTreeMap<String, Vec<Counts>> = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (res, path) in blamed.into_iter().zip(paths) {
+        match res {
+            Ok(counts) => {
+                for (author, counts) in counts {
+                    by_author.entry(author).or_default().push(counts);
+                }
+            }
+            Err(err) => errors.push((Err(Error::for_path(path, err)), path.clone())),
+        }
+    }
+    by_author
+        .into_iter()
+        .map(|(author, counts)| (Ok(counts.into_iter().sum()), PathBuf::from(author)))
+        .chain(errors)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blame() {
+        let porcelain = b"0123abcd 1 1 2\n\
+            author Ada\n\
+            author-mail <ada@example.com>\n\
+            summary first\n\
+            filename a.txt\n\
+            \tauthor Bob said\n\
+            0123abcd 2 2\n\
+            author Ada\n\
+            filename a.txt\n\
+            \t\n\
+            4567ef01 3 3 1\n\
+            author Bob\n\
+            filename a.txt\n\
+            \tlast\n";
+        let lines = parse_blame(porcelain);
+        assert_eq!(2, lines.len());
+        assert_eq!(b"author Bob said\n\n".to_vec(), lines["Ada"]);
+        assert_eq!(b"last\n".to_vec(), lines["Bob"]);
+    }
+
+    #[test]
+    fn test_count_blame_untracked() {
+        let path = std::env::temp_dir().join(format!("rwc-blame-{}", std::process::id()));
+        std::fs::write(&path, "a\n").unwrap();
+        let rows = count_blame(std::slice::from_ref(&path), &CountOptions::new().lines());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(1, rows.len());
+        assert!(rows[0].0.is_err());
+        assert_eq!(path, rows[0].1);
+    }
+}
@@ -0,0 +1,131 @@
+//! `--blank-lines` and `--nonblank-lines`, the lines of each file which are empty or only
+//! whitespace and the lines with content, without a separate grep pass.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+
+pub const BLANK_LINES: &str = "blank lines";
+pub const NONBLANK_LINES: &str = "nonblank lines";
+
+/// Lines ending with `\n` which are empty or only ascii whitespace, like `\r` before the `\n`,
+/// and the rest. A last line without a `\n` is blank if it's only whitespace, but not a line at
+/// all if it's empty.
+#[derive(Debug)]
+struct Lines {
+    blank: u64,
+    nonblank: u64,
+    /// Whether the current line is only whitespace so far.
+    is_blank: bool,
+    /// Whether the current line has any bytes.
+    in_line: bool,
+}
+
+impl Default for Lines {
+    fn default() -> Lines {
+        Lines {
+            blank: 0,
+            nonblank: 0,
+            is_blank: true,
+            in_line: false,
+        }
+    }
+}
+
+impl Lines {
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            if b == b'\n' {
+                self.end_line();
+            } else {
+                self.is_blank &= b.is_ascii_whitespace();
+                self.in_line = true;
+            }
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.is_blank {
+            self.blank += 1;
+        } else {
+            self.nonblank += 1;
+        }
+        self.is_blank = true;
+        self.in_line = false;
+    }
+
+    fn finish(&mut self) {
+        if self.in_line {
+            self.end_line();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BlankLines(Lines);
+
+impl Metric for BlankLines {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        MetricValue::Count(self.0.blank)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NonblankLines(Lines);
+
+impl Metric for NonblankLines {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        MetricValue::Count(self.0.nonblank)
+    }
+}
+
+/// The `BLANK_LINES` column.
+pub fn blank_lines_metric() -> MetricFactory {
+    MetricFactory::new(BLANK_LINES, BlankLines::default)
+}
+
+/// The `NONBLANK_LINES` column.
+pub fn nonblank_lines_metric() -> MetricFactory {
+    MetricFactory::new(NONBLANK_LINES, NonblankLines::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(input: &str, chunk: usize) -> (u64, u64) {
+        let mut lines = Lines::default();
+        for chunk in input.as_bytes().chunks(chunk) {
+            lines.update(chunk);
+        }
+        lines.finish();
+        (lines.blank, lines.nonblank)
+    }
+
+    #[test]
+    fn test_lines() {
+        let input = "\nfn main() {\n  \t\r\n    x\n\r\n}\n \t";
+        for chunk in 1..input.len() {
+            assert_eq!((4, 3), lines(input, chunk));
+        }
+        assert_eq!((0, 0), lines("", 64));
+        assert_eq!((0, 2), lines("a\nb", 64));
+    }
+
+    #[test]
+    fn test_blank_lines_metrics() {
+        for (factory, expected) in [(blank_lines_metric(), 1), (nonblank_lines_metric(), 2)] {
+            let mut metric = factory.create();
+            metric.update(b"a\n\nb\n");
+            assert_eq!(MetricValue::Count(expected), metric.finalize());
+        }
+    }
+}
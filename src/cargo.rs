@@ -0,0 +1,194 @@
+//! `--cargo`, which counts the sources, tests, and benches of each crate in a cargo workspace.
+
+use crate::count::{CountOptions, CountablePath, Counts, Row};
+use crate::error::Error;
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Value;
+use walkdir::WalkDir;
+
+/// The directories of a crate which are counted, each reported as its own row.
+const GROUPS: [&str; 3] = ["src", "tests", "benches"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crate {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+fn read_manifest(dir: &Path) -> Result<Value, Error> {
+    let path = dir.join("Cargo.toml");
+    fs::read_to_string(&path)
+        .map_err(Error::from)
+        .and_then(|src| {
+            src.parse::<Value>()
+                .map_err(|err| Error::CUSTOM(err.to_string()))
+        })
+        .map_err(|err| Error::for_path(&path, err))
+}
+
+fn package_name(manifest: &Value) -> Option<String> {
+    manifest
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(String::from)
+}
+
+fn strings<'a>(workspace: Option<&'a Value>, key: &str) -> Vec<&'a str> {
+    workspace
+        .and_then(|ws| ws.get(key))
+        .and_then(Value::as_array)
+        .map(|vals| vals.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// The crates of the workspace or package whose manifest is in root, sorted by name.
+pub fn members(root: &Path) -> Result<Vec<Crate>, Error> {
+    let manifest = read_manifest(root)?;
+    let mut crates = Vec::new();
+    if let Some(name) = package_name(&manifest) {
+        crates.push(Crate {
+            name,
+            dir: root.to_path_buf(),
+        });
+    }
+
+    let workspace = manifest.get("workspace");
+    let exclude: Vec<PathBuf> = strings(workspace, "exclude")
+        .into_iter()
+        .map(|dir| root.join(dir))
+        .collect();
+    for pattern in strings(workspace, "members") {
+        let pattern = root.join(pattern);
+        let dirs = glob::glob(&pattern.to_string_lossy())
+            .map_err(|err| Error::CUSTOM(format!("invalid workspace member: {}", err)))?;
+        for dir in dirs {
+            let dir = dir.map_err(|err| Error::CUSTOM(err.to_string()))?;
+            if exclude.contains(&dir) || !dir.join("Cargo.toml").is_file() {
+                continue;
+            }
+            let name = package_name(&read_manifest(&dir)?).unwrap_or_else(|| {
+                dir.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+            crates.push(Crate { name, dir });
+        }
+    }
+    crates.sort_by(|a, b| a.name.cmp(&b.name));
+    crates.dedup();
+    Ok(crates)
+}
+
+/// The sum of the counts of every file under dir, the sum is only available for counts which are
+/// available for every file.
+fn count_dir(dir: &Path, opts: &CountOptions) -> Result<Counts, Error> {
+    let files: Vec<PathBuf> = WalkDir::new(dir)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    let (counts, errors): (Vec<_>, Vec<_>) = files
+        .par_iter()
+        .map(|path| {
+            path.count_with(opts.clone())
+                .map_err(|err| Error::for_path(path, err))
+        })
+        .partition(Result::is_ok);
+    if !errors.is_empty() {
+        return Err(errors
+            .into_iter()
+            .map(Result::unwrap_err)
+            .collect::<Vec<Error>>()
+            .into());
+    }
+    Ok(counts.into_iter().map(Result::unwrap).sum())
+}
+
+/// A row for each of the src, tests, and benches directories of each crate in the workspace at
+/// root, labelled like `name/src`.
+pub fn count_workspace(root: &Path, opts: &CountOptions) -> Result<Vec<Row>, Error> {
+    let mut rows = Vec::new();
+    for krate in members(root)? {
+        for group in &GROUPS {
+            let dir = krate.dir.join(group);
+            if dir.is_dir() {
+                rows.push((count_dir(&dir, opts), Path::new(&krate.name).join(group)));
+            }
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn write(path: PathBuf, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_count_workspace() {
+        let root = env::temp_dir().join(format!("rwc-cargo-{}", std::process::id()));
+        write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skipped\"]\n",
+        );
+        write(
+            root.join("crates/alpha/Cargo.toml"),
+            "[package]\nname = \"alpha\"\n",
+        );
+        write(root.join("crates/alpha/src/lib.rs"), "pub fn a() {}\n");
+        write(root.join("crates/alpha/src/util/mod.rs"), "fn b() {}\n\n");
+        write(
+            root.join("crates/alpha/tests/it.rs"),
+            "#[test]\nfn t() {}\n",
+        );
+        write(
+            root.join("crates/beta/Cargo.toml"),
+            "[package]\nname = \"beta\"\n",
+        );
+        write(root.join("crates/beta/benches/b.rs"), "fn main() {}\n");
+        write(
+            root.join("crates/skipped/Cargo.toml"),
+            "[package]\nname = \"skipped\"\n",
+        );
+        write(root.join("crates/skipped/src/lib.rs"), "\n");
+
+        let names: Vec<String> = members(&root)
+            .unwrap()
+            .into_iter()
+            .map(|krate| krate.name)
+            .collect();
+        assert_eq!(vec!["alpha", "beta"], names);
+
+        let opts = CountOptions::new().bytes().words().lines();
+        let rows: Vec<(Counts, PathBuf)> = count_workspace(&root, &opts)
+            .unwrap()
+            .into_iter()
+            .map(|(res, path)| (res.unwrap(), path))
+            .collect();
+        let paths: Vec<&Path> = rows.iter().map(|(_, path)| path.as_path()).collect();
+        assert_eq!(
+            vec![
+                Path::new("alpha/src"),
+                Path::new("alpha/tests"),
+                Path::new("beta/benches")
+            ],
+            paths
+        );
+        assert_eq!(Some(25), rows[0].0.bytes.val);
+        assert_eq!(Some(7), rows[0].0.words.val);
+        assert_eq!(Some(3), rows[0].0.lines.val);
+        assert_eq!(Some(2), rows[1].0.lines.val);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+//! `rwc chars --histogram`, how often each char or byte occurs across the inputs, for checking
+//! encodings and analysing corpora.
+
+use crate::cli::Options;
+use crate::console;
+use crate::count::BUFFER_SIZE;
+use crate::error::Error;
+use crate::format::Format;
+use crate::print::csv_record;
+use colored::*;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::str;
+use term_table::row::Row;
+use term_table::table_cell::{Alignment, TableCell};
+use term_table::Table;
+
+/// Occurrences of each char, or of each byte if bytes. Invalid utf-8 is counted as one U+FFFD
+/// replacement char per invalid sequence, like `--invalid-utf8 replace`.
+#[derive(Debug, Default)]
+pub struct Histogram {
+    bytes: bool,
+    /// Occurrences by code point or byte.
+    counts: BTreeMap<u32, u64>,
+    /// The start of a utf-8 sequence which may be completed by the next chunk.
+    carry: Vec<u8>,
+}
+
+impl Histogram {
+    pub fn new(bytes: bool) -> Histogram {
+        Histogram {
+            bytes,
+            ..Histogram::default()
+        }
+    }
+
+    fn add(&mut self, key: u32) {
+        *self.counts.entry(key).or_insert(0) += 1;
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        if self.bytes {
+            for &b in chunk {
+                self.add(u32::from(b));
+            }
+            return;
+        }
+        let mut input = std::mem::take(&mut self.carry);
+        input.extend_from_slice(chunk);
+        let mut rest = &input[..];
+        loop {
+            match str::from_utf8(rest) {
+                Ok(s) => {
+                    s.chars().for_each(|c| self.add(u32::from(c)));
+                    break;
+                }
+                Err(err) => {
+                    let (valid, after) = rest.split_at(err.valid_up_to());
+                    let valid = str::from_utf8(valid).unwrap();
+                    valid.chars().for_each(|c| self.add(u32::from(c)));
+                    match err.error_len() {
+                        Some(n) => {
+                            self.add(u32::from(char::REPLACEMENT_CHARACTER));
+                            rest = &after[n..];
+                        }
+                        None => {
+                            self.carry = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Count a sequence left incomplete by the end of the input.
+    pub fn finish(&mut self) {
+        if !std::mem::take(&mut self.carry).is_empty() {
+            self.add(u32::from(char::REPLACEMENT_CHARACTER));
+        }
+    }
+
+    fn count_reader<R: Read>(&mut self, mut r: R) -> Result<(), Error> {
+        let mut buf = vec![0; BUFFER_SIZE];
+        loop {
+            match r.read(&mut buf)? {
+                0 => break,
+                n => self.update(&buf[..n]),
+            }
+        }
+        self.finish();
+        Ok(())
+    }
+
+    /// The rows of the histogram, most frequent first.
+    fn rows(&self) -> Vec<Entry> {
+        let mut rows: Vec<Entry> = self
+            .counts
+            .iter()
+            .map(|(&key, &count)| Entry {
+                key,
+                bytes: self.bytes,
+                count,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.count.cmp(&a.count).then(a.key.cmp(&b.key)));
+        rows
+    }
+}
+
+/// A row of the histogram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    key: u32,
+    bytes: bool,
+    count: u64,
+}
+
+impl Entry {
+    /// The char or byte escaped like a rust literal, so whitespace and control chars are
+    /// visible.
+    fn symbol(&self) -> String {
+        if self.bytes {
+            std::ascii::escape_default(self.key as u8).to_string()
+        } else {
+            char::from_u32(self.key)
+                .map(|c| c.escape_debug().to_string())
+                .unwrap_or_default()
+        }
+    }
+
+    fn code(&self) -> String {
+        if self.bytes {
+            format!("0x{:02X}", self.key)
+        } else {
+            format!("U+{:04X}", self.key)
+        }
+    }
+}
+
+/// Count the chars, or bytes if bytes, of paths or of stdin if there are none.
+pub fn histogram<R: Read>(paths: &[PathBuf], bytes: bool, stdin: R) -> Result<Histogram, Error> {
+    let mut histogram = Histogram::new(bytes);
+    if paths.is_empty() {
+        histogram.count_reader(stdin)?;
+    }
+    for path in paths {
+        File::open(path)
+            .map_err(Error::from)
+            .and_then(|file| histogram.count_reader(file))
+            .map_err(|err| Error::for_path(path, err))?;
+    }
+    Ok(histogram)
+}
+
+/// Print the histogram of paths, or of stdin if there are none, as a table, csv, or json.
+pub fn run<R: Read, W: Write>(
+    paths: &[PathBuf],
+    bytes: bool,
+    stdin: R,
+    opts: &Options,
+    fmt: Format,
+    mut w: W,
+) -> Result<bool, Error> {
+    let rows = histogram(paths, bytes, stdin)?.rows();
+    let name = if bytes { "byte" } else { "char" };
+    match fmt.name() {
+        Some("table") => {
+            fn make_cell<'a, T: fmt::Display>(data: &T) -> TableCell<'a> {
+                TableCell::new_with_alignment_and_padding(data, 1, Alignment::Left, true)
+            }
+
+            let mut table = Table::new();
+            table.style = console::table_style();
+            table.add_row(Row::new(
+                [name, "code", "count"]
+                    .iter()
+                    .map(|header| make_cell(&header.blue().bold()))
+                    .collect::<Vec<_>>(),
+            ));
+            for row in &rows {
+                table.add_row(Row::new(vec![
+                    make_cell(&row.symbol().bold()),
+                    make_cell(&row.code()),
+                    make_cell(&row.count),
+                ]));
+            }
+            write!(w, "{}", table.render())?;
+        }
+        Some("csv") => {
+            let mut records = vec![csv_record(&[name, "code", "count"], opts)];
+            records.extend(
+                rows.iter().map(|row| {
+                    csv_record(&[row.symbol(), row.code(), row.count.to_string()], opts)
+                }),
+            );
+            write!(w, "{}", records.join("\n"))?;
+        }
+        Some("json") => {
+            let objects: Vec<Value> = rows
+                .iter()
+                .map(|row| {
+                    let mut object = Map::new();
+                    if !bytes {
+                        let c = char::from_u32(row.key).map(String::from);
+                        object.insert(String::from("char"), json!(c));
+                    }
+                    object.insert(
+                        String::from(if bytes { "byte" } else { "codepoint" }),
+                        json!(row.key),
+                    );
+                    object.insert(String::from("count"), json!(row.count));
+                    Value::Object(object)
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut w, &objects)
+                .map_err(|err| Error::CUSTOM(err.to_string()))?;
+        }
+        _ => {
+            return Err(Error::CUSTOM(String::from(
+                "chars can only write --format table, csv, or json",
+            )))
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(input: &[u8], bytes: bool, chunk: usize) -> Vec<(String, String, u64)> {
+        let mut histogram = Histogram::new(bytes);
+        for chunk in input.chunks(chunk) {
+            histogram.update(chunk);
+        }
+        histogram.finish();
+        histogram
+            .rows()
+            .iter()
+            .map(|row| (row.symbol(), row.code(), row.count))
+            .collect()
+    }
+
+    #[test]
+    fn test_histogram() {
+        let input = "aé\nbaé東\n".as_bytes();
+        for chunk in 1..input.len() {
+            assert_eq!(
+                vec![
+                    (String::from("\\n"), String::from("U+000A"), 2),
+                    (String::from("a"), String::from("U+0061"), 2),
+                    (String::from("é"), String::from("U+00E9"), 2),
+                    (String::from("b"), String::from("U+0062"), 1),
+                    (String::from("東"), String::from("U+6771"), 1),
+                ],
+                rows(input, false, chunk)
+            );
+        }
+        assert_eq!(
+            vec![
+                (String::from("\u{fffd}"), String::from("U+FFFD"), 2),
+                (String::from("a"), String::from("U+0061"), 1),
+            ],
+            rows(b"\xffa\xe6\x9d", false, 64)
+        );
+        assert_eq!(
+            vec![
+                (String::from("\\xa9"), String::from("0xA9"), 1),
+                (String::from("\\xc3"), String::from("0xC3"), 1),
+            ],
+            rows("é".as_bytes(), true, 64)
+        );
+    }
+}
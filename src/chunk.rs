@@ -0,0 +1,139 @@
+//! `--chunk-lines` and `--chunk-bytes`, which count each chunk of a file as its own row so skew
+//! within one big file is visible.
+
+use crate::count::{CountOptions, Countable, Row};
+use crate::error::Error;
+use crate::format::parse_size;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chunk {
+    /// Chunks of this many lines, split after `\n` whatever `--eol` is.
+    Lines(u64),
+    Bytes(u64),
+}
+
+/// A positive number of lines.
+pub fn parse_chunk_lines(src: &str) -> Result<u64, Error> {
+    match src.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --chunk-lines {}, expected a positive number of lines",
+            src
+        ))),
+    }
+}
+
+/// A positive size like `64M`.
+pub fn parse_chunk_bytes(src: &str) -> Result<u64, Error> {
+    match parse_size(src) {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --chunk-bytes {}, expected a positive size like 64M",
+            src
+        ))),
+    }
+}
+
+/// Reads from inner up to and including its lines'th `\n`.
+struct LinesChunk<'a, R> {
+    inner: &'a mut R,
+    lines: u64,
+}
+
+impl<R: BufRead> Read for LinesChunk<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.lines == 0 {
+            return Ok(0);
+        }
+        let available = self.inner.fill_buf()?;
+        let mut n = available.len().min(buf.len());
+        for (i, &b) in available[..n].iter().enumerate() {
+            if b == b'\n' {
+                self.lines -= 1;
+                if self.lines == 0 {
+                    n = i + 1;
+                    break;
+                }
+            }
+        }
+        buf[..n].copy_from_slice(&available[..n]);
+        self.inner.consume(n);
+        Ok(n)
+    }
+}
+
+/// The counts of each chunk of path, named `<path>:<n>` counting from 1. An empty file is one
+/// empty chunk. Counting stops at the first chunk which fails.
+pub fn count_chunks(path: &Path, chunk: Chunk, opts: CountOptions) -> Result<Vec<Row>, Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut rows = Vec::new();
+    for n in 1.. {
+        if n > 1 && reader.fill_buf()?.is_empty() {
+            break;
+        }
+        let res = match chunk {
+            Chunk::Lines(lines) => LinesChunk {
+                inner: &mut reader,
+                lines,
+            }
+            .count_with(opts.clone()),
+            Chunk::Bytes(bytes) => (&mut reader).take(bytes).count_with(opts.clone()),
+        };
+        let failed = res.is_err();
+        rows.push((res, PathBuf::from(format!("{}:{}", path.display(), n))));
+        if failed {
+            break;
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(rows: &[Row]) -> Vec<(u64, String)> {
+        rows.iter()
+            .map(|(res, path)| {
+                let lines = res.as_ref().unwrap().lines.val.unwrap();
+                (lines, path.display().to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_chunks() {
+        assert_eq!(1000, parse_chunk_lines("1000").unwrap());
+        assert!(parse_chunk_lines("0").is_err());
+        assert_eq!(64 << 20, parse_chunk_bytes("64M").unwrap());
+        assert!(parse_chunk_bytes("0K").is_err());
+    }
+
+    #[test]
+    fn test_count_chunks() {
+        let path = Path::new("test_data/default.txt");
+        let opts = CountOptions::new().lines();
+        let rows = count_chunks(path, Chunk::Lines(8000), opts.clone()).unwrap();
+        assert_eq!(
+            vec![
+                (8000, String::from("test_data/default.txt:1")),
+                (8000, String::from("test_data/default.txt:2")),
+                (4681, String::from("test_data/default.txt:3")),
+            ],
+            lines(&rows)
+        );
+
+        let rows = count_chunks(path, Chunk::Bytes(1 << 19), opts).unwrap();
+        assert_eq!(3, rows.len());
+        let bytes: u64 = rows
+            .iter()
+            .map(|(res, _)| res.as_ref().unwrap().bytes.val.unwrap())
+            .sum();
+        assert_eq!(1048697, bytes);
+        let lines: u64 = lines(&rows).iter().map(|(lines, _)| lines).sum();
+        assert_eq!(20681, lines);
+    }
+}
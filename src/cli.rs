@@ -1,5 +1,49 @@
-use crate::format::{parse_format, Format};
+use crate::blank::{blank_lines_metric, nonblank_lines_metric};
+use crate::chunk::{parse_chunk_bytes, parse_chunk_lines, Chunk};
+use crate::code::{code_metrics, Syntax};
+use crate::count::{
+    parse_count_kind, parse_eol, parse_invalid_utf8, parse_record_delim, CountKind, CountOptions,
+    Eol, InvalidUtf8,
+};
+use crate::detect_lang::detect_lang_metric;
+use crate::endings::line_endings_metrics;
+use crate::entropy::entropy_metric;
+use crate::error::Error;
+use crate::fdlimit::default_max_open;
+use crate::format::{
+    parse_delimiter, parse_format, parse_size, parse_template, Format, Formats, Template,
+};
+use crate::goal::{parse_goal, Goal};
+use crate::graphemes::graphemes_metric;
+use crate::indent::{indent_histogram_metric, indent_stats_metrics};
+use crate::linelen::line_stats_metrics;
+use crate::markdown::excluded_words_metric;
+use crate::matches::{
+    count_lines_matching_metric, count_matches_metrics, parse_count_lines_matching,
+    parse_count_matches,
+};
+use crate::metric::MetricFactory;
+use crate::nonascii::non_ascii_metric;
+use crate::output::{parse_tee, Tee};
+use crate::perline::words_per_line_metric;
+use crate::preset::{parse_preset, preset_metric, Preset};
+use crate::prose::{prose_stats_metrics, readability_metrics};
+use crate::sink::{parse_log_target, LogTarget};
+use crate::sort::{parse_sort, Sort};
+use crate::timeout::parse_timeout;
+use crate::tokenize::{
+    parse_tokenizer, parse_word_pattern, AsciiWhitespace, Tokenizer, UnicodeWords,
+};
+use crate::trailing::trailing_ws_metric;
+use crate::unique::unique_words_metric;
+use crate::urls::count_urls_metric;
+use crate::wordlen::{avg_word_length_metric, word_length_histogram_metric};
+use regex::bytes::Regex;
+use std::ffi::OsString;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use structopt::clap;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -11,30 +55,593 @@ pub struct Cli {
     #[structopt(short, long, help = "Print utf-8 character counts.")]
     pub chars: bool,
 
+    #[structopt(
+        long,
+        default_value = "strict",
+        parse(try_from_str = parse_invalid_utf8),
+        help = "How --chars handles invalid utf-8: strict fails, replace counts each invalid sequence as one U+FFFD char, and skip doesn't count invalid sequences."
+    )]
+    pub invalid_utf8: InvalidUtf8,
+
+    #[structopt(
+        long,
+        default_value = "lf",
+        parse(try_from_str = parse_eol),
+        help = "What terminates a line: lf like wc (so crlf is one line too), only crlf, only cr as in classic Mac OS files, or any of them."
+    )]
+    pub eol: Eol,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_record_delim),
+        help = "Count records terminated by <record-delim> as lines, overriding --eol. <record-delim> is one ascii char like ; or an escape like \\0, \\r, or \\x1e."
+    )]
+    pub record_delim: Option<Eol>,
+
+    #[structopt(
+        short = "z",
+        long,
+        conflicts_with = "record-delim",
+        help = "Count NUL terminated records as lines, like the output of find -print0, overriding --eol. Short for --record-delim \\0."
+    )]
+    pub zero_terminated: bool,
+
+    #[structopt(
+        long,
+        help = "Count a last line which doesn't end with a line terminator, which wc doesn't."
+    )]
+    pub count_last_line: bool,
+
+    #[structopt(
+        long,
+        help = "Read files to count their bytes instead of using the size from their metadata, which is 0 for files in /proc and some virtual or network filesystems (rwc reads those anyway)."
+    )]
+    pub no_stat_fastpath: bool,
+
+    #[structopt(
+        long,
+        help = "Print the size of each file from its metadata, which for a sparse file includes its holes."
+    )]
+    pub apparent_size: bool,
+
+    #[structopt(
+        long,
+        help = "Print the bytes allocated on disk for each file, less than its apparent size for a sparse file."
+    )]
+    pub disk_usage: bool,
+
     #[structopt(
         short,
         long,
-        help = "Print word counts. A word is a non-zero-length sequence of non-whitespace characters delimited by ascii whitespace."
+        help = "Print word counts. By default a word is a non-zero-length sequence of non-whitespace characters delimited by ascii whitespace, see --tokenizer."
     )]
     pub words: bool,
 
+    #[structopt(
+        long,
+        parse(try_from_str = parse_tokenizer),
+        help = "How to split words: ascii-whitespace (the default), unicode-whitespace (also split on no-break, em, ideographic and other Unicode spaces), unicode-words (Unicode word boundaries), or regex:<pattern> (each match of <pattern> is a word). Words never span lines."
+    )]
+    pub tokenizer: Option<Arc<dyn Tokenizer>>,
+
+    #[structopt(
+        long,
+        conflicts_with = "tokenizer",
+        parse(try_from_str = parse_word_pattern),
+        help = "Print word counts where a word is each match of the regex <word-pattern>, e.g. \\w+ or an IP address. Short for --words --tokenizer regex:<word-pattern>."
+    )]
+    pub word_pattern: Option<Arc<dyn Tokenizer>>,
+
+    #[structopt(
+        long,
+        conflicts_with_all = &["tokenizer", "word-pattern"],
+        help = "Print word counts split on Unicode word boundaries (UAX #29), so text in languages without spaces like Chinese or Japanese has meaningful counts. Short for --words --tokenizer unicode-words."
+    )]
+    pub words_unicode: bool,
+
+    #[structopt(
+        long,
+        help = "Leave front matter and fenced code blocks out of the word counts of .md and .markdown files."
+    )]
+    pub markdown_aware: bool,
+
+    #[structopt(
+        long,
+        requires = "markdown-aware",
+        help = "With --markdown-aware, add an excluded words column with the words left out of each markdown file's word count."
+    )]
+    pub markdown_excluded_words: bool,
+
+    #[structopt(
+        long,
+        help = "Add an avg word length column with the mean chars per word of each file, words split like --words. High for minified code or base64."
+    )]
+    pub avg_word_length: bool,
+
+    #[structopt(
+        long,
+        help = "Add a word lengths column with the number of words of each length in chars of each file, like 1:40 2:120 3:95, words split like --words. With --show-totals the totals row has the distribution over all files."
+    )]
+    pub word_length_histogram: bool,
+
+    #[structopt(
+        long,
+        help = "Add a unique words column with the number of distinct words of each file, words split on ascii whitespace and compared case sensitively. With --show-totals the totals row has the words distinct across all files."
+    )]
+    pub unique_words: bool,
+
+    #[structopt(
+        long,
+        help = "Add a words/line column with the words of each file divided by its lines, words split like --words. The totals row divides the total words by the total lines."
+    )]
+    pub words_per_line: bool,
+
+    #[structopt(
+        long,
+        help = "Add an entropy column with the Shannon entropy of the bytes of each file in bits per byte, from 0 to 8. Close to 8 for compressed or encrypted files."
+    )]
+    pub entropy: bool,
+
+    #[structopt(
+        long,
+        help = "Add an indent histogram column with the number of non-blank lines at each indentation depth of each file, like 0:120 1:40 2:8. A depth is a multiple of the smallest indent in the file, tabs indent to the next multiple of 4 columns."
+    )]
+    pub indent_histogram: bool,
+
+    #[structopt(
+        long,
+        help = "Add tab indented and space indented columns with the number of lines of each file indented with tabs or only spaces, and an indent width column with the most common increase in indent in columns, tabs indenting to the next multiple of 4."
+    )]
+    pub indent_stats: bool,
+
+    #[structopt(
+        long,
+        help = "Add a language column with the natural language detected in the first 64KiB of each file as an ISO 639-3 code like eng, with a ? if the detection isn't reliable."
+    )]
+    pub detect_lang: bool,
+
+    #[structopt(
+        long,
+        help = "Add a urls column with the number of http:// and https:// URLs in each file."
+    )]
+    pub count_urls: bool,
+
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(try_from_str = parse_preset),
+        help = "Add a column counting a common pattern in each file, one of emails, ipv4 (dotted addresses), uuids, or urls (like --count-urls). Repeat for more columns."
+    )]
+    pub count_preset: Vec<Preset>,
+
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(try_from_str = parse_count_matches),
+        help = "Add a column named after the regex <count-matches> with the number of its matches in each file, e.g. ERROR|FATAL. Matches never span lines, like grep -o. Named groups like (?P<err>ERROR)|(?P<warn>WARN) add a column per group instead. Repeat for more columns."
+    )]
+    pub count_matches: Vec<Regex>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_count_lines_matching),
+        help = "Add a matching lines column with the number of lines of each file matching the regex <count-lines-matching>, like grep -c."
+    )]
+    pub count_lines_matching: Option<Regex>,
+
+    #[structopt(
+        long,
+        help = "Add sentences/paragraph and words/sentence columns for each file. Paragraphs are separated by blank lines and a sentence ends with a word ending in . ! or ?, or with its paragraph."
+    )]
+    pub prose_stats: bool,
+
+    #[structopt(
+        long,
+        help = "Add reading ease and grade level columns with the Flesch reading ease and Flesch-Kincaid grade level of each file, from sentences split like --prose-stats and estimated syllables."
+    )]
+    pub readability: bool,
+
+    #[structopt(
+        long,
+        help = "Add min, mean, and median line length columns for each file, in chars without the line terminator, lines ending like --eol. The totals row has the stats of all lines. Useful for style limits and spotting minified files."
+    )]
+    pub line_stats: bool,
+
+    #[structopt(
+        long,
+        help = "Add a blank lines column with the lines of each file which are empty or only whitespace."
+    )]
+    pub blank_lines: bool,
+
+    #[structopt(
+        long,
+        help = "Add a nonblank lines column with the lines of each file which have at least one non-whitespace character, the lines of actual content."
+    )]
+    pub nonblank_lines: bool,
+
+    #[structopt(
+        long,
+        help = "Add code lines, comment lines, and blank lines columns for each file. Comments are recognized by the syntax of the language of the file, detected by its extension like `rwc langs`."
+    )]
+    pub code: bool,
+
+    #[structopt(
+        long,
+        help = "Add lf, crlf, and cr columns with the number of each line ending in each file, a crlf is only counted as a crlf. Files with more than one are mixed."
+    )]
+    pub line_endings: bool,
+
+    #[structopt(
+        long,
+        help = "Add a trailing ws column with the number of lines of each file ending with spaces or tabs."
+    )]
+    pub trailing_ws: bool,
+
+    #[structopt(
+        long,
+        help = "Add a non-ascii column with the number of chars above U+007F in each file, to find them in sources which should be ascii only."
+    )]
+    pub non_ascii: bool,
+
+    #[structopt(
+        long,
+        help = "Add a graphemes column with the user-perceived chars of each file, like e with a combining accent or an emoji with a skin tone, as extended grapheme clusters."
+    )]
+    pub graphemes: bool,
+
     #[structopt(short, long, help = "Print newline counts.")]
     pub lines: bool,
 
+    #[structopt(
+        short = "L",
+        long,
+        alias = "max-display-width",
+        help = "Print the display width of the longest line like wc -L, with wide chars 2 columns and tabs expanded to the next multiple of 8. The totals row has the longest of all."
+    )]
+    pub max_line_length: bool,
+
+    #[structopt(
+        long,
+        help = "Treat <files> (or the current directory) as cargo workspaces and print a row for the src, tests, and benches of each crate."
+    )]
+    pub cargo: bool,
+
+    #[structopt(
+        long,
+        conflicts_with_all = &["cargo", "apparent-size", "disk-usage"],
+        help = "Attribute the lines of <files>, which have to be tracked by git, to the authors who last changed them according to git blame, and print a row per author with the counts of their lines across all files."
+    )]
+    pub git_blame: bool,
+
     #[structopt(long, help = "Include an extra row showing count totals.")]
     pub show_totals: bool,
 
-    #[structopt(long, default_value = "table", parse(try_from_str = parse_format), help = "TODO")]
+    #[structopt(
+        long,
+        help = "Print a single compact line like 12,345w 1,234l for standard input without a table, header, or colors. Meant for editor statuslines."
+    )]
+    pub statusline: bool,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_log_target),
+        help = "Also emit one structured record per counted file to <log-to>, either syslog or journald."
+    )]
+    pub log_to: Option<LogTarget>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_count_kind),
+        help = "Instead of the table, print a shields.io endpoint badge (https://shields.io/badges/endpoint-badge) of the total <badge> of <files>, one of bytes, chars, words, or lines."
+    )]
+    pub badge: Option<CountKind>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_goal),
+        help = "After the results, print the progress of the total towards <goal>, a count and target like words=50000."
+    )]
+    pub goal: Option<Goal>,
+
+    #[structopt(
+        long,
+        requires = "goal",
+        help = "Apply --goal to every file instead of the total."
+    )]
+    pub goal_per_file: bool,
+
+    #[structopt(
+        long,
+        help = "Also copy the output, without colors, to the system clipboard."
+    )]
+    pub clipboard: bool,
+
+    #[structopt(
+        long,
+        default_value = "table",
+        parse(from_str = parse_format),
+        help = "The output format: table, csv, json, xml, wc (the layout of GNU wc), prometheus, org, latex, or, when built with their features, arrow, parquet, or proto. --format-template and --print0 replace it with their own layouts."
+    )]
     pub format: Format,
 
+    #[structopt(
+        long,
+        parse(try_from_str = parse_template),
+        help = "Print a line per file from a template like \"{path}: {lines} lines, {words} words\" instead of a table. The placeholders are {path}, {bytes}, {chars}, {words}, and {lines}, which are counted without asking for them, and {{ and }} are literal braces."
+    )]
+    pub format_template: Option<Template>,
+
+    #[structopt(
+        long,
+        conflicts_with = "format-template",
+        help = "Print the csv columns and an error column as fields terminated by NUL instead of a table, so paths with newlines or commas round-trip. Every record has as many fields as the header."
+    )]
+    pub print0: bool,
+
+    #[structopt(
+        long,
+        help = "Don't quote csv fields containing commas, quotes, or line breaks, for scripts which split on commas like before."
+    )]
+    pub no_csv_quote: bool,
+
+    #[structopt(
+        long,
+        default_value = ",",
+        parse(try_from_str = parse_delimiter),
+        help = "The character separating csv fields, like ; for spreadsheets in locales with decimal commas, or \\t for tabs."
+    )]
+    pub delimiter: char,
+
+    #[structopt(
+        long,
+        default_value = "path",
+        parse(try_from_str = parse_sort),
+        help = "The order of the rows: path sorts by byte order of each path component, regardless of locale, and path-natural compares runs of digits by value so file2 comes before file10."
+    )]
+    pub sort: Sort,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_timeout),
+        help = "Give up on a file which isn't counted within <timeout> seconds, e.g. a FIFO without a writer or a dead network mount, and report it as an error."
+    )]
+    pub timeout: Option<Duration>,
+
+    #[structopt(
+        long,
+        help = "The most files to have open at once. Defaults to the soft limit of open files (ulimit -n) less a few for stdio and sockets."
+    )]
+    pub max_open: Option<usize>,
+
+    #[structopt(
+        long,
+        conflicts_with_all = &["chunk-bytes", "apparent-size", "disk-usage", "cargo", "git-blame"],
+        parse(try_from_str = parse_chunk_lines),
+        help = "Print a row for each chunk of <chunk-lines> lines of each file, named <path>:<n> counting from 1."
+    )]
+    pub chunk_lines: Option<u64>,
+
+    #[structopt(
+        long,
+        conflicts_with_all = &["apparent-size", "disk-usage", "cargo", "git-blame"],
+        parse(try_from_str = parse_chunk_bytes),
+        help = "Print a row for each chunk of <chunk-bytes> bytes of each file, e.g. 64M, named <path>:<n> counting from 1."
+    )]
+    pub chunk_bytes: Option<u64>,
+
     #[structopt(
         long,
         help = "Read input from the files specified by null separated paths in <files0_from>. If <files0_from> is - then read \\n separated paths from standard input."
     )]
     pub files0_from: Option<PathBuf>,
 
+    #[structopt(
+        long,
+        conflicts_with_all = &["files0-from", "cargo", "git-blame", "statusline"],
+        help = "Count the paths read from standard input, one per line, printing a csv row for each as soon as it's counted. The directive :totals prints a totals row for the paths since the start or the last :reset."
+    )]
+    pub batch: bool,
+
+    #[structopt(
+        long,
+        requires = "batch",
+        help = "With --batch, read null separated paths and directives instead."
+    )]
+    pub null_data: bool,
+
+    #[cfg(unix)]
+    #[structopt(
+        long,
+        help = "Print the counts of <ask> from the running rwc daemon instead of reading it. The daemon's socket is $RWC_SOCKET or $XDG_RUNTIME_DIR/rwc.sock."
+    )]
+    pub ask: Option<PathBuf>,
+
+    #[cfg(feature = "grpc")]
+    #[structopt(
+        long,
+        help = "Serve the Counter gRPC service (see proto/rwc.proto) on <grpc-listen> instead of counting files."
+    )]
+    pub grpc_listen: Option<std::net::SocketAddr>,
+
+    #[cfg(feature = "otel")]
+    #[structopt(
+        long,
+        help = "Export run metrics (files counted, bytes processed, errors, and duration) to the OTLP/gRPC collector at <otel-endpoint>, e.g. http://localhost:4317."
+    )]
+    pub otel_endpoint: Option<String>,
+
+    #[cfg(feature = "sqlite")]
+    #[structopt(
+        long,
+        help = "Append the counts of each file, with the time of the run, to the counts table of the SQLite database <output-db>, creating it if needed."
+    )]
+    pub output_db: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Write the results to <output> instead of standard output, replacing it only once they're complete so a failed run never leaves a truncated file."
+    )]
+    pub output: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(try_from_str = parse_tee),
+        help = "Also write the results to a file in another format, like csv=report.csv, replacing it once they're complete. Can be repeated, and the table is still printed as usual."
+    )]
+    pub tee: Vec<Tee>,
+
     #[structopt(help = "Files to read. If no paths are provided then read standard input.")]
     pub files: Vec<PathBuf>,
+
+    #[structopt(subcommand)]
+    pub cmd: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    #[structopt(
+        about = "Pre-commit size gate. Counts the staged contents of every added or modified file and exits non-zero if any of them exceed the limits."
+    )]
+    Hook {
+        #[structopt(long, help = "Maximum number of lines in a staged file.")]
+        max_lines: Option<u64>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_size),
+            help = "Maximum number of bytes in a staged file, optionally with a K, M, or G suffix."
+        )]
+        max_bytes: Option<u64>,
+    },
+
+    #[structopt(
+        about = "Count <files> with each of rwc's counters, and optionally the system wc, and report any counts which differ."
+    )]
+    Verify {
+        #[structopt(long, help = "Also compare against the counts from <wc-bin>.")]
+        with_wc: bool,
+
+        #[structopt(long, default_value = "wc", help = "The wc binary used by --with-wc.")]
+        wc_bin: String,
+
+        #[structopt(required = true, help = "Files to verify.")]
+        files: Vec<PathBuf>,
+    },
+
+    #[structopt(about = "Record counts of files over time and show how they changed.")]
+    History {
+        #[structopt(
+            long,
+            help = "The jsonl file storing the history. Defaults to $XDG_DATA_HOME/rwc/history.jsonl."
+        )]
+        store: Option<PathBuf>,
+
+        #[structopt(subcommand)]
+        cmd: HistoryCommand,
+    },
+
+    #[structopt(
+        about = "Count the files under <dir-a> and <dir-b> and print how the counts of each relative path differ from a to b, including files only present on one side. Exits non-zero if anything differs."
+    )]
+    Compare {
+        #[structopt(help = "The old tree.")]
+        dir_a: PathBuf,
+
+        #[structopt(help = "The new tree.")]
+        dir_b: PathBuf,
+    },
+
+    #[structopt(about = "Save the counts of files under a name and diff against them later.")]
+    Snapshot {
+        #[structopt(
+            long,
+            help = "The directory storing snapshots. Defaults to $XDG_DATA_HOME/rwc/snapshots."
+        )]
+        dir: Option<PathBuf>,
+
+        #[structopt(subcommand)]
+        cmd: SnapshotCommand,
+    },
+
+    #[structopt(
+        about = "Print the summed counts of the files of each language under <dirs>, recognized by extension. Hidden files and directories are skipped."
+    )]
+    Langs {
+        #[structopt(help = "Directories to scan, the current directory if none are given.")]
+        dirs: Vec<PathBuf>,
+    },
+
+    #[structopt(
+        about = "Print how often each char occurs across <files>, most frequent first. Only --format table, csv, and json are supported."
+    )]
+    Chars {
+        #[structopt(long, required = true, help = "Print a frequency histogram.")]
+        histogram: bool,
+
+        #[structopt(
+            long,
+            help = "Count bytes rather than chars, e.g. to check an encoding. Otherwise invalid utf-8 is counted as U+FFFD."
+        )]
+        bytes: bool,
+
+        #[structopt(help = "Files to read, stdin if none are given.")]
+        files: Vec<PathBuf>,
+    },
+
+    #[cfg(unix)]
+    #[structopt(
+        about = "Answer rwc --ask queries over a unix socket, caching counts until files change."
+    )]
+    Daemon {
+        #[structopt(
+            long,
+            help = "The socket to listen on. Defaults to $RWC_SOCKET or $XDG_RUNTIME_DIR/rwc.sock."
+        )]
+        socket: Option<PathBuf>,
+    },
+
+    // without an external subcommand clap rejects files like hooks as typos of subcommands, any
+    // arguments it takes this way are files and moved back to them by Cli::with_external_files
+    #[structopt(external_subcommand)]
+    Files(Vec<OsString>),
+}
+
+#[derive(StructOpt, Debug)]
+pub enum SnapshotCommand {
+    #[structopt(
+        about = "Count every file under <paths> and save the counts as <name>, replacing any snapshot named <name>."
+    )]
+    Save {
+        name: String,
+
+        #[structopt(
+            help = "Files and directories to count, the current directory if none are given."
+        )]
+        paths: Vec<PathBuf>,
+    },
+
+    #[structopt(
+        about = "Recount the paths saved in <name> and print how each file's counts changed since. Exits non-zero if anything changed."
+    )]
+    Diff { name: String },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum HistoryCommand {
+    #[structopt(about = "Append the current counts of <files> to the history.")]
+    Record {
+        #[structopt(required = true, help = "Files to record.")]
+        files: Vec<PathBuf>,
+    },
+
+    #[structopt(about = "Show the recorded counts of each file matching <pattern>, oldest first.")]
+    Show {
+        #[structopt(
+            help = "A path or glob, matched against absolute paths and paths relative to the current directory."
+        )]
+        pattern: String,
+    },
 }
 
 /// Just the opts passed from the command-line not including the paths. This is because we want
@@ -45,28 +652,275 @@ pub struct Options {
     pub chars: bool,
     pub words: bool,
     pub lines: bool,
+    pub max_line_length: bool,
     pub show_totals: bool,
+    /// Whether the only row is standard input rather than files, which `--format wc` prints
+    /// without a name like GNU wc.
+    pub stdin: bool,
+    pub statusline: bool,
+    pub cargo: bool,
+    pub git_blame: bool,
+    pub markdown_aware: bool,
+    pub log_to: Option<LogTarget>,
+    pub clipboard: bool,
+    pub badge: Option<CountKind>,
+    pub goal: Option<Goal>,
+    pub csv_quote: bool,
+    pub csv_delimiter: char,
+    #[cfg(feature = "otel")]
+    pub otel_endpoint: Option<String>,
+    #[cfg(feature = "sqlite")]
+    pub output_db: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub tee: Vec<Tee>,
+    pub tokenizer: Option<Arc<dyn Tokenizer>>,
+    pub invalid_utf8: InvalidUtf8,
+    pub eol: Eol,
+    pub count_last_line: bool,
+    pub no_stat_fastpath: bool,
+    pub apparent_size: bool,
+    pub disk_usage: bool,
+    pub sort: Sort,
+    pub timeout: Option<Duration>,
+    pub max_open: Option<usize>,
+    pub chunk: Option<Chunk>,
+    /// Additional metrics, each printed as its own column after the built-in counts.
+    pub metrics: Vec<MetricFactory>,
+}
+
+impl Cli {
+    /// Parse iter like `Cli::from_iter`, with `--format` and `--tee` accepting the names in
+    /// formats, and exit with clap's message if it can't be.
+    pub fn from_iter_with_formats<I>(iter: I, formats: &Formats) -> Cli
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        Cli::from_iter_safe_with_formats(iter, formats).unwrap_or_else(|err| err.exit())
+    }
+
+    /// Parse iter like `Cli::from_iter_safe`, with `--format` and `--tee` accepting the names in
+    /// formats.
+    pub fn from_iter_safe_with_formats<I>(iter: I, formats: &Formats) -> Result<Cli, clap::Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        let invalid = |err: Error| {
+            clap::Error::with_description(&err.plain_report(), clap::ErrorKind::InvalidValue)
+        };
+        let mut cli = Cli::from_iter_safe(iter)?;
+        cli.format = cli.format.resolve(formats).map_err(invalid)?;
+        for tee in &mut cli.tee {
+            tee.format = tee.format.clone().resolve(formats).map_err(invalid)?;
+        }
+        Ok(cli)
+    }
+
+    /// Count the arguments clap took for an unknown subcommand as files, so only the exact name
+    /// of a subcommand runs one, and a file named like one can be counted with `rwc -- hook`.
+    pub fn with_external_files(mut self) -> Cli {
+        if let Some(Command::Files(args)) = &self.cmd {
+            self.files.extend(args.iter().map(PathBuf::from));
+            self.cmd = None;
+        }
+        self
+    }
 }
 
 impl From<&Cli> for Options {
     /// Sets up some default values
     fn from(cli: &Cli) -> Options {
-        if !(cli.bytes || cli.chars || cli.words || cli.lines) {
-            Options {
-                bytes: true,
-                chars: false,
-                words: true,
-                lines: true,
-                show_totals: cli.show_totals,
-            }
-        } else {
-            Options {
-                bytes: cli.bytes,
-                chars: cli.chars,
-                words: cli.words,
-                lines: cli.lines,
-                show_totals: cli.show_totals,
-            }
+        // bytes, words, and lines are printed if no counts are asked for
+        let words = cli.words || cli.word_pattern.is_some() || cli.words_unicode;
+        let default = !(cli.bytes
+            || cli.chars
+            || words
+            || cli.lines
+            || cli.max_line_length
+            || cli.badge.is_some()
+            || cli.format_template.is_some());
+        // the counts needed by --badge, --goal, and --format-template are always computed
+        let needed = |kind| {
+            cli.badge == Some(kind)
+                || cli.goal.map(|goal| goal.kind) == Some(kind)
+                || cli.format_template.as_ref().is_some_and(|t| t.uses(kind))
+        };
+        Options {
+            bytes: default || cli.bytes || needed(CountKind::Bytes),
+            chars: cli.chars || needed(CountKind::Chars),
+            words: default || words || needed(CountKind::Words),
+            lines: default || cli.lines || needed(CountKind::Lines),
+            max_line_length: cli.max_line_length,
+            show_totals: cli.show_totals,
+            stdin: false,
+            statusline: cli.statusline,
+            cargo: cli.cargo,
+            git_blame: cli.git_blame,
+            markdown_aware: cli.markdown_aware,
+            log_to: cli.log_to,
+            clipboard: cli.clipboard,
+            badge: cli.badge,
+            goal: cli.goal.map(|goal| Goal {
+                per_file: cli.goal_per_file,
+                ..goal
+            }),
+            csv_quote: !cli.no_csv_quote,
+            csv_delimiter: cli.delimiter,
+            #[cfg(feature = "otel")]
+            otel_endpoint: cli.otel_endpoint.clone(),
+            #[cfg(feature = "sqlite")]
+            output_db: cli.output_db.clone(),
+            output: cli.output.clone(),
+            tee: cli.tee.clone(),
+            tokenizer: tokenizer(cli),
+            invalid_utf8: cli.invalid_utf8,
+            eol: eol(cli),
+            count_last_line: cli.count_last_line,
+            no_stat_fastpath: cli.no_stat_fastpath,
+            apparent_size: cli.apparent_size,
+            disk_usage: cli.disk_usage,
+            sort: cli.sort,
+            timeout: cli.timeout,
+            max_open: cli.max_open.or_else(default_max_open),
+            chunk: cli
+                .chunk_lines
+                .map(Chunk::Lines)
+                .or(cli.chunk_bytes.map(Chunk::Bytes)),
+            metrics: cli_metrics(cli),
+        }
+    }
+}
+
+/// The tokenizer of `--words-unicode`.
+fn unicode_words(cli: &Cli) -> Option<Arc<dyn Tokenizer>> {
+    if cli.words_unicode {
+        Some(Arc::new(UnicodeWords))
+    } else {
+        None
+    }
+}
+
+/// The tokenizer of `--word-pattern`, `--words-unicode`, or `--tokenizer`, in that order.
+fn tokenizer(cli: &Cli) -> Option<Arc<dyn Tokenizer>> {
+    cli.word_pattern
+        .clone()
+        .or_else(|| unicode_words(cli))
+        .or_else(|| cli.tokenizer.clone())
+}
+
+/// The line terminator of `--zero-terminated`, `--record-delim`, or `--eol`, in that order.
+fn eol(cli: &Cli) -> Eol {
+    match cli.record_delim {
+        _ if cli.zero_terminated => Eol::Byte(b'\0'),
+        Some(eol) => eol,
+        None => cli.eol,
+    }
+}
+
+/// The metrics asked for by flags, in a fixed order.
+fn cli_metrics(cli: &Cli) -> Vec<MetricFactory> {
+    let mut metrics = Vec::new();
+    if cli.markdown_excluded_words {
+        metrics.push(excluded_words_metric());
+    }
+    let words = || tokenizer(cli).unwrap_or_else(|| Arc::new(AsciiWhitespace));
+    if cli.avg_word_length {
+        metrics.push(avg_word_length_metric(words()));
+    }
+    if cli.word_length_histogram {
+        metrics.push(word_length_histogram_metric(words()));
+    }
+    if cli.unique_words {
+        metrics.push(unique_words_metric());
+    }
+    if cli.words_per_line {
+        metrics.push(words_per_line_metric(words()));
+    }
+    if cli.entropy {
+        metrics.push(entropy_metric());
+    }
+    if cli.indent_histogram {
+        metrics.push(indent_histogram_metric());
+    }
+    if cli.indent_stats {
+        metrics.extend(indent_stats_metrics());
+    }
+    if cli.detect_lang {
+        metrics.push(detect_lang_metric());
+    }
+    if cli.count_urls {
+        metrics.push(count_urls_metric());
+    }
+    for &preset in &cli.count_preset {
+        if preset != Preset::Urls || !cli.count_urls {
+            metrics.push(preset_metric(preset));
+        }
+    }
+    for (i, regex) in cli.count_matches.iter().enumerate() {
+        // a column per pattern, so a repeated pattern would be a repeated column
+        if !cli.count_matches[..i]
+            .iter()
+            .any(|other| other.as_str() == regex.as_str())
+        {
+            metrics.extend(count_matches_metrics(regex.clone()));
+        }
+    }
+    if let Some(regex) = &cli.count_lines_matching {
+        metrics.push(count_lines_matching_metric(regex.clone()));
+    }
+    if cli.prose_stats {
+        metrics.extend(prose_stats_metrics());
+    }
+    if cli.readability {
+        metrics.extend(readability_metrics());
+    }
+    if cli.line_stats {
+        metrics.extend(line_stats_metrics(eol(cli)));
+    }
+    if cli.blank_lines {
+        metrics.push(blank_lines_metric());
+    }
+    if cli.nonblank_lines {
+        metrics.push(nonblank_lines_metric());
+    }
+    if cli.code {
+        // the syntax is replaced with that of each file by `code::for_path`
+        metrics.extend(code_metrics(Syntax::default()));
+        if !cli.blank_lines {
+            metrics.push(blank_lines_metric());
+        }
+    }
+    if cli.line_endings {
+        metrics.extend(line_endings_metrics());
+    }
+    if cli.trailing_ws {
+        metrics.push(trailing_ws_metric());
+    }
+    if cli.non_ascii {
+        metrics.push(non_ascii_metric());
+    }
+    if cli.graphemes {
+        metrics.push(graphemes_metric());
+    }
+    metrics
+}
+
+impl From<&Options> for CountOptions {
+    fn from(opts: &Options) -> CountOptions {
+        CountOptions {
+            bytes: opts.bytes,
+            chars: opts.chars,
+            words: opts.words,
+            lines: opts.lines,
+            max_line_length: opts.max_line_length,
+            tokenizer: opts.tokenizer.clone(),
+            metrics: opts.metrics.clone(),
+            invalid_utf8: opts.invalid_utf8,
+            eol: opts.eol,
+            count_last_line: opts.count_last_line,
+            no_stat_fastpath: opts.no_stat_fastpath,
+            deadline: None,
         }
     }
 }
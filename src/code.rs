@@ -0,0 +1,257 @@
+//! `--code`, the code and comment lines of each file in the comment syntax of its language, found
+//! by extension like `rwc langs`, alongside the blank lines of `--blank-lines`.
+
+use crate::count::CountOptions;
+use crate::langs::language;
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use std::path::Path;
+
+pub const CODE_LINES: &str = "code lines";
+pub const COMMENT_LINES: &str = "comment lines";
+
+/// How a language writes comments.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Syntax {
+    /// Comments which run to the end of the line, like `//`.
+    line: &'static [&'static str],
+    /// Comments between an opening and closing delimiter, like `/*` and `*/`.
+    block: &'static [(&'static str, &'static str)],
+}
+
+const C_LIKE: Syntax = Syntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+};
+const HASH: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+};
+const MARKUP: Syntax = Syntax {
+    line: &[],
+    block: &[("<!--", "-->")],
+};
+
+/// The comment syntax of a language from `langs::language`, languages without comments like
+/// JSON have none so all their lines are code.
+pub fn syntax(lang: &str) -> Syntax {
+    match lang {
+        "C" | "C Header" | "C++" | "C++ Header" | "C#" | "Go" | "Java" | "JavaScript" | "JSX"
+        | "Kotlin" | "Protocol Buffers" | "Rust" | "Scala" | "Swift" | "TypeScript" | "TSX" => {
+            C_LIKE
+        }
+        "CSS" => Syntax {
+            line: &[],
+            block: &[("/*", "*/")],
+        },
+        "PHP" => Syntax {
+            line: &["//", "#"],
+            block: &[("/*", "*/")],
+        },
+        "Python" | "Ruby" | "Perl" | "Shell" | "TOML" | "YAML" | "Makefile" | "Dockerfile"
+        | "CMake" => HASH,
+        "SQL" => Syntax {
+            line: &["--"],
+            block: &[("/*", "*/")],
+        },
+        "Lua" => Syntax {
+            line: &["--"],
+            block: &[("--[[", "]]")],
+        },
+        "Haskell" => Syntax {
+            line: &["--"],
+            block: &[("{-", "-}")],
+        },
+        "OCaml" => Syntax {
+            line: &[],
+            block: &[("(*", "*)")],
+        },
+        "HTML" | "XML" | "Markdown" => MARKUP,
+        "TeX" => Syntax {
+            line: &["%"],
+            block: &[],
+        },
+        "Vim Script" => Syntax {
+            line: &["\""],
+            block: &[],
+        },
+        _ => Syntax::default(),
+    }
+}
+
+/// Lines which aren't blank, split into those with any code and those with only comments.
+/// Comment delimiters in strings aren't told apart from real ones, and blank lines are left to
+/// `blank::BlankLines` whether they're in a comment or not.
+#[derive(Debug, Default)]
+struct Lines {
+    syntax: Syntax,
+    code: u64,
+    comment: u64,
+    /// The delimiter closing the block comment the current line starts in, if any.
+    in_block: Option<&'static str>,
+    /// A line which spans chunks.
+    line: Vec<u8>,
+}
+
+impl Lines {
+    fn new(syntax: Syntax) -> Lines {
+        Lines {
+            syntax,
+            ..Lines::default()
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        for piece in chunk.split_inclusive(|&b| b == b'\n') {
+            match piece.split_last() {
+                Some((b'\n', line)) if self.line.is_empty() => self.classify(line),
+                Some((b'\n', line)) => {
+                    let mut whole = std::mem::take(&mut self.line);
+                    whole.extend_from_slice(line);
+                    self.classify(&whole);
+                }
+                _ => self.line.extend_from_slice(piece),
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        self.classify(&line);
+    }
+
+    fn classify(&mut self, line: &[u8]) {
+        let syntax = self.syntax;
+        let mut code = false;
+        let mut comment = false;
+        let mut rest = line;
+        while let Some(&b) = rest.first() {
+            if let Some(close) = self.in_block {
+                comment = true;
+                match find(rest, close.as_bytes()) {
+                    Some(i) => {
+                        rest = &rest[i + close.len()..];
+                        self.in_block = None;
+                    }
+                    None => break,
+                }
+            } else if let Some(&(open, close)) = syntax
+                .block
+                .iter()
+                .find(|(open, _)| rest.starts_with(open.as_bytes()))
+            {
+                comment = true;
+                rest = &rest[open.len()..];
+                self.in_block = Some(close);
+            } else if syntax
+                .line
+                .iter()
+                .any(|start| rest.starts_with(start.as_bytes()))
+            {
+                comment = true;
+                break;
+            } else {
+                code |= !b.is_ascii_whitespace();
+                rest = &rest[1..];
+            }
+        }
+        if code {
+            self.code += 1;
+        } else if comment && !line.iter().all(u8::is_ascii_whitespace) {
+            self.comment += 1;
+        }
+    }
+}
+
+/// The index of the first needle in haystack.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[derive(Debug, Default)]
+pub struct CodeLines(Lines);
+
+impl Metric for CodeLines {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        MetricValue::Count(self.0.code)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CommentLines(Lines);
+
+impl Metric for CommentLines {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        MetricValue::Count(self.0.comment)
+    }
+}
+
+/// The `CODE_LINES` and `COMMENT_LINES` columns in syntax.
+pub fn code_metrics(syntax: Syntax) -> Vec<MetricFactory> {
+    vec![
+        MetricFactory::new(CODE_LINES, move || CodeLines(Lines::new(syntax))),
+        MetricFactory::new(COMMENT_LINES, move || CommentLines(Lines::new(syntax))),
+    ]
+}
+
+/// opts with the `CODE_LINES` and `COMMENT_LINES` columns, if any, in the comment syntax of the
+/// language of path. Files of unknown languages have no comments.
+pub fn for_path(path: &Path, mut opts: CountOptions) -> CountOptions {
+    let names = [CODE_LINES, COMMENT_LINES];
+    let metrics = code_metrics(language(path).map(syntax).unwrap_or_default());
+    for metric in opts.metrics.iter_mut() {
+        if let Some(i) = names.iter().position(|name| *name == metric.name) {
+            *metric = metrics[i].clone();
+        }
+    }
+    opts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lang: &str, input: &str, chunk: usize) -> (u64, u64) {
+        let mut lines = Lines::new(syntax(lang));
+        for chunk in input.as_bytes().chunks(chunk) {
+            lines.update(chunk);
+        }
+        lines.finish();
+        (lines.code, lines.comment)
+    }
+
+    #[test]
+    fn test_lines() {
+        let rust = "// a comment\nfn main() { // trailing\n\n    /* block\n\n  still */ let x = 1;\n    /* one line */\n}\n/* unclosed";
+        for chunk in 1..rust.len() {
+            assert_eq!((3, 4), lines("Rust", rust, chunk));
+        }
+        assert_eq!((1, 2), lines("Lua", "--[[ a\n]] -- b\nprint(1)\n", 64));
+        assert_eq!((2, 0), lines("JSON", "{\n  \"a\": \"// b\"}\n", 64));
+        assert_eq!((0, 0), lines("Python", "", 64));
+    }
+
+    #[test]
+    fn test_for_path() {
+        let opts = CountOptions::new().metric(code_metrics(Syntax::default())[1].clone());
+        let mut metric = for_path(Path::new("a.py"), opts).metrics[0].create();
+        metric.update(b"# comment\nx = 1\n");
+        assert_eq!(MetricValue::Count(1), metric.finalize());
+
+        let opts = CountOptions::new().metric(code_metrics(Syntax::default())[1].clone());
+        let mut metric = for_path(Path::new("LICENSE"), opts).metrics[0].create();
+        metric.update(b"# not a comment\n");
+        assert_eq!(MetricValue::Count(0), metric.finalize());
+    }
+}
@@ -0,0 +1,468 @@
+//! `rwc compare`, which counts two directory trees and prints how the counts of each relative
+//! path differ between them.
+
+use crate::cli::Options;
+use crate::console;
+use crate::count::{CountDelta, CountKind, CountOptions, CountablePath, Counts};
+use crate::error::Error;
+use crate::format::Format;
+use crate::print::{
+    csv_record, latex_tabular, org_table, plain_row_error, prometheus_label, row_error, wc_lines,
+    write0, xml_element, xml_escape,
+};
+use colored::*;
+use rayon::prelude::*;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::Write;
+use std::iter;
+use std::path::{Path, PathBuf};
+use term_table::row::Row;
+use term_table::table_cell::{Alignment, TableCell};
+use term_table::Table;
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Changed,
+    OnlyInA,
+    OnlyInB,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Changed => write!(f, "changed"),
+            Status::OnlyInA => write!(f, "removed"),
+            Status::OnlyInB => write!(f, "added"),
+        }
+    }
+}
+
+/// How a relative path differs between the trees. A missing file counts as empty, so the delta
+/// of a removed file is the negation of its counts.
+#[derive(Debug)]
+pub struct Difference {
+    pub path: PathBuf,
+    pub status: Status,
+    pub delta: Result<[CountDelta; 4], Error>,
+}
+
+/// The counts of every file under root, keyed by path relative to root.
+fn count_tree(root: &Path, opts: &CountOptions) -> BTreeMap<PathBuf, Result<Counts, Error>> {
+    let files: Vec<PathBuf> = WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    files
+        .into_par_iter()
+        .map(|path| {
+            let counts = (&path)
+                .count_with(opts.clone())
+                .map_err(|err| Error::for_path(&path, err));
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            (relative, counts)
+        })
+        .collect()
+}
+
+/// Every path whose counts differ between a and b, sorted by path. Paths with equal counts are
+/// left out.
+pub(crate) fn differences(
+    mut a_counts: BTreeMap<PathBuf, Result<Counts, Error>>,
+    mut b_counts: BTreeMap<PathBuf, Result<Counts, Error>>,
+) -> Vec<Difference> {
+    let paths: BTreeSet<PathBuf> = a_counts.keys().chain(b_counts.keys()).cloned().collect();
+
+    let empty = || Ok(iter::empty().sum());
+    let mut diffs = Vec::new();
+    for path in paths {
+        let (status, old, new) = match (a_counts.remove(&path), b_counts.remove(&path)) {
+            (Some(old), Some(new)) => (Status::Changed, old, new),
+            (Some(old), None) => (Status::OnlyInA, old, empty()),
+            (None, Some(new)) => (Status::OnlyInB, empty(), new),
+            (None, None) => unreachable!(),
+        };
+        let delta = old.and_then(|old| {
+            new.map(|new| {
+                let delta = new - old;
+                [delta.bytes, delta.chars, delta.words, delta.lines]
+            })
+        });
+        if let Ok(delta) = &delta {
+            if status == Status::Changed && delta.iter().all(|d| d.val.unwrap_or(0) == 0) {
+                continue;
+            }
+        }
+        diffs.push(Difference {
+            path,
+            status,
+            delta,
+        });
+    }
+    diffs
+}
+
+/// Every relative path whose counts differ between the trees a and b, sorted by path.
+pub fn compare(a: &Path, b: &Path, opts: &CountOptions) -> Result<Vec<Difference>, Error> {
+    for dir in &[a, b] {
+        if !dir.is_dir() {
+            return Err(Error::for_path(dir, String::from("not a directory").into()));
+        }
+    }
+    let (a_counts, b_counts) = rayon::join(|| count_tree(a, opts), || count_tree(b, opts));
+    Ok(differences(a_counts, b_counts))
+}
+
+/// Print a row for each of diffs with the counts enabled in opts.
+pub(crate) fn print_differences<W: Write>(
+    diffs: &[Difference],
+    opts: &Options,
+    fmt: Format,
+    mut w: W,
+) -> Result<(), Error> {
+    let columns: Vec<(usize, &str)> = [opts.bytes, opts.chars, opts.words, opts.lines]
+        .iter()
+        .zip(&["bytes", "chars", "words", "lines"])
+        .enumerate()
+        .filter(|(_, (enabled, _))| **enabled)
+        .map(|(i, (_, name))| (i, *name))
+        .collect();
+
+    match fmt {
+        Format::Named(name, _) if name == "table" => {
+            fn make_cell<'a, T: fmt::Display>(data: &T) -> TableCell<'a> {
+                TableCell::new_with_alignment_and_padding(data, 1, Alignment::Left, true)
+            }
+
+            let mut table = Table::new();
+            table.style = console::table_style();
+            let mut header = vec![
+                make_cell(&"path".blue().bold()),
+                make_cell(&"status".blue().bold()),
+            ];
+            header.extend(
+                columns
+                    .iter()
+                    .map(|(_, name)| make_cell(&name.blue().bold())),
+            );
+            table.add_row(Row::new(header));
+            for diff in diffs {
+                let status = match diff.status {
+                    Status::Changed => diff.status.to_string().yellow(),
+                    Status::OnlyInA => diff.status.to_string().red(),
+                    Status::OnlyInB => diff.status.to_string().green(),
+                };
+                let mut cells = vec![
+                    make_cell(&console::display_path(&diff.path).bold()),
+                    make_cell(&status),
+                ];
+                match &diff.delta {
+                    Ok(delta) => cells.extend(columns.iter().map(|(i, _)| make_cell(&delta[*i]))),
+                    Err(err) => cells.push(TableCell::new_with_alignment_and_padding(
+                        row_error(err),
+                        columns.len(),
+                        Alignment::Center,
+                        false,
+                    )),
+                }
+                table.add_row(Row::new(cells));
+            }
+            write!(w, "{}", table.render())?;
+        }
+        Format::Named(name, _) if name == "csv" => {
+            let mut rows = Vec::new();
+            let mut header = vec!["path", "status"];
+            header.extend(columns.iter().map(|(_, name)| *name));
+            rows.push(csv_record(&header, opts));
+            for diff in diffs {
+                let mut cells = vec![console::display_path(&diff.path), diff.status.to_string()];
+                match &diff.delta {
+                    Ok(delta) => cells.extend(columns.iter().map(|(i, _)| delta[*i].to_string())),
+                    Err(err) => cells.push(row_error(err).to_string()),
+                }
+                rows.push(csv_record(&cells, opts));
+            }
+            write!(w, "{}", rows.join("\n"))?;
+        }
+        Format::Named(name, _) if name == "json" => {
+            let objects: Vec<Value> = diffs
+                .iter()
+                .map(|diff| {
+                    let mut object = Map::new();
+                    object.insert(
+                        String::from("path"),
+                        json!(console::display_path(&diff.path)),
+                    );
+                    object.insert(String::from("status"), json!(diff.status.to_string()));
+                    match &diff.delta {
+                        Ok(delta) => {
+                            for (i, name) in &columns {
+                                object.insert(String::from(*name), json!(delta[*i].val));
+                            }
+                        }
+                        Err(err) => {
+                            object.insert(String::from("error"), json!(plain_row_error(err)));
+                        }
+                    }
+                    Value::Object(object)
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut w, &objects)
+                .map_err(|err| Error::CUSTOM(err.to_string()))?;
+        }
+        Format::Named(name, _) if name == "xml" => {
+            let mut lines = vec![
+                String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"),
+                String::from("<rwc>"),
+            ];
+            for diff in diffs {
+                lines.push(format!(
+                    "  <difference path=\"{}\" status=\"{}\">",
+                    xml_escape(&console::display_path(&diff.path)),
+                    diff.status
+                ));
+                match &diff.delta {
+                    Ok(delta) => lines.extend(columns.iter().map(|(i, name)| {
+                        let value = delta[*i].val.map(|_| delta[*i].to_string());
+                        format!("    {}", xml_element(name, value))
+                    })),
+                    Err(err) => lines.push(format!(
+                        "    {}",
+                        xml_element("error", Some(plain_row_error(err)))
+                    )),
+                }
+                lines.push(String::from("  </difference>"));
+            }
+            lines.push(String::from("</rwc>"));
+            write!(w, "{}", lines.join("\n"))?;
+        }
+        Format::Named(name, _) if name == "wc" => {
+            let rows = diffs
+                .iter()
+                .filter_map(|diff| {
+                    let delta = diff.delta.as_ref().ok()?;
+                    // wc's order of lines, words, chars, then bytes
+                    let values = columns.iter().rev().map(|(i, _)| delta[*i].to_string());
+                    let label = format!("{} {}", diff.status, console::display_path(&diff.path));
+                    Some((values.collect(), Some(label)))
+                })
+                .collect();
+            write!(w, "{}", wc_lines(rows))?;
+        }
+        Format::Named(name, _) if name == "org" => {
+            let mut header = vec![String::from("path"), String::from("status")];
+            header.extend(columns.iter().map(|(_, name)| String::from(*name)));
+            let rows = diffs
+                .iter()
+                .map(|diff| {
+                    let mut cells =
+                        vec![console::display_path(&diff.path), diff.status.to_string()];
+                    match &diff.delta {
+                        Ok(delta) => {
+                            cells.extend(columns.iter().map(|(i, _)| delta[*i].to_string()))
+                        }
+                        Err(err) => cells.push(plain_row_error(err)),
+                    }
+                    cells
+                })
+                .collect();
+            write!(w, "{}", org_table(header, rows, Vec::new()))?;
+        }
+        Format::Named(name, _) if name == "latex" => {
+            let mut header = vec![String::from("path"), String::from("status")];
+            header.extend(columns.iter().map(|(_, name)| String::from(*name)));
+            let rows = diffs
+                .iter()
+                .map(|diff| {
+                    let mut cells =
+                        vec![console::display_path(&diff.path), diff.status.to_string()];
+                    match &diff.delta {
+                        Ok(delta) => {
+                            cells.extend(columns.iter().map(|(i, _)| delta[*i].to_string()))
+                        }
+                        Err(err) => cells.push(plain_row_error(err)),
+                    }
+                    cells
+                })
+                .collect();
+            write!(w, "{}", latex_tabular(header, rows, Vec::new()))?;
+        }
+        Format::Named(name, _) if name == "prometheus" => {
+            for (i, name) in &columns {
+                writeln!(
+                    w,
+                    "# HELP rwc_{}_delta How the {} of each file changed.",
+                    name, name
+                )?;
+                writeln!(w, "# TYPE rwc_{}_delta gauge", name)?;
+                for diff in diffs {
+                    let delta = diff.delta.as_ref().ok().and_then(|delta| delta[*i].val);
+                    if let Some(n) = delta {
+                        let path = prometheus_label(&console::display_path(&diff.path));
+                        writeln!(
+                            w,
+                            "rwc_{}_delta{{path=\"{}\",status=\"{}\"}} {}",
+                            name, path, diff.status, n
+                        )?;
+                    }
+                }
+            }
+        }
+        Format::Null => {
+            let mut header = vec![String::from("path"), String::from("status")];
+            header.extend(columns.iter().map(|(_, name)| String::from(*name)));
+            header.push(String::from("error"));
+            let records = diffs
+                .iter()
+                .map(|diff| {
+                    let mut fields = vec![diff.status.to_string()];
+                    match &diff.delta {
+                        Ok(delta) => {
+                            fields.extend(columns.iter().map(|(i, _)| delta[*i].to_string()));
+                            fields.push(String::new());
+                        }
+                        Err(err) => {
+                            fields.extend(columns.iter().map(|_| String::new()));
+                            fields.push(plain_row_error(err));
+                        }
+                    }
+                    (Some(diff.path.as_path()), fields)
+                })
+                .collect();
+            write0(header, records, &mut w)?;
+        }
+        Format::Template(template) => {
+            let lines: Vec<String> = diffs
+                .iter()
+                .filter_map(|diff| {
+                    let delta = diff.delta.as_ref().ok()?;
+                    let path = console::display_path(&diff.path);
+                    Some(template.render(&path, |kind| {
+                        let i = match kind {
+                            CountKind::Bytes => 0,
+                            CountKind::Chars => 1,
+                            CountKind::Words => 2,
+                            CountKind::Lines => 3,
+                        };
+                        delta[i].to_string()
+                    }))
+                })
+                .collect();
+            write!(w, "{}", lines.join("\n"))?;
+        }
+        Format::Named(name, _) => {
+            return Err(format!("compare can't write --format {}", name).into());
+        }
+    }
+    Ok(())
+}
+
+/// Print the differences between a and b, returning whether there were none.
+pub fn run<W: Write>(a: &Path, b: &Path, opts: &Options, fmt: Format, w: W) -> Result<bool, Error> {
+    let diffs = compare(a, b, &CountOptions::from(opts))?;
+    print_differences(&diffs, opts, fmt, w)?;
+    Ok(diffs.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::{Eol, InvalidUtf8};
+    use crate::format::parse_format;
+    use crate::sort::Sort;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_compare() {
+        let root = env::temp_dir().join(format!("rwc-compare-{}", std::process::id()));
+        let (a, b) = (root.join("a"), root.join("b"));
+        for dir in &[&a, &b] {
+            fs::create_dir_all(dir.join("sub")).unwrap();
+            fs::write(dir.join("same.txt"), "one two\n").unwrap();
+        }
+        fs::write(a.join("sub/changed.txt"), "one\n").unwrap();
+        fs::write(b.join("sub/changed.txt"), "one two three\nfour\n").unwrap();
+        fs::write(a.join("removed.txt"), "gone now\n").unwrap();
+        fs::write(b.join("added.txt"), "new\n").unwrap();
+
+        let opts = CountOptions::new().bytes().words().lines();
+        let diffs = compare(&a, &b, &opts).unwrap();
+        let summary: Vec<(&Path, Status, Option<i64>, Option<i64>)> = diffs
+            .iter()
+            .map(|diff| {
+                let delta = diff.delta.as_ref().unwrap();
+                (diff.path.as_path(), diff.status, delta[0].val, delta[2].val)
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (Path::new("added.txt"), Status::OnlyInB, Some(4), Some(1)),
+                (
+                    Path::new("removed.txt"),
+                    Status::OnlyInA,
+                    Some(-9),
+                    Some(-2)
+                ),
+                (
+                    Path::new("sub/changed.txt"),
+                    Status::Changed,
+                    Some(15),
+                    Some(3)
+                ),
+            ],
+            summary
+        );
+
+        let opts = Options {
+            bytes: false,
+            chars: false,
+            words: true,
+            lines: false,
+            max_line_length: false,
+            show_totals: false,
+            stdin: false,
+            statusline: false,
+            cargo: false,
+            git_blame: false,
+            markdown_aware: false,
+            log_to: None,
+            clipboard: false,
+            badge: None,
+            goal: None,
+            csv_quote: true,
+            csv_delimiter: ',',
+            #[cfg(feature = "otel")]
+            otel_endpoint: None,
+            #[cfg(feature = "sqlite")]
+            output_db: None,
+            output: None,
+            tee: Vec::new(),
+            tokenizer: None,
+            invalid_utf8: InvalidUtf8::Strict,
+            eol: Eol::Lf,
+            count_last_line: false,
+            no_stat_fastpath: false,
+            apparent_size: false,
+            disk_usage: false,
+            sort: Sort::Path,
+            timeout: None,
+            max_open: None,
+            chunk: None,
+            metrics: Vec::new(),
+        };
+        let mut out = Vec::new();
+        assert!(!run(&a, &b, &opts, parse_format("csv"), &mut out).unwrap());
+        assert_eq!(
+            "path,status,words\nadded.txt,added,+1\nremoved.txt,removed,-2\nsub/changed.txt,changed,+3",
+            String::from_utf8(out).unwrap()
+        );
+        assert!(run(&a, &a, &opts, parse_format("csv"), Vec::new()).unwrap());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
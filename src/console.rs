@@ -0,0 +1,157 @@
+//! Terminal setup and path display which differ on Windows.
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use term_table::TableStyle;
+
+static ASCII_BORDERS: AtomicBool = AtomicBool::new(false);
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn SetConsoleOutputCP(code_page: u32) -> i32;
+}
+
+/// Prepare the console for colored tables. On Windows this enables ANSI escape codes and the
+/// UTF-8 code page for the table borders, falling back to plain output on consoles without them.
+pub fn init() {
+    #[cfg(windows)]
+    {
+        use std::io::IsTerminal;
+
+        const CP_UTF8: u32 = 65001;
+        if colored::control::set_virtual_terminal(true).is_err() {
+            colored::control::set_override(false);
+        }
+        // SAFETY: SetConsoleOutputCP has no preconditions, it fails without a console
+        if unsafe { SetConsoleOutputCP(CP_UTF8) } == 0 && std::io::stdout().is_terminal() {
+            ASCII_BORDERS.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Rounded borders, or ASCII ones where the console can't draw them.
+pub fn table_style() -> TableStyle {
+    if ASCII_BORDERS.load(Ordering::Relaxed) {
+        TableStyle::simple()
+    } else {
+        TableStyle::rounded()
+    }
+}
+
+/// A path as the user would write it, without the `\\?\` prefix of long Windows paths.
+pub fn display_path(path: &Path) -> String {
+    let path = path.display().to_string();
+    if cfg!(windows) {
+        strip_verbatim(&path).into_owned()
+    } else {
+        path
+    }
+}
+
+/// The bytes of path for output read by other programs, exactly as given on unix.
+pub fn path_bytes(path: &Path) -> Vec<u8> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    }
+    #[cfg(not(unix))]
+    {
+        display_path(path).into_bytes()
+    }
+}
+
+/// A writer which drops the ANSI escape sequences of colors, for output which ends up in files or
+/// the clipboard. Unlike `colored::control::set_override` it only affects what's written through
+/// it, not other threads or output printed later.
+pub struct Uncolored<W> {
+    inner: W,
+    /// How far into an escape sequence the previous write stopped.
+    escape: Escape,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    /// After the `ESC`.
+    Start,
+    /// After the `ESC [`, until the final byte like the `m` of `ESC [ 3 1 m`.
+    Csi,
+}
+
+impl<W: Write> Uncolored<W> {
+    pub fn new(inner: W) -> Uncolored<W> {
+        Uncolored {
+            inner,
+            escape: Escape::None,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for Uncolored<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut plain = Vec::with_capacity(buf.len());
+        for &b in buf {
+            self.escape = match (self.escape, b) {
+                (Escape::None, 0x1b) => Escape::Start,
+                (Escape::None, _) => {
+                    plain.push(b);
+                    Escape::None
+                }
+                (Escape::Start, b'[') => Escape::Csi,
+                (Escape::Csi, 0x40..=0x7e) | (Escape::Start, _) => Escape::None,
+                (Escape::Csi, _) => Escape::Csi,
+            };
+        }
+        self.inner.write_all(&plain)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn strip_verbatim(path: &str) -> std::borrow::Cow<'_, str> {
+    if let Some(share) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", share).into()
+    } else if let Some(path) = path.strip_prefix(r"\\?\") {
+        path.into()
+    } else {
+        path.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_verbatim() {
+        assert_eq!(r"C:\src\a.txt", strip_verbatim(r"\\?\C:\src\a.txt"));
+        assert_eq!(
+            r"\\server\share\a.txt",
+            strip_verbatim(r"\\?\UNC\server\share\a.txt")
+        );
+        assert_eq!(r"C:\src\a.txt", strip_verbatim(r"C:\src\a.txt"));
+        assert_eq!("src/a.txt", strip_verbatim("src/a.txt"));
+    }
+
+    #[test]
+    fn test_uncolored() {
+        let colored = "\x1b[1;34mpath\x1b[0m \x1b[31mIO Error\x1b[0m: gone\n";
+        for chunk in 1..colored.len() {
+            let mut w = Uncolored::new(Vec::new());
+            for chunk in colored.as_bytes().chunks(chunk) {
+                w.write_all(chunk).unwrap();
+            }
+            assert_eq!(b"path IO Error: gone\n", &w.into_inner()[..]);
+        }
+    }
+}
@@ -1,28 +1,71 @@
+use crate::counter::counters;
 use crate::error::Error;
-use bytecount;
+use crate::graphemes::graphemes_metric;
+use crate::interrupt::Interruptible;
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use crate::status::{self, Tracked};
+use crate::timeout::Deadline;
+use crate::tokenize::Tokenizer;
+use std::convert::TryFrom;
 use std::fmt;
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read};
+use std::iter;
 use std::ops;
-use std::path::Path;
-use utf8::BufReadDecoder;
+use std::path::{Path, PathBuf};
+use std::str;
+use std::sync::Arc;
 
-const BUFFER_SIZE: usize = 1048576;
+pub(crate) const BUFFER_SIZE: usize = 1048576;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Count {
-    pub val: Option<usize>,
+    pub val: Option<u64>,
 }
 
-impl ops::Add<Count> for usize {
-    type Output = usize;
+/// A running total of counts, which skips unavailable counts and saturates instead of wrapping
+/// if it overflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Total {
+    pub val: u64,
+    pub overflowed: bool,
+}
+
+impl ops::Add<Count> for Total {
+    type Output = Total;
+
+    fn add(self, rhs: Count) -> Total {
+        match self.val.checked_add(rhs.val.unwrap_or(0)) {
+            Some(val) => Total { val, ..self },
+            None => Total {
+                val: u64::MAX,
+                overflowed: true,
+            },
+        }
+    }
+}
 
-    fn add(self, rhs: Count) -> usize {
-        if let Some(n) = rhs.val {
-            return self + n;
+impl fmt::Display for Total {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.overflowed {
+            write!(f, "overflow")
+        } else {
+            write!(f, "{}", self.val)
         }
-        return self;
+    }
+}
+
+/// Only available if both counts are and the sum doesn't overflow.
+impl ops::Add<Count> for Count {
+    type Output = Count;
+
+    fn add(self, rhs: Count) -> Count {
+        let val = match (self.val, rhs.val) {
+            (Some(a), Some(b)) => a.checked_add(b),
+            _ => None,
+        };
+        Count { val }
     }
 }
 
@@ -36,169 +79,677 @@ impl fmt::Display for Count {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Counts {
     pub bytes: Count,
     pub chars: Count,
     pub words: Count,
     pub lines: Count,
+    /// Display width of the longest line like `wc -L`, only counted if asked for.
+    pub max_line_length: Count,
+    /// Values of the metrics registered with `CountOptions::metric`, in registration order.
+    pub metrics: Vec<(String, MetricValue)>,
 }
 
-pub trait Countable {
-    fn count(self, bytes: bool, chars: bool, words: bool, lines: bool) -> Result<Counts, Error>;
+/// The counts of a file, or of what else a row of the output stands for, and its label.
+pub type Row = (Result<Counts, Error>, PathBuf);
+
+/// One of the built-in counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountKind {
+    Bytes,
+    Chars,
+    Words,
+    Lines,
 }
 
-pub trait CountablePath {
-    fn count(self, bytes: bool, chars: bool, words: bool, lines: bool) -> Result<Counts, Error>;
+impl CountKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            CountKind::Bytes => "bytes",
+            CountKind::Chars => "chars",
+            CountKind::Words => "words",
+            CountKind::Lines => "lines",
+        }
+    }
 }
 
-impl<P: AsRef<Path>> CountablePath for P {
-    fn count(self, bytes: bool, chars: bool, words: bool, lines: bool) -> Result<Counts, Error> {
-        if bytes && !(chars || words || lines) {
-            count_bytes(self)
+pub fn parse_count_kind(src: &str) -> Result<CountKind, Error> {
+    match src {
+        "bytes" => Ok(CountKind::Bytes),
+        "chars" => Ok(CountKind::Chars),
+        "words" => Ok(CountKind::Words),
+        "lines" => Ok(CountKind::Lines),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid count {}, expected bytes, chars, words, or lines",
+            src
+        ))),
+    }
+}
+
+impl Counts {
+    pub fn get(&self, kind: CountKind) -> Count {
+        match kind {
+            CountKind::Bytes => self.bytes,
+            CountKind::Chars => self.chars,
+            CountKind::Words => self.words,
+            CountKind::Lines => self.lines,
+        }
+    }
+}
+
+impl Count {
+    /// The larger of two counts, only available if both are.
+    pub fn max(self, rhs: Count) -> Count {
+        let val = match (self.val, rhs.val) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
+        Count { val }
+    }
+}
+
+/// Sums of each count, a sum is only available if every count is, and the longest of the max
+/// line lengths. Metrics aren't summed.
+impl iter::Sum<Counts> for Counts {
+    fn sum<I: Iterator<Item = Counts>>(iter: I) -> Counts {
+        let zero = Count { val: Some(0) };
+        iter.fold(
+            Counts {
+                bytes: zero,
+                chars: zero,
+                words: zero,
+                lines: zero,
+                max_line_length: zero,
+                metrics: Vec::new(),
+            },
+            |acc, counts| Counts {
+                bytes: acc.bytes + counts.bytes,
+                chars: acc.chars + counts.chars,
+                words: acc.words + counts.words,
+                lines: acc.lines + counts.lines,
+                max_line_length: acc.max_line_length.max(counts.max_line_length),
+                metrics: Vec::new(),
+            },
+        )
+    }
+}
+
+/// Signed difference between two `Count`s, only available if both counts are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountDelta {
+    pub val: Option<i64>,
+}
+
+impl ops::Sub<Count> for Count {
+    type Output = CountDelta;
+
+    fn sub(self, rhs: Count) -> CountDelta {
+        let val = match (self.val, rhs.val) {
+            (Some(a), Some(b)) if a >= b => i64::try_from(a - b).ok(),
+            (Some(a), Some(b)) => i64::try_from(b - a).ok().map(|d| -d),
+            _ => None,
+        };
+        CountDelta { val }
+    }
+}
+
+impl fmt::Display for CountDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(n) = self.val {
+            write!(f, "{:+}", n)
         } else {
-            count_readable(File::open(self)?, bytes, chars, words, lines)
+            write!(f, "N/A")
         }
     }
 }
 
-impl<R: Read> Countable for R {
+/// Per-count differences between two `Counts`, e.g. `current - baseline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountsDelta {
+    pub bytes: CountDelta,
+    pub chars: CountDelta,
+    pub words: CountDelta,
+    pub lines: CountDelta,
+}
+
+impl ops::Sub<Counts> for Counts {
+    type Output = CountsDelta;
+
+    fn sub(self, rhs: Counts) -> CountsDelta {
+        CountsDelta {
+            bytes: self.bytes - rhs.bytes,
+            chars: self.chars - rhs.chars,
+            words: self.words - rhs.words,
+            lines: self.lines - rhs.lines,
+        }
+    }
+}
+
+/// Which counts to compute. Counts which aren't requested may still be computed if they come for
+/// free with a requested count, e.g. bytes are always available when counting lines.
+#[derive(Debug, Clone, Default)]
+pub struct CountOptions {
+    pub bytes: bool,
+    pub chars: bool,
+    pub words: bool,
+    pub lines: bool,
+    /// Measure the longest line, see `Counts::max_line_length`.
+    pub max_line_length: bool,
+    /// How to split words, ascii whitespace if `None`.
+    pub tokenizer: Option<Arc<dyn Tokenizer>>,
+    /// Additional metrics to compute for each input.
+    pub metrics: Vec<MetricFactory>,
+    /// What to do with invalid utf-8 when counting chars.
+    pub invalid_utf8: InvalidUtf8,
+    /// What terminates a line.
+    pub eol: Eol,
+    /// Count a final line without a terminator, unlike wc.
+    pub count_last_line: bool,
+    /// Read the input to count bytes even when only bytes are counted, rather than trusting
+    /// the size in its metadata.
+    pub no_stat_fastpath: bool,
+    /// Stop reading with a timeout error once this passes, see `--timeout`.
+    pub deadline: Option<Deadline>,
+}
+
+/// How invalid utf-8 is handled when counting chars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidUtf8 {
+    /// Fail with `Error::UTF8`.
+    #[default]
+    Strict,
+    /// Count each invalid sequence as one U+FFFD replacement char.
+    Replace,
+    /// Don't count invalid sequences as chars.
+    Skip,
+}
+
+pub fn parse_invalid_utf8(src: &str) -> Result<InvalidUtf8, Error> {
+    match src {
+        "strict" => Ok(InvalidUtf8::Strict),
+        "replace" => Ok(InvalidUtf8::Replace),
+        "skip" => Ok(InvalidUtf8::Skip),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --invalid-utf8 {}, expected strict, replace, or skip",
+            src
+        ))),
+    }
+}
+
+/// What terminates a line when counting lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eol {
+    /// `\n`, like wc, so `\r\n` is one line.
+    #[default]
+    Lf,
+    /// Only `\r\n`, a lone `\n` or `\r` isn't a line.
+    Crlf,
+    /// `\r`, as in classic Mac OS files.
+    Cr,
+    /// Any of `\n`, `\r\n`, or `\r`.
+    Any,
+    /// Any single byte, like `;` or NUL for records which aren't lines.
+    Byte(u8),
+}
+
+pub fn parse_eol(src: &str) -> Result<Eol, Error> {
+    match src {
+        "lf" => Ok(Eol::Lf),
+        "crlf" => Ok(Eol::Crlf),
+        "cr" => Ok(Eol::Cr),
+        "any" => Ok(Eol::Any),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --eol {}, expected lf, crlf, cr, or any",
+            src
+        ))),
+    }
+}
+
+/// Parse the `--record-delim` argument, a single ascii char or one of the escapes `\0`, `\n`,
+/// `\r`, `\t`, `\\`, or `\xHH` for any byte.
+pub fn parse_record_delim(src: &str) -> Result<Eol, Error> {
+    let byte = match src.as_bytes() {
+        [b] if b.is_ascii() => Some(*b),
+        b"\\0" => Some(b'\0'),
+        b"\\n" => Some(b'\n'),
+        b"\\r" => Some(b'\r'),
+        b"\\t" => Some(b'\t'),
+        b"\\\\" => Some(b'\\'),
+        [b'\\', b'x', hex @ ..] if hex.len() == 2 => str::from_utf8(hex)
+            .ok()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+        _ => None,
+    };
+    byte.map(Eol::Byte).ok_or_else(|| {
+        Error::CUSTOM(format!(
+            "invalid --record-delim {}, expected one ascii char or an escape like \\0 or \\x1e",
+            src
+        ))
+    })
+}
+
+impl CountOptions {
+    pub fn new() -> CountOptions {
+        CountOptions::default()
+    }
+
+    pub fn bytes(mut self) -> CountOptions {
+        self.bytes = true;
+        self
+    }
+
+    pub fn chars(mut self) -> CountOptions {
+        self.chars = true;
+        self
+    }
+
+    pub fn words(mut self) -> CountOptions {
+        self.words = true;
+        self
+    }
+
+    pub fn lines(mut self) -> CountOptions {
+        self.lines = true;
+        self
+    }
+
+    pub fn max_line_length(mut self) -> CountOptions {
+        self.max_line_length = true;
+        self
+    }
+
+    pub fn tokenizer<T: Tokenizer + 'static>(mut self, tokenizer: T) -> CountOptions {
+        self.tokenizer = Some(Arc::new(tokenizer));
+        self
+    }
+
+    pub fn metric(mut self, metric: MetricFactory) -> CountOptions {
+        self.metrics.push(metric);
+        self
+    }
+
+    /// Count user-perceived chars as a `GRAPHEMES` metric, see `Graphemes`.
+    pub fn graphemes(self) -> CountOptions {
+        self.metric(graphemes_metric())
+    }
+
+    pub fn invalid_utf8(mut self, policy: InvalidUtf8) -> CountOptions {
+        self.invalid_utf8 = policy;
+        self
+    }
+
+    pub fn eol(mut self, eol: Eol) -> CountOptions {
+        self.eol = eol;
+        self
+    }
+
+    pub fn count_last_line(mut self) -> CountOptions {
+        self.count_last_line = true;
+        self
+    }
+
+    pub fn no_stat_fastpath(mut self) -> CountOptions {
+        self.no_stat_fastpath = true;
+        self
+    }
+
+    pub fn deadline(mut self, deadline: Deadline) -> CountOptions {
+        self.deadline = Some(deadline);
+        self
+    }
+}
+
+/// Progress reported to the callback passed to `count_with_progress` after each chunk of input
+/// has been counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BytesProcessed {
+    /// Number of bytes in the chunk that was just counted.
+    pub chunk: u64,
+    /// Number of bytes counted so far, including `chunk`.
+    pub total: u64,
+}
+
+pub trait Countable: Sized {
+    fn count_with(self, opts: CountOptions) -> Result<Counts, Error> {
+        self.count_with_progress(opts, |_| {})
+    }
+
+    fn count_with_progress<F: FnMut(BytesProcessed)>(
+        self,
+        opts: CountOptions,
+        progress: F,
+    ) -> Result<Counts, Error>;
+
+    #[deprecated(note = "use `count_with` and `CountOptions` instead")]
+    fn count(self, bytes: bool, chars: bool, words: bool, lines: bool) -> Result<Counts, Error> {
+        self.count_with(CountOptions {
+            bytes,
+            chars,
+            words,
+            lines,
+            max_line_length: false,
+            tokenizer: None,
+            metrics: Vec::new(),
+            invalid_utf8: InvalidUtf8::Strict,
+            eol: Eol::Lf,
+            count_last_line: false,
+            no_stat_fastpath: false,
+            deadline: None,
+        })
+    }
+}
+
+pub trait CountablePath: Sized {
+    fn count_with(self, opts: CountOptions) -> Result<Counts, Error> {
+        self.count_with_progress(opts, |_| {})
+    }
+
+    fn count_with_progress<F: FnMut(BytesProcessed)>(
+        self,
+        opts: CountOptions,
+        progress: F,
+    ) -> Result<Counts, Error>;
+
+    #[deprecated(note = "use `count_with` and `CountOptions` instead")]
     fn count(self, bytes: bool, chars: bool, words: bool, lines: bool) -> Result<Counts, Error> {
-        count_readable(self, bytes, chars, words, lines)
+        self.count_with(CountOptions {
+            bytes,
+            chars,
+            words,
+            lines,
+            max_line_length: false,
+            tokenizer: None,
+            metrics: Vec::new(),
+            invalid_utf8: InvalidUtf8::Strict,
+            eol: Eol::Lf,
+            count_last_line: false,
+            no_stat_fastpath: false,
+            deadline: None,
+        })
+    }
+}
+
+impl<P: AsRef<Path>> CountablePath for P {
+    fn count_with_progress<F: FnMut(BytesProcessed)>(
+        self,
+        opts: CountOptions,
+        progress: F,
+    ) -> Result<Counts, Error> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "count",
+            path = %self.as_ref().display(),
+            bytes = tracing::field::Empty
+        )
+        .entered();
+        let bytes_only = opts.bytes
+            && !(opts.chars || opts.words || opts.lines || opts.max_line_length)
+            && opts.metrics.is_empty();
+        let res = if bytes_only && !opts.no_stat_fastpath {
+            count_bytes(self, progress)
+        } else {
+            let file = {
+                #[cfg(feature = "tracing")]
+                let _span = tracing::debug_span!("open").entered();
+                File::open(self)
+            };
+            match file {
+                Ok(file) => count_readable(file, opts, progress),
+                Err(err) => Err(err.into()),
+            }
+        };
+        #[cfg(feature = "tracing")]
+        trace_result(&span, &res);
+        res
     }
 }
 
-fn count_readable<R: Read>(
+/// Every count of path, leaving chars unavailable rather than failing if path isn't utf-8.
+pub(crate) fn count_all(path: &Path) -> Result<Counts, Error> {
+    path.count_with(CountOptions::new().bytes().chars().words().lines())
+        .or_else(|err| match err.cause() {
+            Error::UTF8() => path.count_with(CountOptions::new().bytes().words().lines()),
+            _ => Err(err),
+        })
+}
+
+impl<R: Read> Countable for R {
+    fn count_with_progress<F: FnMut(BytesProcessed)>(
+        self,
+        opts: CountOptions,
+        progress: F,
+    ) -> Result<Counts, Error> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("count", bytes = tracing::field::Empty).entered();
+        let res = count_readable(self, opts, progress);
+        #[cfg(feature = "tracing")]
+        trace_result(&span, &res);
+        res
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn trace_result(span: &tracing::Span, res: &Result<Counts, Error>) {
+    match res {
+        Ok(counts) => {
+            if let Some(bytes) = counts.bytes.val {
+                span.record("bytes", bytes);
+            }
+        }
+        Err(err) => tracing::warn!(error = %err.report(), "counting failed"),
+    }
+}
+
+fn count_readable<R: Read, F: FnMut(BytesProcessed)>(
     readable: R,
-    _bytes: bool,
-    chars: bool,
-    words: bool,
-    lines: bool,
+    opts: CountOptions,
+    progress: F,
 ) -> Result<Counts, Error> {
-    let reader = BufReader::with_capacity(BUFFER_SIZE, readable);
-    if chars {
-        count_bytes_chars_words_lines(reader)
-    } else if lines && !words {
-        count_bytes_lines(reader)
-    } else {
-        count_bytes_words_lines(reader)
+    let reader = BufReader::with_capacity(BUFFER_SIZE, Interruptible(Tracked(readable)));
+    let mut metrics: Vec<_> = opts.metrics.iter().map(MetricFactory::create).collect();
+    let res = count_pass(reader, &opts, &mut metrics, progress);
+    let finalized = opts
+        .metrics
+        .iter()
+        .zip(metrics.iter_mut())
+        .map(|(factory, metric)| (factory.name.clone(), metric.finalize()))
+        .collect();
+    match res {
+        Ok(mut counts) => {
+            counts.metrics = finalized;
+            Ok(counts)
+        }
+        Err(Error::PARTIAL { mut counts, source }) => {
+            counts.metrics = finalized;
+            Err(Error::PARTIAL { counts, source })
+        }
+        Err(err) => Err(err),
     }
 }
 
-pub(crate) fn count_bytes<P: AsRef<Path>>(path: P) -> Result<Counts, Error> {
-    let bytes = fs::metadata(path)?.len() as usize;
-    Ok(Counts {
+/// Count everything opts asks for, and feed metrics, in a single pass over reader. Bytes are
+/// always counted, the rest by the `Counter`s for opts.
+pub(crate) fn count_pass<T: Read, F: FnMut(BytesProcessed)>(
+    mut reader: BufReader<T>,
+    opts: &CountOptions,
+    metrics: &mut [Box<dyn Metric>],
+    mut progress: F,
+) -> Result<Counts, Error> {
+    let mut counters = counters(opts);
+    let mut bytes = 0u64;
+    let res = (|| -> Result<(), Error> {
+        loop {
+            if let Some(deadline) = opts.deadline {
+                deadline.check()?;
+            }
+            let buffer = reader.fill_buf()?;
+            let len = buffer.len();
+            if len == 0 {
+                break;
+            }
+            bytes += len as u64;
+            for counter in counters.iter_mut() {
+                counter.update(buffer)?;
+            }
+            for metric in metrics.iter_mut() {
+                metric.update(buffer);
+            }
+            reader.consume(len);
+            progress(BytesProcessed {
+                chunk: len as u64,
+                total: bytes,
+            });
+        }
+        counters.iter_mut().try_for_each(|counter| counter.finish())
+    })();
+    let mut counts = Counts {
         bytes: Count { val: Some(bytes) },
         chars: Count { val: None },
         words: Count { val: None },
         lines: Count { val: None },
-    })
+        max_line_length: Count { val: None },
+        metrics: Vec::new(),
+    };
+    for counter in &counters {
+        counter.record(&mut counts, res.is_ok());
+    }
+    match res {
+        Ok(()) => Ok(counts),
+        Err(err) => Err(Error::partial(counts, err)),
+    }
 }
 
-pub(crate) fn count_bytes_words_lines<T: Read>(mut reader: BufReader<T>) -> Result<Counts, Error> {
-    let (mut bytes, mut words, mut lines) = (0, 0, 0);
-    let mut in_word = false;
-    loop {
-        let buffer = reader.fill_buf()?;
-        let len = buffer.len();
-        if len == 0 {
-            break;
-        }
-        bytes += len;
-        for &b in buffer {
-            lines += if b == b'\n' { 1 } else { 0 };
-            if b.is_ascii_whitespace() {
-                words += if in_word { 1 } else { 0 };
-                in_word = false;
-            } else {
-                in_word = true;
-            }
-        }
-        reader.consume(len);
-    }
-    if in_word {
-        words += 1;
+pub(crate) fn count_bytes<P: AsRef<Path>, F: FnMut(BytesProcessed)>(
+    path: P,
+    mut progress: F,
+) -> Result<Counts, Error> {
+    let bytes = fs::metadata(&path)?.len();
+    // /proc and some virtual or network filesystems report 0 for files which aren't empty
+    if bytes == 0 {
+        return count_readable(File::open(path)?, CountOptions::new().bytes(), progress);
     }
+    status::add_bytes(bytes);
+    progress(BytesProcessed {
+        chunk: bytes,
+        total: bytes,
+    });
     Ok(Counts {
         bytes: Count { val: Some(bytes) },
         chars: Count { val: None },
-        words: Count { val: Some(words) },
-        lines: Count { val: Some(lines) },
+        words: Count { val: None },
+        lines: Count { val: None },
+        max_line_length: Count { val: None },
+        metrics: Vec::new(),
     })
 }
 
-pub(crate) fn count_bytes_chars_words_lines<T: Read>(
-    reader: BufReader<T>,
-) -> Result<Counts, Error> {
-    let (mut bytes, mut chars, mut words, mut lines) = (0, 0, 0, 0);
-    let mut in_word = false;
-    let mut decoder = BufReadDecoder::new(reader);
-    loop {
-        if let Some(res) = decoder.next_strict() {
-            let str = res?;
-            bytes += str.len();
-            for c in str.chars() {
-                chars += 1;
-                lines += if c == '\n' { 1 } else { 0 };
-                if c.is_ascii_whitespace() {
-                    words += if in_word { 1 } else { 0 };
-                    in_word = false;
-                } else {
-                    in_word = true;
+/// Iterator over the `Counts` of each line in a reader, see `lines`.
+pub struct Lines<R> {
+    reader: BufReader<R>,
+    opts: CountOptions,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> Lines<R> {
+    /// Read the next line into buf, with its terminator as `opts.eol` defines it.
+    fn read_line(&mut self) -> io::Result<usize> {
+        self.buf.clear();
+        match self.opts.eol {
+            Eol::Lf => self.reader.read_until(b'\n', &mut self.buf),
+            Eol::Cr => self.reader.read_until(b'\r', &mut self.buf),
+            Eol::Byte(b) => self.reader.read_until(b, &mut self.buf),
+            Eol::Crlf => loop {
+                if self.reader.read_until(b'\n', &mut self.buf)? == 0 || self.buf.ends_with(b"\r\n")
+                {
+                    return Ok(self.buf.len());
+                }
+            },
+            Eol::Any => {
+                loop {
+                    let available = self.reader.fill_buf()?;
+                    if available.is_empty() {
+                        break;
+                    }
+                    match available.iter().position(|&b| b == b'\n' || b == b'\r') {
+                        Some(i) => {
+                            self.buf.extend_from_slice(&available[..=i]);
+                            self.reader.consume(i + 1);
+                            // a \r\n is one terminator
+                            if self.buf.ends_with(b"\r")
+                                && self.reader.fill_buf()?.first() == Some(&b'\n')
+                            {
+                                self.buf.push(b'\n');
+                                self.reader.consume(1);
+                            }
+                            break;
+                        }
+                        None => {
+                            let len = available.len();
+                            self.buf.extend_from_slice(available);
+                            self.reader.consume(len);
+                        }
+                    }
                 }
+                Ok(self.buf.len())
             }
-        } else {
-            break;
         }
     }
-    if in_word {
-        words += 1;
-    }
-    Ok(Counts {
-        bytes: Count { val: Some(bytes) },
-        chars: Count { val: Some(chars) },
-        words: Count { val: Some(words) },
-        lines: Count { val: Some(lines) },
-    })
 }
 
-pub(crate) fn count_bytes_lines<T: Read>(mut reader: BufReader<T>) -> Result<Counts, Error> {
-    let (mut bytes, mut lines) = (0, 0);
-    loop {
-        let buffer = reader.fill_buf()?;
-        let len = buffer.len();
-        if len == 0 {
-            break;
+impl<R: Read> Iterator for Lines<R> {
+    type Item = Result<Counts, Error>;
+
+    fn next(&mut self) -> Option<Result<Counts, Error>> {
+        match self.read_line() {
+            Ok(0) => None,
+            Ok(_) => Some(count_pass(
+                BufReader::new(&self.buf[..]),
+                &self.opts,
+                &mut [],
+                |_| {},
+            )),
+            Err(err) => Some(Err(err.into())),
         }
-        bytes += len;
-        lines += bytecount::count(buffer, b'\n');
-        reader.consume(len);
     }
-    Ok(Counts {
-        bytes: Count { val: Some(bytes) },
-        chars: Count { val: None },
-        words: Count { val: None },
-        lines: Count { val: Some(lines) },
-    })
+}
+
+/// Lazily count each line of readable as opts asks, lines ending as `opts.eol` says. A line's
+/// counts include its terminator, so they add up to those of `count_with`, and a final line
+/// without one is still yielded. `opts.metrics` are left out.
+pub fn lines<R: Read>(readable: R, opts: CountOptions) -> Lines<R> {
+    Lines {
+        reader: BufReader::with_capacity(BUFFER_SIZE, readable),
+        opts,
+        buf: Vec::new(),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::counter::LineCounter;
+    use crate::metric::Metric;
+    use crate::tokenize::{AsciiWhitespace, UnicodeWords};
+    use std::io;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     #[test]
     fn test_count_bytes_words_lines() {
         let text: &[u8] =
             "hello😀😃😄😁😆😅😂🤣😀😃😄😁 hello world 12345\n67890😀 😃 😄 😁".as_bytes();
         let reader = BufReader::with_capacity(10, text);
-        let counts = count_bytes_words_lines(reader).unwrap();
+        let opts = CountOptions::new().bytes().words().lines();
+        let counts = count_pass(reader, &opts, &mut [], |_| {}).unwrap();
         assert_eq!(96, counts.bytes.val.unwrap(),);
         assert_eq!(8, counts.words.val.unwrap(),);
         assert_eq!(1, counts.lines.val.unwrap(),);
 
         let path: PathBuf = ["test_data", "default.txt"].iter().collect();
-        let counts = path.count(true, false, true, true).unwrap();
+        let counts = path
+            .count_with(CountOptions::new().bytes().words().lines())
+            .unwrap();
         assert_eq!(1048697, counts.bytes.val.unwrap());
         assert_eq!(183155, counts.words.val.unwrap());
         assert_eq!(20681, counts.lines.val.unwrap());
@@ -209,48 +760,447 @@ mod tests {
         let text: &[u8] =
             "hello😀😃😄😁😆😅😂🤣😀😃😄😁 hello world 12345\n67890😀 😃 😄 😁".as_bytes();
         let reader = BufReader::with_capacity(10, text);
-        let counts = count_bytes_chars_words_lines(reader).unwrap();
+        let opts = CountOptions::new().chars().words().lines();
+        let counts = count_pass(reader, &opts, &mut [], |_| {}).unwrap();
         assert_eq!(96, counts.bytes.val.unwrap(),);
         assert_eq!(48, counts.chars.val.unwrap(),);
         assert_eq!(8, counts.words.val.unwrap(),);
         assert_eq!(1, counts.lines.val.unwrap(),);
 
         let path: PathBuf = ["test_data", "default.txt"].iter().collect();
-        let counts = path.count(true, true, true, true).unwrap();
+        let counts = path
+            .count_with(CountOptions::new().bytes().chars().words().lines())
+            .unwrap();
         assert_eq!(1048697, counts.bytes.val.unwrap());
         assert_eq!(726780, counts.chars.val.unwrap());
         assert_eq!(183155, counts.words.val.unwrap());
         assert_eq!(20681, counts.lines.val.unwrap());
     }
 
+    #[test]
+    fn test_count_tokenized() {
+        let text: &[u8] =
+            "hello😀😃😄😁😆😅😂🤣😀😃😄😁 hello world 12345\n67890😀 😃 😄 😁".as_bytes();
+        let reader = BufReader::with_capacity(10, text);
+        let opts = CountOptions::new()
+            .chars()
+            .words()
+            .tokenizer(AsciiWhitespace);
+        let counts = count_pass(reader, &opts, &mut [], |_| {}).unwrap();
+        assert_eq!(96, counts.bytes.val.unwrap());
+        assert_eq!(48, counts.chars.val.unwrap());
+        assert_eq!(8, counts.words.val.unwrap());
+        assert_eq!(1, counts.lines.val.unwrap());
+
+        let counts = "東京 is\nnice"
+            .as_bytes()
+            .count_with(CountOptions::new().words().tokenizer(UnicodeWords))
+            .unwrap();
+        assert_eq!(4, counts.words.val.unwrap());
+        assert_eq!(None, counts.chars.val);
+
+        let path: PathBuf = ["test_data", "default.txt"].iter().collect();
+        let counts = path
+            .count_with(CountOptions::new().words().tokenizer(AsciiWhitespace))
+            .unwrap();
+        assert_eq!(1048697, counts.bytes.val.unwrap());
+        assert_eq!(183155, counts.words.val.unwrap());
+        assert_eq!(20681, counts.lines.val.unwrap());
+    }
+
+    #[test]
+    fn test_count_with_metrics() {
+        struct CountByte(u8, u64);
+        impl Metric for CountByte {
+            fn update(&mut self, chunk: &[u8]) {
+                self.1 += bytecount::count(chunk, self.0) as u64;
+            }
+            fn finalize(&mut self) -> MetricValue {
+                MetricValue::Count(self.1)
+            }
+        }
+
+        let path: PathBuf = ["test_data", "default.txt"].iter().collect();
+        let opts = CountOptions::new()
+            .bytes()
+            .metric(MetricFactory::new("newlines", || CountByte(b'\n', 0)))
+            .metric(MetricFactory::new("spaces", || CountByte(b' ', 0)));
+        let counts = (&path).count_with(opts.clone()).unwrap();
+        assert_eq!(1048697, counts.bytes.val.unwrap());
+        assert_eq!(
+            vec![
+                (String::from("newlines"), MetricValue::Count(20681)),
+                (String::from("spaces"), MetricValue::Count(165428)),
+            ],
+            counts.metrics
+        );
+
+        // each input gets a fresh metric
+        let counts = path.count_with(opts).unwrap();
+        assert_eq!(MetricValue::Count(20681), counts.metrics[0].1);
+
+        let text = "e\u{301}👍🏽\n".as_bytes();
+        let counts = text
+            .count_with(CountOptions::new().chars().graphemes())
+            .unwrap();
+        assert_eq!(Some(5), counts.chars.val);
+        assert_eq!(
+            vec![(String::from("graphemes"), MetricValue::Count(3))],
+            counts.metrics
+        );
+    }
+
     #[test]
     fn test_count_bytes_lines() {
         let text: &[u8] =
             "hello😀😃😄😁😆😅😂🤣😀😃😄😁 hello world 12345\n67890😀 😃 😄 😁".as_bytes();
         let reader = BufReader::with_capacity(10, text);
-        let counts = count_bytes_lines(reader).unwrap();
+        let counts = count_pass(reader, &CountOptions::new().lines(), &mut [], |_| {}).unwrap();
         assert_eq!(96, counts.bytes.val.unwrap(),);
         assert_eq!(1, counts.lines.val.unwrap(),);
 
         let path: PathBuf = ["test_data", "default.txt"].iter().collect();
-        let counts = path.count(true, false, false, true).unwrap();
+        let counts = path
+            .count_with(CountOptions::new().bytes().lines())
+            .unwrap();
         assert_eq!(20681, counts.lines.val.unwrap());
     }
 
     #[test]
     fn test_count_bytes() {
         let path: PathBuf = ["test_data", "default.txt"].iter().collect();
-        let counts = path.count(true, false, false, false).unwrap();
+        let counts = (&path).count_with(CountOptions::new().bytes()).unwrap();
         assert_eq!(counts.bytes.val.unwrap(), 1048697);
+
+        let opts = CountOptions::new().bytes().no_stat_fastpath();
+        assert_eq!(1048697, path.count_with(opts).unwrap().bytes.val.unwrap());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_count_bytes_zero_size_metadata() {
+        let counts = "/proc/self/status"
+            .count_with(CountOptions::new().bytes())
+            .unwrap();
+        assert!(counts.bytes.val.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_count_with_progress() {
+        let text: &[u8] = b"hello world\nfoo bar baz\n";
+        let mut chunks = Vec::new();
+        let counts = text
+            .count_with_progress(CountOptions::new().bytes().words().lines(), |p| {
+                chunks.push(p)
+            })
+            .unwrap();
+        assert_eq!(24, counts.bytes.val.unwrap());
+        assert_eq!(24, chunks.iter().map(|p| p.chunk).sum::<u64>());
+        assert_eq!(24, chunks.last().unwrap().total);
+
+        let reader = BufReader::with_capacity(10, text);
+        let mut totals = Vec::new();
+        count_pass(reader, &CountOptions::new().chars(), &mut [], |p| {
+            totals.push(p.total)
+        })
+        .unwrap();
+        assert_eq!(vec![10, 20, 24], totals);
+    }
+
+    #[test]
+    fn test_lines() {
+        let text: &[u8] = "hello😀 world\n\nfoo  bar baz".as_bytes();
+        let opts = CountOptions::new().chars().words().lines();
+        let counts = lines(text, opts).collect::<Result<Vec<_>, _>>().unwrap();
+        let vals: Vec<_> = counts
+            .iter()
+            .map(|c| (c.bytes.val, c.chars.val, c.words.val, c.lines.val))
+            .collect();
+        assert_eq!(
+            vec![
+                (Some(16), Some(13), Some(2), Some(1)),
+                (Some(1), Some(1), Some(0), Some(1)),
+                (Some(12), Some(12), Some(3), Some(0)),
+            ],
+            vals
+        );
+
+        let text: &[u8] = b"a b\r\nc\rd\ne";
+        let opts = CountOptions::new().words().lines().eol(Eol::Any);
+        let bytes: Vec<_> = lines(text, opts)
+            .map(|c| c.unwrap().bytes.val.unwrap())
+            .collect();
+        assert_eq!(vec![5, 2, 2, 1], bytes);
+
+        let invalid: &[u8] = b"ok\n\xff\n";
+        let mut it = lines(invalid, CountOptions::new().chars());
+        assert!(it.next().unwrap().is_ok());
+        assert!(it.next().unwrap().is_err());
+        assert!(it.next().is_none());
+        let mut it = lines(
+            invalid,
+            CountOptions::new()
+                .chars()
+                .invalid_utf8(InvalidUtf8::Replace),
+        );
+        assert_eq!(Some(2), it.nth(1).unwrap().unwrap().chars.val);
+    }
+
+    #[test]
+    fn test_lines_sum_to_whole() {
+        let path: PathBuf = ["test_data", "default.txt"].iter().collect();
+        let text = fs::read(&path).unwrap();
+        let whole_opts = || CountOptions::new().chars().words().lines();
+        let options = vec![
+            whole_opts(),
+            whole_opts().tokenizer(UnicodeWords),
+            whole_opts().eol(Eol::Any).count_last_line(),
+        ];
+        for opts in options {
+            let whole = text.as_slice().count_with(opts.clone()).unwrap();
+            let sum: Counts = lines(text.as_slice(), opts).map(Result::unwrap).sum();
+            assert_eq!(whole.bytes, sum.bytes);
+            assert_eq!(whole.chars, sum.chars);
+            assert_eq!(whole.words, sum.words);
+            assert_eq!(whole.lines, sum.lines);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_count_deprecated_flags() {
+        let path: PathBuf = ["test_data", "default.txt"].iter().collect();
+        let counts = path.count(true, false, true, true).unwrap();
+        assert_eq!(1048697, counts.bytes.val.unwrap());
+        assert_eq!(183155, counts.words.val.unwrap());
+        assert_eq!(20681, counts.lines.val.unwrap());
+    }
+
+    #[test]
+    fn subtracting_counts() {
+        let a = Count { val: Some(2) };
+        let b = Count { val: Some(5) };
+        let na = Count { val: None };
+        assert_eq!(Some(3), (b - a).val);
+        assert_eq!(Some(-3), (a - b).val);
+        assert_eq!(None, (a - na).val);
+        assert_eq!("-3", (a - b).to_string());
+        assert_eq!("+3", (b - a).to_string());
+        assert_eq!("+0", (a - a).to_string());
+        assert_eq!("N/A", (na - a).to_string());
+
+        let old = Counts {
+            bytes: Count { val: Some(10) },
+            chars: Count { val: None },
+            words: Count { val: Some(4) },
+            lines: Count { val: Some(2) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let new = Counts {
+            bytes: Count { val: Some(7) },
+            chars: Count { val: Some(7) },
+            words: Count { val: Some(4) },
+            lines: Count { val: Some(3) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let delta = new - old;
+        assert_eq!(Some(-3), delta.bytes.val);
+        assert_eq!(None, delta.chars.val);
+        assert_eq!(Some(0), delta.words.val);
+        assert_eq!(Some(1), delta.lines.val);
     }
 
     #[test]
     fn adding_counts() {
-        let n = 1;
+        let n = Total {
+            val: 1,
+            overflowed: false,
+        };
         let c = Count { val: Some(2) };
-        assert_eq!((n + c), 3);
+        assert_eq!((n + c).val, 3);
 
         let c = Count { val: None };
-        assert_eq!((n + c), 1);
+        assert_eq!((n + c).val, 1);
+
+        let a = Count { val: Some(2) };
+        assert_eq!(Some(4), (a + a).val);
+        assert_eq!(None, (a + c).val);
+
+        let max = Count {
+            val: Some(u64::MAX),
+        };
+        let total = n + max;
+        assert!(total.overflowed);
+        assert_eq!("overflow", total.to_string());
+        assert!((total + a).overflowed);
+        assert_eq!(None, (a + max).val);
+        assert_eq!(None, (max - Count { val: Some(0) }).val);
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let input: &[u8] = b"caf\xc3\xa9 \xff\xfe ok\n\xe2\x82\n";
+        let count = |policy, tokenizer: Option<UnicodeWords>| {
+            let mut opts = CountOptions::new().chars().words().invalid_utf8(policy);
+            if let Some(tokenizer) = tokenizer {
+                opts = opts.tokenizer(tokenizer);
+            }
+            input.count_with(opts)
+        };
+        for &tokenizer in &[None, Some(UnicodeWords)] {
+            assert!(count(InvalidUtf8::Strict, tokenizer).is_err());
+
+            // the \xff and \xfe are separate invalid sequences, the truncated \xe2\x82 is one
+            let counts = count(InvalidUtf8::Replace, tokenizer).unwrap();
+            assert_eq!(Some(15), counts.bytes.val);
+            assert_eq!(Some(13), counts.chars.val);
+
+            let counts = count(InvalidUtf8::Skip, tokenizer).unwrap();
+            assert_eq!(Some(15), counts.bytes.val);
+            assert_eq!(Some(10), counts.chars.val);
+        }
+    }
+
+    #[test]
+    fn test_eol() {
+        let input: &[u8] = b"a\r\nb\rc\nd\r\r\ne";
+        let eols = [
+            (Eol::Lf, 3),
+            (Eol::Crlf, 2),
+            (Eol::Cr, 4),
+            (Eol::Any, 5),
+            (Eol::Byte(b'\r'), 4),
+            (Eol::Byte(b'c'), 1),
+        ];
+        for (eol, expected) in &eols {
+            let options = vec![
+                CountOptions::new().lines(),
+                CountOptions::new().words().lines(),
+                CountOptions::new().chars().lines(),
+                CountOptions::new().words().lines().tokenizer(UnicodeWords),
+            ];
+            for opts in options {
+                let counts = input.count_with(opts.eol(*eol)).unwrap();
+                assert_eq!(Some(*expected), counts.lines.val, "{:?}", eol);
+            }
+
+            // a \r\n split between buffers
+            let mut lines = LineCounter::new(*eol, false);
+            for i in 0..input.len() {
+                lines.update(&input[i..i + 1]);
+            }
+            assert_eq!(*expected, lines.lines, "{:?}", eol);
+        }
+    }
+
+    #[test]
+    fn test_count_last_line() {
+        let count = |input: &[u8], eol| {
+            let options = vec![
+                CountOptions::new().lines(),
+                CountOptions::new().words().lines(),
+                CountOptions::new().chars().lines(),
+                CountOptions::new().words().lines().tokenizer(UnicodeWords),
+            ];
+            let lines: Vec<_> = options
+                .into_iter()
+                .map(|opts| {
+                    let opts = opts.eol(eol).count_last_line();
+                    input.count_with(opts).unwrap().lines.val.unwrap()
+                })
+                .collect();
+            assert!(lines.iter().all(|&n| n == lines[0]), "{:?}", lines);
+            lines[0]
+        };
+        assert_eq!(0, count(b"", Eol::Lf));
+        assert_eq!(1, count(b"one", Eol::Lf));
+        assert_eq!(1, count(b"one\n", Eol::Lf));
+        assert_eq!(2, count(b"one\ntwo", Eol::Lf));
+        assert_eq!(2, count(b"one\r\ntwo\r", Eol::Crlf));
+        assert_eq!(1, count(b"one\r\n", Eol::Crlf));
+        assert_eq!(1, count(b"one\r", Eol::Any));
+        assert_eq!(2, count(b"one\rtwo", Eol::Cr));
+        assert_eq!(3, count(b"a;b\n;c", Eol::Byte(b';')));
+        assert_eq!(2, count(b"a\0b\0", Eol::Byte(0)));
+    }
+
+    #[test]
+    fn test_parse_record_delim() {
+        assert_eq!(Eol::Byte(b';'), parse_record_delim(";").unwrap());
+        assert_eq!(Eol::Byte(0), parse_record_delim("\\0").unwrap());
+        assert_eq!(Eol::Byte(b'\r'), parse_record_delim("\\r").unwrap());
+        assert_eq!(Eol::Byte(0x1e), parse_record_delim("\\x1E").unwrap());
+        for src in &["", ";;", "é", "\\q", "\\x1"] {
+            assert!(parse_record_delim(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_partial_counts() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+        let count = |opts| {
+            let err = b"one two\nthr".chain(Failing).count_with(opts).unwrap_err();
+            assert!(matches!(err.cause(), Error::IO(_)));
+            err.partial_counts().cloned().unwrap()
+        };
+
+        let counts = count(CountOptions::new().bytes().words().lines());
+        assert_eq!(
+            (Some(11), Some(3), Some(1)),
+            (counts.bytes.val, counts.words.val, counts.lines.val)
+        );
+        let counts = count(CountOptions::new().lines());
+        assert_eq!((Some(11), Some(1)), (counts.bytes.val, counts.lines.val));
+        let counts = count(CountOptions::new().chars().words());
+        assert_eq!((Some(11), Some(3)), (counts.chars.val, counts.words.val));
+        // the words of an unfinished line count too
+        let counts = count(CountOptions::new().words().tokenizer(UnicodeWords));
+        assert_eq!((Some(11), Some(3)), (counts.bytes.val, counts.words.val));
+
+        let err = b"ok\n\xff"
+            .count_with(CountOptions::new().chars())
+            .unwrap_err();
+        assert!(matches!(err.cause(), Error::UTF8()));
+        assert_eq!(Some(3), err.partial_counts().unwrap().chars.val);
+    }
+
+    #[test]
+    fn test_deadline() {
+        // an endless input stops once the deadline passes, keeping what was counted
+        let opts = CountOptions::new()
+            .words()
+            .deadline(Deadline::after(Duration::from_millis(20)));
+        let err = io::repeat(b'a').count_with(opts).unwrap_err();
+        match err.cause() {
+            Error::CUSTOM(msg) => assert_eq!("timed out after 0.02s", msg),
+            err => panic!("expected a timeout, got {:?}", err),
+        }
+        assert!(err.partial_counts().unwrap().bytes.val.unwrap() > 0);
+    }
+
+    #[test]
+    fn summing_counts() {
+        let counts = |chars| Counts {
+            bytes: Count { val: Some(3) },
+            chars: Count { val: chars },
+            words: Count { val: Some(1) },
+            lines: Count { val: Some(1) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let sum: Counts = vec![counts(Some(3)), counts(None)].into_iter().sum();
+        assert_eq!(Some(6), sum.bytes.val);
+        assert_eq!(None, sum.chars.val);
+        assert_eq!(Some(2), sum.lines.val);
+
+        let sum: Counts = Vec::new().into_iter().sum();
+        assert_eq!(Some(0), sum.words.val);
     }
 }
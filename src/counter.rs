@@ -0,0 +1,385 @@
+//! The counts computed by `count_pass`, each fed every chunk of the input in turn so any
+//! combination of them takes a single pass.
+
+use crate::count::{Count, CountOptions, Counts, Eol, InvalidUtf8};
+use crate::error::Error;
+use crate::tokenize::Tokenizer;
+use crate::width::{Decoder, Widest};
+use std::str;
+use std::sync::Arc;
+
+/// One count over the raw bytes of an input. A new counter only needs an impl and a line in
+/// `counters`.
+pub(crate) trait Counter {
+    /// Called with each consecutive chunk of the input.
+    fn update(&mut self, chunk: &[u8]) -> Result<(), Error>;
+
+    /// Called once after the last chunk, unless counting failed.
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Store the count in counts, finished is false if the input ended early with an error.
+    fn record(&self, counts: &mut Counts, finished: bool);
+}
+
+/// The counters for opts. Lines are always counted since they're nearly free.
+pub(crate) fn counters(opts: &CountOptions) -> Vec<Box<dyn Counter>> {
+    let mut counters: Vec<Box<dyn Counter>> = Vec::new();
+    counters.push(Box::new(LineCounter::new(opts.eol, opts.count_last_line)));
+    if opts.words {
+        match &opts.tokenizer {
+            Some(tokenizer) => counters.push(Box::new(TokenizedWords::new(tokenizer.clone()))),
+            None => counters.push(Box::new(AsciiWords::default())),
+        }
+    }
+    if opts.chars {
+        counters.push(Box::new(Chars::new(opts.invalid_utf8)));
+    }
+    if opts.max_line_length {
+        counters.push(Box::new(MaxLineLength::default()));
+    }
+    counters
+}
+
+/// Counts the lines terminated by an `Eol` across consecutive buffers, since a `\r\n` may be
+/// split between them.
+#[derive(Debug, Default)]
+pub(crate) struct LineCounter {
+    eol: Eol,
+    count_last_line: bool,
+    prev_cr: bool,
+    /// Whether the input so far is empty or ends with a terminator.
+    terminated: bool,
+    pub lines: u64,
+}
+
+impl LineCounter {
+    pub fn new(eol: Eol, count_last_line: bool) -> LineCounter {
+        LineCounter {
+            eol,
+            count_last_line,
+            terminated: true,
+            ..LineCounter::default()
+        }
+    }
+
+    /// The lines of the whole input, including an unterminated last line if it's counted.
+    pub fn finish(&self) -> u64 {
+        self.lines + u64::from(self.count_last_line && !self.terminated)
+    }
+
+    fn crlfs(&self, buffer: &[u8]) -> usize {
+        let split = usize::from(self.prev_cr && buffer.first() == Some(&b'\n'));
+        split + buffer.windows(2).filter(|pair| pair == b"\r\n").count()
+    }
+
+    pub fn update(&mut self, buffer: &[u8]) {
+        if buffer.is_empty() {
+            return;
+        }
+        let lines = match self.eol {
+            Eol::Lf => bytecount::count(buffer, b'\n'),
+            Eol::Cr => bytecount::count(buffer, b'\r'),
+            Eol::Crlf => self.crlfs(buffer),
+            // a \r\n is both a \r and a \n but only one line
+            Eol::Any => {
+                bytecount::count(buffer, b'\n') + bytecount::count(buffer, b'\r')
+                    - self.crlfs(buffer)
+            }
+            Eol::Byte(b) => bytecount::count(buffer, b),
+        };
+        self.lines += lines as u64;
+        let last = buffer[buffer.len() - 1];
+        self.terminated = match self.eol {
+            Eol::Lf => last == b'\n',
+            Eol::Cr => last == b'\r',
+            Eol::Crlf => {
+                last == b'\n' && (buffer.ends_with(b"\r\n") || buffer.len() == 1 && self.prev_cr)
+            }
+            Eol::Any => last == b'\n' || last == b'\r',
+            Eol::Byte(b) => last == b,
+        };
+        self.prev_cr = last == b'\r';
+    }
+}
+
+impl Counter for LineCounter {
+    fn update(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        LineCounter::update(self, chunk);
+        Ok(())
+    }
+
+    fn record(&self, counts: &mut Counts, finished: bool) {
+        let lines = if finished { self.finish() } else { self.lines };
+        counts.lines = Count { val: Some(lines) };
+    }
+}
+
+/// Words delimited by ascii whitespace, the default. Invalid utf-8 is part of a word.
+#[derive(Debug, Default)]
+pub(crate) struct AsciiWords {
+    words: u64,
+    in_word: bool,
+}
+
+impl Counter for AsciiWords {
+    fn update(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        for &b in chunk {
+            if b.is_ascii_whitespace() {
+                self.words += u64::from(self.in_word);
+                self.in_word = false;
+            } else {
+                self.in_word = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&self, counts: &mut Counts, _finished: bool) {
+        counts.words = Count {
+            val: Some(self.words + u64::from(self.in_word)),
+        };
+    }
+}
+
+/// Words split by a `Tokenizer`, which is given one line at a time.
+#[derive(Debug)]
+pub(crate) struct TokenizedWords {
+    tokenizer: Arc<dyn Tokenizer>,
+    words: u64,
+    /// A line which spans chunks.
+    line: Vec<u8>,
+}
+
+impl TokenizedWords {
+    pub fn new(tokenizer: Arc<dyn Tokenizer>) -> TokenizedWords {
+        TokenizedWords {
+            tokenizer,
+            words: 0,
+            line: Vec::new(),
+        }
+    }
+}
+
+impl Counter for TokenizedWords {
+    fn update(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        for piece in chunk.split_inclusive(|&b| b == b'\n') {
+            match piece.split_last() {
+                Some((b'\n', line)) if self.line.is_empty() => {
+                    self.words += self.tokenizer.count_words(line) as u64;
+                }
+                Some((b'\n', line)) => {
+                    self.line.extend_from_slice(line);
+                    self.words += self.tokenizer.count_words(&self.line) as u64;
+                    self.line.clear();
+                }
+                _ => self.line.extend_from_slice(piece),
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&self, counts: &mut Counts, _finished: bool) {
+        let last = self.tokenizer.count_words(&self.line) as u64;
+        counts.words = Count {
+            val: Some(self.words + last),
+        };
+    }
+}
+
+/// Utf-8 chars, with invalid sequences handled by an `InvalidUtf8` policy.
+#[derive(Debug)]
+pub(crate) struct Chars {
+    invalid_utf8: InvalidUtf8,
+    chars: u64,
+    /// The start of a char split between chunks, at most 3 bytes.
+    pending: Vec<u8>,
+}
+
+impl Chars {
+    pub fn new(invalid_utf8: InvalidUtf8) -> Chars {
+        Chars {
+            invalid_utf8,
+            chars: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    fn invalid(&mut self) -> Result<(), Error> {
+        match self.invalid_utf8 {
+            InvalidUtf8::Strict => Err(Error::UTF8()),
+            InvalidUtf8::Replace => {
+                self.chars += 1;
+                Ok(())
+            }
+            InvalidUtf8::Skip => Ok(()),
+        }
+    }
+
+    /// Count the chars of bytes and return the length of an incomplete char at its end.
+    fn decode(&mut self, bytes: &[u8]) -> Result<usize, Error> {
+        let mut chunks = bytes.utf8_chunks().peekable();
+        while let Some(chunk) = chunks.next() {
+            self.chars += bytecount::num_chars(chunk.valid().as_bytes()) as u64;
+            let invalid = chunk.invalid();
+            if invalid.is_empty() {
+                continue;
+            }
+            // only the last chunk can end with the start of a char rather than an invalid one
+            let last = chunks.peek().is_none();
+            match str::from_utf8(invalid) {
+                Err(err) if last && err.error_len().is_none() => return Ok(invalid.len()),
+                _ => self.invalid()?,
+            }
+        }
+        Ok(0)
+    }
+}
+
+impl Counter for Chars {
+    fn update(&mut self, mut chunk: &[u8]) -> Result<(), Error> {
+        if !self.pending.is_empty() {
+            // decode the pending start of a char with enough of chunk to complete it, then
+            // carry on from wherever that left off in chunk
+            let pending = self.pending.len();
+            let mut head = std::mem::take(&mut self.pending);
+            head.extend_from_slice(&chunk[..chunk.len().min(3)]);
+            let incomplete = self.decode(&head)?;
+            let decoded = head.len() - incomplete;
+            if decoded < pending {
+                self.pending = head;
+                return Ok(());
+            }
+            chunk = &chunk[decoded - pending..];
+        }
+        let incomplete = self.decode(chunk)?;
+        self.pending = chunk[chunk.len() - incomplete..].to_vec();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.pending.clear();
+        self.invalid()
+    }
+
+    fn record(&self, counts: &mut Counts, _finished: bool) {
+        counts.chars = Count {
+            val: Some(self.chars),
+        };
+    }
+}
+
+/// The columns of the longest line like `wc -L`, by East Asian width so wide chars like 東 are 2
+/// columns, and with a tab advancing to the next multiple of 8. A `\r` or form feed ends a line
+/// too, as in wc, so `\r\n` isn't counted.
+#[derive(Debug, Default)]
+pub(crate) struct MaxLineLength {
+    decoder: Decoder,
+    widest: Widest,
+}
+
+impl MaxLineLength {
+    fn add(widest: &mut Widest, c: char) {
+        widest.add(c, matches!(c, '\n' | '\r' | '\x0c'));
+    }
+}
+
+impl Counter for MaxLineLength {
+    fn update(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        let widest = &mut self.widest;
+        self.decoder
+            .decode(chunk, |c| MaxLineLength::add(widest, c));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let widest = &mut self.widest;
+        self.decoder.finish(|c| MaxLineLength::add(widest, c));
+        Ok(())
+    }
+
+    fn record(&self, counts: &mut Counts, _finished: bool) {
+        counts.max_line_length = Count {
+            val: Some(self.widest.max),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize::UnicodeWords;
+
+    /// Feed input to a fresh counter in chunks of size and record the result.
+    fn count(opts: &CountOptions, input: &[u8], size: usize) -> Result<Counts, Error> {
+        let mut counts = Counts {
+            bytes: Count { val: None },
+            chars: Count { val: None },
+            words: Count { val: None },
+            lines: Count { val: None },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        for mut counter in counters(opts) {
+            for chunk in input.chunks(size) {
+                counter.update(chunk)?;
+            }
+            counter.finish()?;
+            counter.record(&mut counts, true);
+        }
+        Ok(counts)
+    }
+
+    #[test]
+    fn test_counters_split_chunks() {
+        let input = "caf\u{e9} 東京\n😀 \u{a0}ok\nend".as_bytes();
+        let options = [
+            CountOptions::new()
+                .chars()
+                .words()
+                .lines()
+                .max_line_length(),
+            CountOptions::new().chars().words().tokenizer(UnicodeWords),
+        ];
+        for opts in &options {
+            let whole = count(opts, input, input.len()).unwrap();
+            assert_eq!(Some(17), whole.chars.val);
+            for size in 1..input.len() {
+                assert_eq!(whole, count(opts, input, size).unwrap(), "{}", size);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        let opts = CountOptions::new().max_line_length();
+        let input = "ab\tc\r\n東京東京東京東京東京\n\tx\u{301}".as_bytes();
+        for size in 1..input.len() + 1 {
+            let counts = count(&opts, input, size).unwrap();
+            // wide chars are 2 columns, like GNU wc
+            assert_eq!(Some(20), counts.max_line_length.val);
+        }
+        let counts = count(&opts, b"", 1).unwrap();
+        assert_eq!(Some(0), counts.max_line_length.val);
+    }
+
+    #[test]
+    fn test_chars_invalid_split_chunks() {
+        // an invalid \xff, a truncated \xe2\x82, and a truncated 4 byte char at the end
+        let input: &[u8] = b"a\xffb\xe2\x82c\xf0\x9f\x98";
+        for size in 1..input.len() + 1 {
+            let replace = CountOptions::new()
+                .chars()
+                .invalid_utf8(InvalidUtf8::Replace);
+            assert_eq!(Some(6), count(&replace, input, size).unwrap().chars.val);
+            let skip = CountOptions::new().chars().invalid_utf8(InvalidUtf8::Skip);
+            assert_eq!(Some(3), count(&skip, input, size).unwrap().chars.val);
+            let strict = CountOptions::new().chars();
+            assert!(matches!(count(&strict, input, size), Err(Error::UTF8())));
+        }
+    }
+}
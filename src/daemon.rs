@@ -0,0 +1,209 @@
+//! `rwc daemon`, which answers count queries over a unix socket from a cache, and the
+//! `rwc --ask` client. Cached counts are reused until the file's size or modification time
+//! changes.
+
+use crate::count::{count_all, Count, Counts};
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+/// One line sent by the client.
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    path: PathBuf,
+}
+
+/// One line sent back by the daemon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Response {
+    Counts {
+        bytes: u64,
+        /// None if the file isn't valid utf-8.
+        chars: Option<u64>,
+        words: u64,
+        lines: u64,
+    },
+    Error(String),
+}
+
+/// The metadata which invalidates a cached count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+type Cache = Arc<Mutex<HashMap<PathBuf, (Stamp, Response)>>>;
+
+/// `$RWC_SOCKET`, falling back to `$XDG_RUNTIME_DIR/rwc.sock` and then a per-user socket in the
+/// temp directory.
+pub fn default_socket() -> PathBuf {
+    if let Some(socket) = env::var_os("RWC_SOCKET") {
+        return PathBuf::from(socket);
+    }
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("rwc.sock"),
+        _ => env::temp_dir().join(format!("rwc-{}.sock", unsafe { libc::getuid() })),
+    }
+}
+
+fn count(path: &Path) -> Response {
+    match count_all(path) {
+        Ok(counts) => Response::Counts {
+            bytes: counts.bytes.val.unwrap_or(0),
+            chars: counts.chars.val,
+            words: counts.words.val.unwrap_or(0),
+            lines: counts.lines.val.unwrap_or(0),
+        },
+        Err(err) => Response::Error(err.plain_report()),
+    }
+}
+
+fn lookup(cache: &Cache, path: &Path) -> Response {
+    let stamp = match fs::metadata(path) {
+        Ok(meta) => Stamp {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        },
+        Err(err) => return Response::Error(Error::from(err).plain_report()),
+    };
+    if let Some((cached, res)) = cache.lock().unwrap().get(path) {
+        if *cached == stamp {
+            return res.clone();
+        }
+    }
+    // count without holding the lock so other clients aren't blocked
+    let res = count(path);
+    cache
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), (stamp, res.clone()));
+    res
+}
+
+fn handle(cache: Cache, stream: UnixStream) -> Result<(), Error> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let res = match serde_json::from_str::<Request>(&line?) {
+            Ok(req) => lookup(&cache, &req.path),
+            Err(err) => Response::Error(format!("invalid request: {}", err)),
+        };
+        let res = serde_json::to_string(&res).map_err(|err| Error::CUSTOM(err.to_string()))?;
+        writeln!(writer, "{}", res)?;
+    }
+    Ok(())
+}
+
+/// Answer queries on listener until it fails, each connection on its own thread.
+pub fn serve(listener: UnixListener) -> Result<(), Error> {
+    let cache: Cache = Arc::default();
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let cache = Arc::clone(&cache);
+        thread::spawn(move || {
+            if let Err(err) = handle(cache, stream) {
+                eprintln!("{}", err.report());
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Bind socket, replacing a stale socket file left by a daemon which is no longer running.
+pub fn bind(socket: &Path) -> Result<UnixListener, Error> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(Error::for_path(
+                socket,
+                String::from("a daemon is already listening").into(),
+            ));
+        }
+        fs::remove_file(socket)?;
+    }
+    UnixListener::bind(socket).map_err(|err| Error::for_path(socket, err.into()))
+}
+
+/// Ask the daemon listening on socket for the counts of path.
+pub fn ask(socket: &Path, path: &Path) -> Result<Counts, Error> {
+    let path = path.canonicalize()?;
+    let mut stream = UnixStream::connect(socket).map_err(|err| {
+        Error::for_path(
+            socket,
+            Error::CUSTOM(format!("cannot connect to rwc daemon: {}", err)),
+        )
+    })?;
+    let req =
+        serde_json::to_string(&Request { path }).map_err(|err| Error::CUSTOM(err.to_string()))?;
+    writeln!(stream, "{}", req)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    match serde_json::from_str(&line) {
+        Ok(Response::Counts {
+            bytes,
+            chars,
+            words,
+            lines,
+        }) => Ok(Counts {
+            bytes: Count { val: Some(bytes) },
+            chars: Count { val: chars },
+            words: Count { val: Some(words) },
+            lines: Count { val: Some(lines) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        }),
+        Ok(Response::Error(err)) => Err(Error::CUSTOM(err)),
+        Err(err) => Err(Error::CUSTOM(format!(
+            "invalid response from daemon: {}",
+            err
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ask() {
+        let dir = env::temp_dir().join(format!("rwc-daemon-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("rwc.sock");
+        let file = dir.join("notes.txt");
+        fs::write(&file, "one two\n").unwrap();
+
+        let listener = bind(&socket).unwrap();
+        thread::spawn(move || serve(listener));
+        assert!(bind(&socket).is_err());
+
+        let counts = ask(&socket, &file).unwrap();
+        assert_eq!(Some(8), counts.bytes.val);
+        assert_eq!(Some(8), counts.chars.val);
+        assert_eq!(Some(2), counts.words.val);
+        assert_eq!(Some(1), counts.lines.val);
+
+        // the size changed so the cached count is stale
+        fs::write(&file, "one two\nthree\n").unwrap();
+        let counts = ask(&socket, &file).unwrap();
+        assert_eq!(Some(14), counts.bytes.val);
+        assert_eq!(Some(3), counts.words.val);
+
+        fs::write(&file, b"\xff\n").unwrap();
+        let counts = ask(&socket, &file).unwrap();
+        assert_eq!(Some(2), counts.bytes.val);
+        assert_eq!(None, counts.chars.val);
+
+        fs::remove_file(&file).unwrap();
+        assert!(ask(&socket, &file).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,60 @@
+//! `--detect-lang`, the natural language of each file, so the counts of a multilingual corpus
+//! can be split by language.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+
+pub const LANGUAGE: &str = "language";
+
+/// Bytes from the start of the input which are looked at, plenty to tell languages apart.
+const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// The ISO 639-3 code of the language of the start of the input, e.g. `eng`, with a `?` if
+/// the detection isn't reliable, or `unknown`.
+#[derive(Debug, Default)]
+pub struct DetectLang {
+    sample: Vec<u8>,
+}
+
+impl Metric for DetectLang {
+    fn update(&mut self, chunk: &[u8]) {
+        let n = chunk.len().min(SAMPLE_SIZE - self.sample.len());
+        self.sample.extend_from_slice(&chunk[..n]);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        let text = String::from_utf8_lossy(&self.sample);
+        let lang = match whatlang::detect(&text) {
+            Some(info) if info.is_reliable() => info.lang().code().to_string(),
+            Some(info) => format!("{}?", info.lang().code()),
+            None => String::from("unknown"),
+        };
+        MetricValue::Text(lang)
+    }
+}
+
+/// The `LANGUAGE` column.
+pub fn detect_lang_metric() -> MetricFactory {
+    MetricFactory::new(LANGUAGE, DetectLang::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(text: &str) -> MetricValue {
+        let mut metric = DetectLang::default();
+        metric.update(text.as_bytes());
+        metric.finalize()
+    }
+
+    #[test]
+    fn test_detect_lang() {
+        let english = "The quick brown fox jumps over the lazy dog while the farmer watches \
+                       from the porch and wonders where the afternoon has gone.";
+        assert_eq!(MetricValue::Text(String::from("eng")), detect(english));
+        let german = "Der schnelle braune Fuchs springt über den faulen Hund, während der \
+                      Bauer von der Veranda aus zusieht und sich fragt, wo der Nachmittag blieb.";
+        assert_eq!(MetricValue::Text(String::from("deu")), detect(german));
+        assert_eq!(MetricValue::Text(String::from("unknown")), detect(""));
+    }
+}
@@ -0,0 +1,130 @@
+//! `--line-endings`, the `\n`, `\r\n`, and lone `\r` line endings of each file, so files with
+//! mixed line endings stand out.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+
+pub const LF: &str = "lf";
+pub const CRLF: &str = "crlf";
+pub const CR: &str = "cr";
+
+/// Line endings by kind, a `\r\n` is only a crlf and not also a cr and an lf.
+#[derive(Debug, Default)]
+struct Endings {
+    lf: u64,
+    crlf: u64,
+    cr: u64,
+    /// Whether the last byte was a `\r`, which may be followed by a `\n` in the next chunk.
+    prev_cr: bool,
+}
+
+impl Endings {
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            match b {
+                b'\n' if self.prev_cr => self.crlf += 1,
+                b'\n' => self.lf += 1,
+                _ => self.cr += u64::from(self.prev_cr),
+            }
+            self.prev_cr = b == b'\r';
+        }
+    }
+
+    fn finish(&mut self) {
+        self.cr += u64::from(self.prev_cr);
+        self.prev_cr = false;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LfEndings(Endings);
+
+impl Metric for LfEndings {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        MetricValue::Count(self.0.lf)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CrlfEndings(Endings);
+
+impl Metric for CrlfEndings {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        MetricValue::Count(self.0.crlf)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CrEndings(Endings);
+
+impl Metric for CrEndings {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        MetricValue::Count(self.0.cr)
+    }
+}
+
+/// The `LF`, `CRLF`, and `CR` columns.
+pub fn line_endings_metrics() -> Vec<MetricFactory> {
+    vec![
+        MetricFactory::new(LF, LfEndings::default),
+        MetricFactory::new(CRLF, CrlfEndings::default),
+        MetricFactory::new(CR, CrEndings::default),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endings(input: &[u8], chunk: usize) -> (u64, u64, u64) {
+        let mut endings = Endings::default();
+        for chunk in input.chunks(chunk) {
+            endings.update(chunk);
+        }
+        endings.finish();
+        (endings.lf, endings.crlf, endings.cr)
+    }
+
+    #[test]
+    fn test_endings() {
+        let input = b"a\r\nb\rc\nd\r\r\n\n\re\r";
+        for chunk in 1..input.len() {
+            assert_eq!((2, 2, 4), endings(input, chunk));
+        }
+        assert_eq!((0, 0, 0), endings(b"", 64));
+    }
+
+    #[test]
+    fn test_line_endings_metrics() {
+        let values: Vec<_> = line_endings_metrics()
+            .iter()
+            .map(|factory| {
+                let mut metric = factory.create();
+                metric.update(b"a\nb\r\nc\n");
+                metric.finalize()
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                MetricValue::Count(2),
+                MetricValue::Count(1),
+                MetricValue::Count(0),
+            ],
+            values
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! `--entropy`, the Shannon entropy of the bytes of each file, close to 8 bits per byte for
+//! compressed or encrypted blobs and well under for text.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+
+pub const ENTROPY: &str = "entropy";
+
+/// Occurrences of each byte value.
+#[derive(Debug)]
+pub struct Entropy {
+    counts: [u64; 256],
+}
+
+impl Default for Entropy {
+    fn default() -> Entropy {
+        Entropy { counts: [0; 256] }
+    }
+}
+
+impl Metric for Entropy {
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            self.counts[usize::from(b)] += 1;
+        }
+    }
+
+    /// In bits per byte, from 0 for a file of one repeated byte or an empty file to 8.
+    fn finalize(&mut self) -> MetricValue {
+        let total: u64 = self.counts.iter().sum();
+        if total == 0 {
+            return MetricValue::Float(0.0);
+        }
+        let entropy = self
+            .counts
+            .iter()
+            .filter(|&&n| n > 0)
+            .map(|&n| {
+                let p = n as f64 / total as f64;
+                p * (1.0 / p).log2()
+            })
+            .sum();
+        MetricValue::Float(entropy)
+    }
+}
+
+/// The `ENTROPY` column.
+pub fn entropy_metric() -> MetricFactory {
+    MetricFactory::new(ENTROPY, Entropy::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entropy(input: &[u8]) -> MetricValue {
+        let mut metric = entropy_metric().create();
+        metric.update(input);
+        metric.finalize()
+    }
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(MetricValue::Float(0.0), entropy(b""));
+        assert_eq!(MetricValue::Float(0.0), entropy(b"aaaa"));
+        assert_eq!(MetricValue::Float(1.0), entropy(b"abab"));
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(MetricValue::Float(8.0), entropy(&all));
+    }
+}
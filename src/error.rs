@@ -1,6 +1,10 @@
+use crate::console;
+use crate::count::Counts;
 use colored::*;
+use std::error;
 use std::fmt;
 use std::io;
+use std::path::{Path, PathBuf};
 use std::string::{FromUtf8Error, String};
 use utf8::BufReadDecoderError;
 
@@ -12,23 +16,150 @@ pub enum Error {
     MANY(Vec<Error>),
     CUSTOM(String),
     PARSEFORMAT(String),
+    PARSETOKENIZER(String),
+    FORPATH {
+        path: PathBuf,
+        source: Box<Error>,
+    },
+    PARTIAL {
+        counts: Box<Counts>,
+        source: Box<Error>,
+    },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, true)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::IO(err) => Some(err),
+            Error::FORPATH { source, .. } | Error::PARTIAL { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// Attach the path which caused err.
+    pub fn for_path<P: AsRef<Path>>(path: P, err: Error) -> Error {
+        Error::FORPATH {
+            path: path.as_ref().to_path_buf(),
+            source: Box::new(err),
+        }
+    }
+
+    /// Attach the counts accumulated before err interrupted counting.
+    pub fn partial(counts: Counts, err: Error) -> Error {
+        Error::PARTIAL {
+            counts: Box::new(counts),
+            source: Box::new(err),
+        }
+    }
+
+    /// The error which caused this one, looking through paths and partial counts.
+    pub fn cause(&self) -> &Error {
+        match self {
+            Error::FORPATH { source, .. } | Error::PARTIAL { source, .. } => source.cause(),
+            _ => self,
+        }
+    }
+
+    /// The counts accumulated before this error, if there are any.
+    pub fn partial_counts(&self) -> Option<&Counts> {
+        match self {
+            Error::FORPATH { source, .. } => source.partial_counts(),
+            Error::PARTIAL { counts, .. } => Some(counts),
+            _ => None,
+        }
+    }
+
+    /// The report without colors, for messages which don't end up on a terminal.
+    pub fn plain_report(&self) -> String {
+        Report {
+            err: self,
+            color: false,
+        }
+        .to_string()
+    }
+
+    /// Display the error followed by each of its sources.
+    pub fn report(&self) -> Report<'_> {
+        Report {
+            err: self,
+            color: true,
+        }
+    }
+
+    /// Write the error, with its heading in red if color. Colors are chosen here rather than by
+    /// overriding them globally, which would race with other threads printing errors.
+    fn write(&self, f: &mut fmt::Formatter<'_>, color: bool) -> fmt::Result {
+        let heading = |s: &str| if color { s.red().bold() } else { s.normal() };
         match self {
-            Error::IO(err) => write!(f, "{}: {}", "IO Error".red().bold(), err),
-            Error::UTF8() => write!(f, "{}", "UTF-8 Error".red().bold()),
+            Error::IO(_) => write!(f, "{}", heading("IO Error")),
+            Error::UTF8() => write!(f, "{}", heading("UTF-8 Error")),
             Error::PATH(v) => write!(
                 f,
                 "{}: {}",
-                "Invalid Path".red().bold(),
+                heading("Invalid Path"),
                 String::from_utf8_lossy(v)
             ),
-            Error::MANY(errs) => write!(f, "{}: {:?}", "Errors".red().bold(), errs),
-            Error::CUSTOM(s) => write!(f, "{}: {}", "Error".red().bold(), s),
-            Error::PARSEFORMAT(s) => write!(f, "{}: {}", "Error Parsing --format".red().bold(), s),
+            Error::MANY(errs) => {
+                write!(f, "{}:", heading("Errors"))?;
+                for err in errs {
+                    write!(f, "\n  {}", Report { err, color })?;
+                }
+                Ok(())
+            }
+            Error::CUSTOM(s) => write!(f, "{}: {}", heading("Error"), s),
+            Error::PARSEFORMAT(s) => write!(f, "{}: {}", heading("Error Parsing --format"), s),
+            Error::PARSETOKENIZER(s) => {
+                write!(f, "{}: {}", heading("Error Parsing --tokenizer"), s)
+            }
+            Error::FORPATH { path, .. } if color => {
+                write!(f, "{}", console::display_path(path).bold())
+            }
+            Error::FORPATH { path, .. } => write!(f, "{}", console::display_path(path)),
+            Error::PARTIAL { counts, .. } => {
+                write!(f, "{} after {} bytes", heading("Stopped"), counts.bytes)
+            }
+        }
+    }
+}
+
+/// Displays an error and its chain of sources separated by `: `, e.g.
+/// `IO Error: No such file or directory (os error 2)`.
+pub struct Report<'a> {
+    err: &'a (dyn error::Error + 'static),
+    color: bool,
+}
+
+impl Report<'_> {
+    fn write_one(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        err: &(dyn error::Error + 'static),
+    ) -> fmt::Result {
+        match err.downcast_ref::<Error>() {
+            Some(err) => err.write(f, self.color),
+            None => write!(f, "{}", err),
+        }
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_one(f, self.err)?;
+        let mut source = self.err.source();
+        while let Some(err) = source {
+            write!(f, ": ")?;
+            self.write_one(f, err)?;
+            source = err.source();
         }
+        Ok(())
     }
 }
 
@@ -64,3 +195,46 @@ impl<'a> From<BufReadDecoderError<'a>> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_source_and_report() {
+        let err = Error::from(io::Error::new(io::ErrorKind::NotFound, "gone"));
+        assert_eq!("gone", err.source().unwrap().to_string());
+        assert_eq!(format!("{}: gone", err), err.report().to_string());
+
+        let report = err.report().to_string();
+        let err = Error::for_path("foo.txt", err);
+        assert_eq!(format!("{}: {}", err, report), err.report().to_string());
+        let report = err.report().to_string();
+
+        let err = Error::from(vec![err, Error::UTF8()]);
+        assert!(err.source().is_none());
+        assert_eq!(
+            format!(
+                "{}:\n  {}\n  {}",
+                "Errors".red().bold(),
+                report,
+                Error::UTF8()
+            ),
+            err.report().to_string()
+        );
+    }
+
+    #[test]
+    fn test_plain_report() {
+        let gone = Error::from(io::Error::new(io::ErrorKind::NotFound, "gone"));
+        let err = Error::from(vec![
+            Error::for_path("foo.txt", gone),
+            Error::CUSTOM(String::from("bad")),
+        ]);
+        assert_eq!(
+            "Errors:\n  foo.txt: IO Error: gone\n  Error: bad",
+            err.plain_report()
+        );
+    }
+}
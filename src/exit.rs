@@ -0,0 +1,45 @@
+//! The exit codes of `rwc`, so wrapper scripts can tell failures apart.
+
+use crate::error::Error;
+use std::path::PathBuf;
+
+pub const SUCCESS: i32 = 0;
+/// Invalid arguments, or an error which stopped the whole run, e.g. an unreadable
+/// `--files0-from`.
+pub const USAGE: i32 = 1;
+/// Some, but not all, files couldn't be counted or only partially.
+pub const SOME_FAILED: i32 = 2;
+/// A check failed, e.g. `rwc hook` found a file over the limits or `rwc compare` found
+/// differences.
+pub const ASSERTION_FAILED: i32 = 3;
+/// None of the files could be counted.
+pub const ALL_FAILED: i32 = 4;
+/// Interrupted by Ctrl-C, like shells report for SIGINT.
+pub const INTERRUPTED: i32 = 130;
+
+/// The exit code for the results of counting files.
+pub fn for_results<T>(results: &[(Result<T, Error>, PathBuf)]) -> i32 {
+    let failed = results.iter().filter(|(res, _)| res.is_err()).count();
+    if failed == 0 {
+        SUCCESS
+    } else if failed == results.len() {
+        ALL_FAILED
+    } else {
+        SOME_FAILED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_results() {
+        let ok = || (Ok(()), PathBuf::from("ok"));
+        let err = || (Err(Error::UTF8()), PathBuf::from("err"));
+        assert_eq!(SUCCESS, for_results::<()>(&[]));
+        assert_eq!(SUCCESS, for_results(&[ok(), ok()]));
+        assert_eq!(SOME_FAILED, for_results(&[ok(), err()]));
+        assert_eq!(ALL_FAILED, for_results(&[err(), err()]));
+    }
+}
@@ -0,0 +1,120 @@
+//! Bounds the number of files counted at once so large parallel runs stay under the limit of
+//! open file descriptors.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Descriptors left for stdio, sockets, and whatever else the process has open.
+#[cfg(unix)]
+const RESERVED: usize = 16;
+
+/// The soft `RLIMIT_NOFILE` less some reserved descriptors, or `None` if there is no limit.
+#[cfg(unix)]
+pub fn default_max_open() -> Option<usize> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0
+        || limit.rlim_cur == libc::RLIM_INFINITY
+    {
+        return None;
+    }
+    Some((limit.rlim_cur as usize).saturating_sub(RESERVED).max(1))
+}
+
+#[cfg(not(unix))]
+pub fn default_max_open() -> Option<usize> {
+    None
+}
+
+/// A counting semaphore of open files, callers wait in `acquire` once max are open.
+#[derive(Debug)]
+pub struct OpenFiles {
+    max: usize,
+    open: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Permission to have one file open, released when dropped. It can move to another thread along
+/// with the file.
+#[derive(Debug)]
+pub struct Permit(Arc<OpenFiles>);
+
+impl OpenFiles {
+    pub fn new(max: usize) -> OpenFiles {
+        OpenFiles {
+            max: max.max(1),
+            open: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn acquire(self: &Arc<Self>) -> Permit {
+        let mut open = self.open.lock().unwrap();
+        while *open >= self.max {
+            open = self.released.wait(open).unwrap();
+        }
+        *open += 1;
+        Permit(Arc::clone(self))
+    }
+
+    /// Like `acquire` but gives up after timeout, since the holders may never finish, e.g.
+    /// counts stuck opening FIFOs.
+    pub fn acquire_timeout(self: &Arc<Self>, timeout: Duration) -> Option<Permit> {
+        let open = self.open.lock().unwrap();
+        let (mut open, res) = self
+            .released
+            .wait_timeout_while(open, timeout, |open| *open >= self.max)
+            .unwrap();
+        if res.timed_out() {
+            return None;
+        }
+        *open += 1;
+        Some(Permit(Arc::clone(self)))
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        *self.0.open.lock().unwrap() -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_open_files() {
+        let files = Arc::new(OpenFiles::new(2));
+        let (current, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let _permit = files.acquire();
+                    let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    current.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(2, peak.load(Ordering::SeqCst));
+
+        let _permits = (files.acquire(), files.acquire());
+        assert!(files.acquire_timeout(Duration::from_millis(10)).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_default_max_open() {
+        if let Some(max) = default_max_open() {
+            assert!(max >= 1);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! C bindings for the counting functions, see include/rwc.h. Build a shared library with
+//! `cargo rustc --release --lib --features rwc-ffi --crate-type cdylib`.
+
+use crate::count::{Count, CountOptions, Countable, CountablePath, Counts, Eol, InvalidUtf8};
+use crate::error::Error;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::path::PathBuf;
+use std::ptr;
+use std::slice;
+
+pub const RWC_OK: c_int = 0;
+pub const RWC_ERR_NULL: c_int = 1;
+pub const RWC_ERR_IO: c_int = 2;
+pub const RWC_ERR_UTF8: c_int = 3;
+pub const RWC_ERR_PATH: c_int = 4;
+pub const RWC_ERR_OTHER: c_int = 5;
+
+/// Value stored in `rwc_counts` for counts which weren't computed.
+pub const RWC_COUNT_NA: u64 = u64::MAX;
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy)]
+pub struct rwc_count_options {
+    pub bytes: bool,
+    pub chars: bool,
+    pub words: bool,
+    pub lines: bool,
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy)]
+pub struct rwc_counts {
+    pub bytes: u64,
+    pub chars: u64,
+    pub words: u64,
+    pub lines: u64,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+impl From<rwc_count_options> for CountOptions {
+    fn from(opts: rwc_count_options) -> CountOptions {
+        CountOptions {
+            bytes: opts.bytes,
+            chars: opts.chars,
+            words: opts.words,
+            lines: opts.lines,
+            max_line_length: false,
+            tokenizer: None,
+            metrics: Vec::new(),
+            invalid_utf8: InvalidUtf8::Strict,
+            eol: Eol::Lf,
+            count_last_line: false,
+            no_stat_fastpath: false,
+            deadline: None,
+        }
+    }
+}
+
+impl From<Counts> for rwc_counts {
+    fn from(counts: Counts) -> rwc_counts {
+        fn raw(count: Count) -> u64 {
+            count.val.unwrap_or(RWC_COUNT_NA)
+        }
+        rwc_counts {
+            bytes: raw(counts.bytes),
+            chars: raw(counts.chars),
+            words: raw(counts.words),
+            lines: raw(counts.lines),
+        }
+    }
+}
+
+fn set_last_error(err: &Error) {
+    let msg = CString::new(err.plain_report()).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(msg));
+}
+
+fn error_code(err: &Error) -> c_int {
+    match err.cause() {
+        Error::IO(_) => RWC_ERR_IO,
+        Error::UTF8() => RWC_ERR_UTF8,
+        Error::PATH(_) => RWC_ERR_PATH,
+        _ => RWC_ERR_OTHER,
+    }
+}
+
+fn finish(res: Result<Counts, Error>, out_counts: *mut rwc_counts) -> c_int {
+    match res {
+        Ok(counts) => {
+            unsafe { *out_counts = counts.into() };
+            RWC_OK
+        }
+        Err(err) => {
+            set_last_error(&err);
+            error_code(&err)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> Result<PathBuf, Error> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    Ok(PathBuf::from(OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> Result<PathBuf, Error> {
+    Ok(PathBuf::from(String::from_utf8(bytes.to_vec())?))
+}
+
+/// Count the file at the null terminated `path` and store the result in `out_counts`.
+///
+/// # Safety
+///
+/// `path` must be a valid null terminated string and `out_counts` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rwc_count_file(
+    path: *const c_char,
+    options: rwc_count_options,
+    out_counts: *mut rwc_counts,
+) -> c_int {
+    if path.is_null() || out_counts.is_null() {
+        return RWC_ERR_NULL;
+    }
+    let res = path_from_bytes(CStr::from_ptr(path).to_bytes())
+        .and_then(|path| path.count_with(options.into()));
+    finish(res, out_counts)
+}
+
+/// Count the `len` bytes starting at `data` and store the result in `out_counts`.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes and `out_counts` must be valid for writes.
+/// `data` may be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn rwc_count_buffer(
+    data: *const u8,
+    len: usize,
+    options: rwc_count_options,
+    out_counts: *mut rwc_counts,
+) -> c_int {
+    if (data.is_null() && len > 0) || out_counts.is_null() {
+        return RWC_ERR_NULL;
+    }
+    let data = if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    };
+    finish(data.count_with(options.into()), out_counts)
+}
+
+/// Message for the last error returned on this thread, or null if there hasn't been one. The
+/// string is owned by rwc and is valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn rwc_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(msg) => msg.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> rwc_count_options {
+        rwc_count_options {
+            bytes: true,
+            chars: true,
+            words: true,
+            lines: true,
+        }
+    }
+
+    #[test]
+    fn test_rwc_count_file() {
+        let path = CString::new("test_data/default.txt").unwrap();
+        let mut counts = rwc_counts {
+            bytes: 0,
+            chars: 0,
+            words: 0,
+            lines: 0,
+        };
+        let options = rwc_count_options {
+            bytes: true,
+            chars: false,
+            words: true,
+            lines: true,
+        };
+        let code = unsafe { rwc_count_file(path.as_ptr(), options, &mut counts) };
+        assert_eq!(RWC_OK, code);
+        assert_eq!(1048697, counts.bytes);
+        assert_eq!(RWC_COUNT_NA, counts.chars);
+        assert_eq!(183155, counts.words);
+        assert_eq!(20681, counts.lines);
+
+        let path = CString::new("test_data/does_not_exist.txt").unwrap();
+        let code = unsafe { rwc_count_file(path.as_ptr(), all(), &mut counts) };
+        assert_eq!(RWC_ERR_IO, code);
+        assert!(!rwc_last_error_message().is_null());
+    }
+
+    #[test]
+    fn test_rwc_count_buffer() {
+        let data = "hello😀 world\n".as_bytes();
+        let mut counts = rwc_counts {
+            bytes: 0,
+            chars: 0,
+            words: 0,
+            lines: 0,
+        };
+        let code = unsafe { rwc_count_buffer(data.as_ptr(), data.len(), all(), &mut counts) };
+        assert_eq!(RWC_OK, code);
+        assert_eq!(16, counts.bytes);
+        assert_eq!(13, counts.chars);
+        assert_eq!(2, counts.words);
+        assert_eq!(1, counts.lines);
+
+        let invalid = b"\xff";
+        let code = unsafe { rwc_count_buffer(invalid.as_ptr(), invalid.len(), all(), &mut counts) };
+        assert_eq!(RWC_ERR_UTF8, code);
+
+        let code = unsafe { rwc_count_buffer(ptr::null(), 1, all(), &mut counts) };
+        assert_eq!(RWC_ERR_NULL, code);
+    }
+}
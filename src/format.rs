@@ -1,15 +1,291 @@
+use crate::count::{parse_count_kind, CountKind};
 use crate::error::Error;
+use crate::print::{self, Printer};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
-pub fn parse_format(src: &str) -> Result<Format, Error> {
-    match src {
-        "table" => Ok(Format::Table),
-        "csv" => Ok(Format::CSV),
-        _ => Err(Error::PARSEFORMAT(src.into())),
+/// A `--format` or the format of a `--tee` by name, looked up once the `Cli` is parsed with the
+/// `Formats` of the binary, see `Cli::from_iter_with_formats`.
+pub fn parse_format(src: &str) -> Format {
+    Format::Named(String::from(src), None)
+}
+
+/// The formats `--format` and `--tee` accept by name. Library users can add their own printers
+/// with `register` and parse a `Cli` with them by `Cli::from_iter_with_formats`.
+#[derive(Debug, Clone)]
+pub struct Formats(BTreeMap<String, Arc<dyn Printer>>);
+
+impl Default for Formats {
+    fn default() -> Formats {
+        let mut formats = Formats(BTreeMap::new());
+        formats
+            .register("table", print::TablePrinter)
+            .register("csv", print::CsvPrinter)
+            .register("json", print::JsonPrinter)
+            .register("xml", print::XmlPrinter)
+            .register("wc", print::WcPrinter)
+            .register("prometheus", print::PrometheusPrinter)
+            .register("org", print::OrgPrinter)
+            .register("latex", print::LatexPrinter);
+        #[cfg(feature = "arrow")]
+        formats.register("arrow", print::ArrowPrinter);
+        #[cfg(feature = "parquet")]
+        formats.register("parquet", crate::parquet::ParquetPrinter);
+        #[cfg(feature = "proto")]
+        formats.register("proto", crate::proto::ProtoPrinter);
+        formats
     }
 }
 
-#[derive(Debug)]
+impl Formats {
+    /// Name printer, replacing any format already called name.
+    pub fn register<P: Printer + 'static>(&mut self, name: &str, printer: P) -> &mut Formats {
+        self.0.insert(String::from(name), Arc::new(printer));
+        self
+    }
+
+    /// The names of the formats in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    pub fn parse(&self, src: &str) -> Result<Format, Error> {
+        match self.0.get(src) {
+            Some(printer) => Ok(Format::Named(String::from(src), Some(printer.clone()))),
+            None => Err(Error::PARSEFORMAT(src.into())),
+        }
+    }
+}
+
+/// Parse a byte size with an optional binary K, M, or G suffix, e.g. `1M` is 1048576 bytes.
+pub fn parse_size(src: &str) -> Result<u64, Error> {
+    let (digits, multiplier) = match src.char_indices().last() {
+        Some((i, 'K')) | Some((i, 'k')) => (&src[..i], 1 << 10),
+        Some((i, 'M')) | Some((i, 'm')) => (&src[..i], 1 << 20),
+        Some((i, 'G')) | Some((i, 'g')) => (&src[..i], 1 << 30),
+        _ => (src, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| Error::CUSTOM(format!("invalid size: {}", src)))
+}
+
+/// A single character other than a quote or line break, with `\t` for a tab.
+pub fn parse_delimiter(src: &str) -> Result<char, Error> {
+    let mut chars = src.chars();
+    match (src, chars.next(), chars.next()) {
+        ("\\t", _, _) => Ok('\t'),
+        (_, Some(c), None) if !matches!(c, '"' | '\n' | '\r') => Ok(c),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --delimiter {}, expected a character other than a quote or line break",
+            src
+        ))),
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Format {
-    Table,
-    CSV,
+    /// A format by the name `--format` or `--tee` gave, with its printer once it's looked up in
+    /// a `Formats`, see `resolve`. One which never was is printed by the default formats.
+    Named(String, Option<Arc<dyn Printer>>),
+    /// A line per file from `--format-template`.
+    Template(Template),
+    /// NUL terminated fields from `--print0`, see `print::print0`.
+    Null,
+}
+
+impl Format {
+    /// The name `--format` or `--tee` gave, `None` for `--format-template` and `--print0`.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Format::Named(name, _) => Some(name),
+            Format::Template(_) | Format::Null => None,
+        }
+    }
+
+    /// Look up the printer of a named format in formats.
+    pub fn resolve(self, formats: &Formats) -> Result<Format, Error> {
+        match self {
+            Format::Named(name, _) => formats.parse(&name),
+            fmt => Ok(fmt),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Path,
+    Count(CountKind),
+}
+
+/// A line like `{path}: {lines} lines` with placeholders for the path and each count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template(Vec<Piece>);
+
+/// Placeholders are `{path}`, `{bytes}`, `{chars}`, `{words}`, and `{lines}`, and `{{` and `}}`
+/// are literal braces.
+pub fn parse_template(src: &str) -> Result<Template, Error> {
+    let invalid =
+        |reason: &str| Error::CUSTOM(format!("invalid --format-template {}, {}", src, reason));
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = src.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest.find('}').ok_or_else(|| invalid("unclosed {"))?;
+                let piece = match &rest[..end] {
+                    "path" => Piece::Path,
+                    name => Piece::Count(parse_count_kind(name).map_err(|_| {
+                        invalid(&format!(
+                            "unknown placeholder {{{}}}, expected path, bytes, chars, words, or lines",
+                            name
+                        ))
+                    })?),
+                };
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(piece);
+                chars = rest[end + 1..].chars();
+            }
+            '}' => return Err(invalid("unmatched }, write }} for a brace")),
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(Template(pieces))
+}
+
+impl Template {
+    /// Whether the template has a placeholder for kind.
+    pub fn uses(&self, kind: CountKind) -> bool {
+        self.0.contains(&Piece::Count(kind))
+    }
+
+    /// The line for path with each count from count.
+    pub fn render<F: Fn(CountKind) -> String>(&self, path: &str, count: F) -> String {
+        let mut line = String::new();
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(text) => line.push_str(text),
+                Piece::Path => line.push_str(path),
+                Piece::Count(kind) => line.push_str(&count(*kind)),
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Options};
+    use crate::count::Counts;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    #[derive(Debug)]
+    struct Paths;
+
+    impl Printer for Paths {
+        fn print(
+            &self,
+            results: &[(Result<Counts, Error>, PathBuf)],
+            _: &Options,
+            w: &mut dyn Write,
+        ) -> Result<(), Error> {
+            for (_, path) in results {
+                writeln!(w, "{}", path.display())?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_formats() {
+        let mut formats = Formats::default();
+        assert_eq!(Some("csv"), formats.parse("csv").unwrap().name());
+        assert!(formats.parse("paths").is_err());
+        formats.register("paths", Paths);
+        assert!(formats.names().any(|name| name == "paths"));
+        assert!(matches!(
+            formats.parse("paths").unwrap(),
+            Format::Named(_, Some(_))
+        ));
+        assert!(parse_format("paths").resolve(&formats).is_ok());
+        assert!(parse_format("paths").resolve(&Formats::default()).is_err());
+    }
+
+    #[test]
+    fn test_cli_with_formats() {
+        let mut formats = Formats::default();
+        formats.register("paths", Paths);
+        let args = ["rwc", "--format", "paths", "--tee", "csv=a.csv"];
+        let cli = Cli::from_iter_safe_with_formats(&args, &formats).unwrap();
+        assert!(matches!(cli.format, Format::Named(_, Some(_))));
+        assert_eq!(Some("paths"), cli.format.name());
+        assert_eq!(Some("csv"), cli.tee[0].format.name());
+
+        let results = vec![(Err(Error::UTF8()), PathBuf::from("a.txt"))];
+        let mut out = Vec::new();
+        let opts = Options::from(&cli);
+        crate::print::print(cli.format, &results, &opts, &mut out).unwrap();
+        assert_eq!("a.txt\n", String::from_utf8(out).unwrap());
+
+        assert!(Cli::from_iter_safe_with_formats(&args, &Formats::default()).is_err());
+        let args = ["rwc", "--tee", "bogus=a.csv"];
+        assert!(Cli::from_iter_safe_with_formats(&args, &formats).is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(12, parse_size("12").unwrap());
+        assert_eq!(2048, parse_size("2K").unwrap());
+        assert_eq!(1048576, parse_size("1M").unwrap());
+        assert_eq!(3 << 30, parse_size("3g").unwrap());
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("1.5M").is_err());
+        assert!(parse_size("").is_err());
+    }
+
+    #[test]
+    fn test_parse_delimiter() {
+        assert_eq!(';', parse_delimiter(";").unwrap());
+        assert_eq!('\t', parse_delimiter("\\t").unwrap());
+        assert!(parse_delimiter("").is_err());
+        assert!(parse_delimiter(";;").is_err());
+        assert!(parse_delimiter("\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let template = parse_template("{path}: {lines} lines, {{{words}}} words").unwrap();
+        assert!(template.uses(CountKind::Lines));
+        assert!(!template.uses(CountKind::Bytes));
+        let line = template.render("a.txt", |kind| match kind {
+            CountKind::Lines => String::from("3"),
+            _ => String::from("12"),
+        });
+        assert_eq!("a.txt: 3 lines, {12} words", line);
+
+        assert!(parse_template("{line}").is_err());
+        assert!(parse_template("{path").is_err());
+        assert!(parse_template("path}").is_err());
+        assert_eq!(Template(Vec::new()), parse_template("").unwrap());
+    }
 }
@@ -0,0 +1,149 @@
+//! `--goal`, which prints the progress of the total or of each file towards a target count.
+
+use crate::console;
+use crate::count::{parse_count_kind, CountKind, Counts};
+use crate::error::Error;
+use colored::*;
+use num_format::{Locale, ToFormattedString};
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Goal {
+    pub kind: CountKind,
+    pub target: u64,
+    /// Whether every file should reach target rather than their total.
+    pub per_file: bool,
+}
+
+/// Parse a goal like `words=50000`, `_` and `,` may separate digits.
+pub fn parse_goal(src: &str) -> Result<Goal, Error> {
+    let invalid = || {
+        Error::CUSTOM(format!(
+            "invalid --goal {}, expected <count>=<target> like words=50000",
+            src
+        ))
+    };
+    let mut parts = src.splitn(2, '=');
+    let kind = parse_count_kind(parts.next().unwrap_or_default())?;
+    let target: String = parts
+        .next()
+        .ok_or_else(invalid)?
+        .chars()
+        .filter(|c| *c != '_' && *c != ',')
+        .collect();
+    let target = target.parse::<u64>().map_err(|_| invalid())?;
+    if target == 0 {
+        return Err(invalid());
+    }
+    Ok(Goal {
+        kind,
+        target,
+        per_file: false,
+    })
+}
+
+/// A line like `words: 42,000 of 50,000 (84.0%), 8,000 to go`.
+fn progress(goal: &Goal, val: u64) -> String {
+    let percent = format!("({:.1}%)", val as f64 * 100.0 / goal.target as f64);
+    let status = if val >= goal.target {
+        format!(
+            "{} {}",
+            percent.green().bold(),
+            "goal reached!".green().bold()
+        )
+    } else {
+        format!(
+            "{}, {} to go",
+            percent.yellow(),
+            (goal.target - val).to_formatted_string(&Locale::en)
+        )
+    };
+    format!(
+        "{}: {} of {} {}",
+        goal.kind.name(),
+        val.to_formatted_string(&Locale::en),
+        goal.target.to_formatted_string(&Locale::en),
+        status
+    )
+}
+
+/// The progress lines for results, one for the total or one per counted file if the goal is per
+/// file. Files which couldn't be counted are left out.
+pub fn goal_lines(goal: &Goal, results: &[(Result<Counts, Error>, PathBuf)]) -> Vec<String> {
+    let counted = results
+        .iter()
+        .filter_map(|(res, path)| Some((res.as_ref().ok()?.get(goal.kind).val?, path)));
+    if goal.per_file {
+        counted
+            .map(|(val, path)| {
+                format!(
+                    "{} {}",
+                    console::display_path(path).bold(),
+                    progress(goal, val)
+                )
+            })
+            .collect()
+    } else {
+        let total = counted.fold(0u64, |total, (val, _)| total.saturating_add(val));
+        vec![progress(goal, total)]
+    }
+}
+
+pub fn print_goal<W: Write>(lines: &[String], mut w: W) -> Result<(), Error> {
+    for line in lines {
+        write!(w, "\n{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::Count;
+
+    #[test]
+    fn test_parse_goal() {
+        let goal = parse_goal("words=50_000").unwrap();
+        assert_eq!(CountKind::Words, goal.kind);
+        assert_eq!(50000, goal.target);
+        assert_eq!(1000, parse_goal("lines=1,000").unwrap().target);
+        assert!(parse_goal("words").is_err());
+        assert!(parse_goal("words=lots").is_err());
+        assert!(parse_goal("words=0").is_err());
+        assert!(parse_goal("pages=10").is_err());
+    }
+
+    #[test]
+    fn test_goal_lines() {
+        colored::control::set_override(false);
+        let counts = |words| Counts {
+            bytes: Count { val: None },
+            chars: Count { val: None },
+            words: Count { val: Some(words) },
+            lines: Count { val: None },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts(30_000)), PathBuf::from("a.md")),
+            (Err(Error::UTF8()), PathBuf::from("b.md")),
+            (Ok(counts(12_000)), PathBuf::from("c.md")),
+        ];
+        let mut goal = parse_goal("words=40000").unwrap();
+        assert_eq!(
+            vec!["words: 42,000 of 40,000 (105.0%) goal reached!"],
+            goal_lines(&goal, &results)
+        );
+
+        goal.per_file = true;
+        assert_eq!(
+            vec![
+                "a.md words: 30,000 of 40,000 (75.0%), 10,000 to go",
+                "c.md words: 12,000 of 40,000 (30.0%), 28,000 to go",
+            ],
+            goal_lines(&goal, &results)
+        );
+        colored::control::unset_override();
+    }
+}
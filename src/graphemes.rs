@@ -0,0 +1,69 @@
+//! `--graphemes`, the user-perceived chars of each file like é or 👍🏽, which may be several
+//! codepoints each.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const GRAPHEMES: &str = "graphemes";
+
+/// The extended grapheme clusters of line, with invalid utf-8 replaced by U+FFFD.
+fn graphemes(line: &[u8]) -> u64 {
+    String::from_utf8_lossy(line).graphemes(true).count() as u64
+}
+
+/// Extended grapheme clusters as defined by UAX #29, segmented a line at a time since no cluster
+/// continues past a `\n`.
+#[derive(Debug, Default)]
+pub struct Graphemes {
+    graphemes: u64,
+    /// A line which spans chunks.
+    line: Vec<u8>,
+}
+
+impl Metric for Graphemes {
+    fn update(&mut self, chunk: &[u8]) {
+        for piece in chunk.split_inclusive(|&b| b == b'\n') {
+            if !piece.ends_with(b"\n") {
+                self.line.extend_from_slice(piece);
+            } else if self.line.is_empty() {
+                self.graphemes += graphemes(piece);
+            } else {
+                self.line.extend_from_slice(piece);
+                self.graphemes += graphemes(&self.line);
+                self.line.clear();
+            }
+        }
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.graphemes += graphemes(&std::mem::take(&mut self.line));
+        MetricValue::Count(self.graphemes)
+    }
+}
+
+/// The `GRAPHEMES` column.
+pub fn graphemes_metric() -> MetricFactory {
+    MetricFactory::new(GRAPHEMES, Graphemes::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphemes() {
+        // e and a combining acute, a thumbs up with a skin tone, and \r\n are one grapheme each
+        let input = ["cafe\u{301} 👍🏽\r\n東京\n".as_bytes(), b"\xff"].concat();
+        for chunk in 1..input.len() {
+            let mut metric = graphemes_metric().create();
+            for chunk in input.chunks(chunk) {
+                metric.update(chunk);
+            }
+            assert_eq!(MetricValue::Count(11), metric.finalize());
+        }
+        assert_eq!(
+            MetricValue::Count(0),
+            graphemes_metric().create().finalize()
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! The `Counter` gRPC service defined in proto/rwc.proto, which counts data streamed by the client
+//! without buffering all of it.
+
+use crate::count::{self, CountOptions, Countable};
+use crate::error::Error;
+use std::io::{self, Read};
+use std::net::SocketAddr;
+use tokio::sync::mpsc;
+use tonic::{Request, Response, Status, Streaming};
+
+pub mod proto {
+    tonic::include_proto!("rwc");
+}
+
+use proto::count_request::Kind;
+use proto::counter_server::{Counter, CounterServer};
+use proto::{CountRequest, CountResponse};
+
+/// Number of chunks which can be queued before the stream is applied backpressure.
+const CHUNK_QUEUE: usize = 16;
+
+#[derive(Debug, Default)]
+pub struct CounterService;
+
+impl From<proto::CountOptions> for CountOptions {
+    fn from(opts: proto::CountOptions) -> CountOptions {
+        CountOptions {
+            bytes: opts.bytes,
+            chars: opts.chars,
+            words: opts.words,
+            lines: opts.lines,
+            ..CountOptions::default()
+        }
+    }
+}
+
+impl From<count::Counts> for CountResponse {
+    fn from(counts: count::Counts) -> CountResponse {
+        CountResponse {
+            bytes: counts.bytes.val,
+            chars: counts.chars.val,
+            words: counts.words.val,
+            lines: counts.lines.val,
+        }
+    }
+}
+
+fn to_status(err: Error) -> Status {
+    match err.cause() {
+        Error::UTF8() => Status::invalid_argument(err.plain_report()),
+        _ => Status::internal(err.plain_report()),
+    }
+}
+
+/// Blocking reader over the chunks received from the stream.
+struct ChunkReader {
+    chunks: mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[tonic::async_trait]
+impl Counter for CounterService {
+    async fn count(
+        &self,
+        request: Request<Streaming<CountRequest>>,
+    ) -> Result<Response<CountResponse>, Status> {
+        let mut stream = request.into_inner();
+        let opts = match stream.message().await? {
+            Some(CountRequest {
+                kind: Some(Kind::Options(opts)),
+            }) => CountOptions::from(opts),
+            _ => {
+                return Err(Status::invalid_argument(
+                    "the first request must set options",
+                ))
+            }
+        };
+
+        let (tx, rx) = mpsc::channel(CHUNK_QUEUE);
+        let reader = ChunkReader {
+            chunks: rx,
+            chunk: Vec::new(),
+            pos: 0,
+        };
+        let counting = tokio::task::spawn_blocking(move || reader.count_with(opts));
+        while let Some(req) = stream.message().await? {
+            match req.kind {
+                Some(Kind::Chunk(chunk)) => {
+                    // counting stopped early because of an error
+                    if tx.send(chunk).await.is_err() {
+                        break;
+                    }
+                }
+                _ => {
+                    return Err(Status::invalid_argument(
+                        "only the first request may set options",
+                    ))
+                }
+            }
+        }
+        drop(tx);
+
+        let counts = counting
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .map_err(to_status)?;
+        Ok(Response::new(counts.into()))
+    }
+}
+
+/// Serve the `Counter` service on addr until the process is killed.
+pub async fn serve(addr: SocketAddr) -> Result<(), Error> {
+    tonic::transport::Server::builder()
+        .add_service(CounterServer::new(CounterService))
+        .serve(addr)
+        .await
+        .map_err(|err| Error::CUSTOM(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto::counter_client::CounterClient;
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Endpoint;
+
+    async fn count(requests: Vec<CountRequest>) -> Result<CountResponse, Status> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(CounterServer::new(CounterService))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        let channel = Endpoint::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let mut client = CounterClient::new(channel);
+        client
+            .count(tokio_stream::iter(requests))
+            .await
+            .map(Response::into_inner)
+    }
+
+    fn options() -> CountRequest {
+        CountRequest {
+            kind: Some(Kind::Options(proto::CountOptions {
+                bytes: true,
+                chars: true,
+                words: true,
+                lines: true,
+            })),
+        }
+    }
+
+    fn chunk(data: &str) -> CountRequest {
+        CountRequest {
+            kind: Some(Kind::Chunk(data.as_bytes().to_vec())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_count() {
+        let res = count(vec![
+            options(),
+            chunk("hello wo"),
+            chunk("rld 😀\n"),
+            chunk("foo"),
+        ])
+        .await
+        .unwrap();
+        assert_eq!(Some(20), res.bytes);
+        assert_eq!(Some(17), res.chars);
+        assert_eq!(Some(4), res.words);
+        assert_eq!(Some(1), res.lines);
+    }
+
+    #[tokio::test]
+    async fn test_count_requires_options() {
+        let status = count(vec![chunk("hello")]).await.unwrap_err();
+        assert_eq!(tonic::Code::InvalidArgument, status.code());
+
+        let status = count(vec![options(), options()]).await.unwrap_err();
+        assert_eq!(tonic::Code::InvalidArgument, status.code());
+    }
+}
@@ -0,0 +1,264 @@
+//! `rwc history`, which records counts of files in a local jsonl store and shows how they changed
+//! between runs.
+
+use crate::console;
+use crate::count::{CountOptions, CountablePath};
+use crate::error::Error;
+use colored::*;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use term_table::row::Row;
+use term_table::table_cell::{Alignment, TableCell};
+use term_table::Table;
+
+/// The counts of a file at some point in time, one line of the store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since the unix epoch.
+    pub time: u64,
+    /// Canonicalized so the same file recorded from different directories has one history.
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub words: u64,
+    pub lines: u64,
+}
+
+/// `$XDG_DATA_HOME/rwc`, falling back to `~/.local/share/rwc`.
+pub fn data_dir() -> Result<PathBuf, Error> {
+    let data = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".local").join("share"),
+            None => {
+                return Err(
+                    String::from("cannot find rwc's data directory, set XDG_DATA_HOME").into(),
+                )
+            }
+        },
+    };
+    Ok(data.join("rwc"))
+}
+
+/// `history.jsonl` in the `data_dir`.
+pub fn default_store() -> Result<PathBuf, Error> {
+    Ok(data_dir()?.join("history.jsonl"))
+}
+
+fn count_entry(path: &Path, time: u64) -> Result<Entry, Error> {
+    let path = path.canonicalize()?;
+    let counts = (&path).count_with(CountOptions::new().bytes().words().lines())?;
+    Ok(Entry {
+        time,
+        bytes: counts.bytes.val.unwrap_or(0),
+        words: counts.words.val.unwrap_or(0),
+        lines: counts.lines.val.unwrap_or(0),
+        path,
+    })
+}
+
+/// Count each of paths and append them to store, all with the same time.
+pub fn record(store: &Path, paths: &[PathBuf], time: u64) -> Result<Vec<Entry>, Error> {
+    let (entries, errors): (Vec<_>, Vec<_>) = paths
+        .iter()
+        .map(|path| count_entry(path, time).map_err(|err| Error::for_path(path, err)))
+        .partition(Result::is_ok);
+    if !errors.is_empty() {
+        return Err(errors
+            .into_iter()
+            .map(Result::unwrap_err)
+            .collect::<Vec<Error>>()
+            .into());
+    }
+    let entries: Vec<Entry> = entries.into_iter().map(Result::unwrap).collect();
+
+    if let Some(dir) = store.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut f = OpenOptions::new().create(true).append(true).open(store)?;
+    for entry in &entries {
+        let line = serde_json::to_string(entry).map_err(|err| Error::CUSTOM(err.to_string()))?;
+        writeln!(f, "{}", line)?;
+    }
+    Ok(entries)
+}
+
+/// Every entry in store, oldest first. A missing store has no entries.
+pub fn load(store: &Path) -> Result<Vec<Entry>, Error> {
+    let f = match File::open(store) {
+        Ok(f) => f,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(Error::for_path(store, err.into())),
+    };
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(f).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|err| {
+            Error::for_path(store, Error::CUSTOM(format!("line {}: {}", i + 1, err)))
+        })?;
+        entries.push(entry);
+    }
+    entries.sort_by_key(|entry: &Entry| entry.time);
+    Ok(entries)
+}
+
+/// Whether the entry's path matches pattern, either as recorded or relative to cwd.
+fn matches(pattern: &Pattern, path: &Path, cwd: Option<&Path>) -> bool {
+    if pattern.matches_path(path) {
+        return true;
+    }
+    match cwd.and_then(|cwd| path.strip_prefix(cwd).ok()) {
+        Some(relative) => pattern.matches_path(relative),
+        None => false,
+    }
+}
+
+/// Format secs since the unix epoch like `2021-03-04 05:06:07 UTC`.
+fn format_time(secs: u64) -> String {
+    // days to a civil date, from Howard Hinnant's chrono-compatible algorithms
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let rem = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// A count followed by its change since the previous entry, e.g. `1200 (+35)`.
+fn trend(val: u64, prev: Option<u64>) -> String {
+    match prev {
+        Some(prev) if val >= prev => format!("{} (+{})", val, val - prev),
+        Some(prev) => format!("{} (-{})", val, prev - val),
+        None => val.to_string(),
+    }
+}
+
+/// Print a table of the recorded counts of each path in store matching pattern, a path or glob.
+/// Returns false if nothing matched.
+pub fn show<W: Write>(store: &Path, pattern: &str, mut w: W) -> Result<bool, Error> {
+    let pattern = Pattern::new(pattern).map_err(|err| Error::CUSTOM(err.to_string()))?;
+    let cwd = env::current_dir().ok();
+    let entries: Vec<Entry> = load(store)?
+        .into_iter()
+        .filter(|entry| matches(&pattern, &entry.path, cwd.as_deref()))
+        .collect();
+    if entries.is_empty() {
+        writeln!(w, "No history for {}", pattern)?;
+        return Ok(false);
+    }
+
+    let mut paths: Vec<&Path> = entries.iter().map(|entry| entry.path.as_path()).collect();
+    paths.sort();
+    paths.dedup();
+
+    fn make_cell<'a, T: std::fmt::Display>(data: &T) -> TableCell<'a> {
+        TableCell::new_with_alignment_and_padding(data, 1, Alignment::Left, true)
+    }
+
+    for path in paths {
+        let mut table = Table::new();
+        table.style = console::table_style();
+        table.add_row(Row::new(vec![TableCell::new_with_alignment_and_padding(
+            console::display_path(path).green().bold(),
+            4,
+            Alignment::Left,
+            true,
+        )]));
+        table.add_row(Row::new(
+            ["time", "bytes", "words", "lines"]
+                .iter()
+                .map(|name| make_cell(&name.blue().bold()))
+                .collect::<Vec<_>>(),
+        ));
+        let mut prev: Option<&Entry> = None;
+        for entry in entries.iter().filter(|entry| entry.path == path) {
+            table.add_row(Row::new(vec![
+                make_cell(&format_time(entry.time)),
+                make_cell(&trend(entry.bytes, prev.map(|p| p.bytes))),
+                make_cell(&trend(entry.words, prev.map(|p| p.words))),
+                make_cell(&trend(entry.lines, prev.map(|p| p.lines))),
+            ]));
+            prev = Some(entry);
+        }
+        write!(w, "{}", table.render())?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!("1970-01-01 00:00:00 UTC", format_time(0));
+        assert_eq!("2021-03-04 05:06:07 UTC", format_time(1_614_834_367));
+        assert_eq!("2000-02-29 23:59:59 UTC", format_time(951_868_799));
+    }
+
+    #[test]
+    fn test_trend() {
+        assert_eq!("10", trend(10, None));
+        assert_eq!("10 (+3)", trend(10, Some(7)));
+        assert_eq!("10 (-2)", trend(10, Some(12)));
+    }
+
+    #[test]
+    fn test_record_and_show() {
+        let dir = env::temp_dir().join(format!("rwc-history-{}", std::process::id()));
+        let store = dir.join("store").join("history.jsonl");
+        let file = dir.join("notes.txt");
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(&file, "one two\n").unwrap();
+        record(&store, std::slice::from_ref(&file), 1_614_834_367).unwrap();
+        fs::write(&file, "one two\nthree\n").unwrap();
+        record(&store, std::slice::from_ref(&file), 1_614_920_767).unwrap();
+
+        let entries = load(&store).unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!(
+            (8, 2, 1),
+            (entries[0].bytes, entries[0].words, entries[0].lines)
+        );
+        assert_eq!(
+            (14, 3, 2),
+            (entries[1].bytes, entries[1].words, entries[1].lines)
+        );
+
+        colored::control::set_override(false);
+        let mut out = Vec::new();
+        let glob = dir.join("*.txt");
+        assert!(show(&store, glob.to_str().unwrap(), &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("2021-03-05 05:06:07 UTC"));
+        assert!(out.contains("14 (+6)"));
+        assert!(out.contains("2 (+1)"));
+
+        let mut out = Vec::new();
+        let missing = dir.join("*.md");
+        assert!(!show(&store, missing.to_str().unwrap(), &mut out).unwrap());
+        colored::control::unset_override();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+//! `rwc hook`, a pre-commit size gate which counts the staged contents of files.
+
+use crate::console;
+use crate::count::{CountOptions, Countable, Counts};
+use crate::error::Error;
+use colored::*;
+use rayon::prelude::*;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Limits {
+    pub max_lines: Option<u64>,
+    pub max_bytes: Option<u64>,
+}
+
+/// Descriptions of each limit exceeded by counts.
+pub fn violations(counts: &Counts, limits: &Limits) -> Vec<String> {
+    let mut violations = Vec::new();
+    if let (Some(max), Some(n)) = (limits.max_lines, counts.lines.val) {
+        if n > max {
+            violations.push(format!("{} lines exceeds --max-lines {}", n, max));
+        }
+    }
+    if let (Some(max), Some(n)) = (limits.max_bytes, counts.bytes.val) {
+        if n > max {
+            violations.push(format!("{} bytes exceeds --max-bytes {}", n, max));
+        }
+    }
+    violations
+}
+
+fn git(args: &[&str]) -> Result<Vec<u8>, Error> {
+    let output = Command::new("git").args(args).output()?;
+    if !output.status.success() {
+        return Err(Error::CUSTOM(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Paths, relative to the repository root, of files which are added, copied, modified, or renamed
+/// in the index.
+fn staged_paths() -> Result<Vec<PathBuf>, Error> {
+    let out = git(&[
+        "diff",
+        "--cached",
+        "--name-only",
+        "-z",
+        "--diff-filter=ACMR",
+    ])?;
+    out.split(|&b| b == b'\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| Ok(PathBuf::from(String::from_utf8(path.to_vec())?)))
+        .collect()
+}
+
+fn count_staged(path: &Path) -> Result<Counts, Error> {
+    let spec = format!(":{}", path.display());
+    let contents = git(&["show", &spec])?;
+    contents
+        .as_slice()
+        .count_with(CountOptions::new().bytes().lines())
+        .map_err(|err| Error::for_path(path, err))
+}
+
+/// Count every staged file and print the ones exceeding limits to w. Returns whether every file
+/// is within the limits.
+pub fn run<W: Write>(limits: &Limits, mut w: W) -> Result<bool, Error> {
+    let paths = staged_paths()?;
+    let results: Vec<_> = paths
+        .par_iter()
+        .map(|path| count_staged(path).map(|counts| violations(&counts, limits)))
+        .collect();
+    let mut ok = true;
+    for (path, res) in paths.iter().zip(results) {
+        for violation in res? {
+            ok = false;
+            writeln!(
+                w,
+                "{}: {}",
+                console::display_path(path).bold(),
+                violation.red()
+            )?;
+        }
+    }
+    Ok(ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::Count;
+
+    #[test]
+    fn test_violations() {
+        let counts = Counts {
+            bytes: Count { val: Some(2000) },
+            chars: Count { val: None },
+            words: Count { val: None },
+            lines: Count { val: Some(100) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let limits = Limits {
+            max_lines: Some(100),
+            max_bytes: Some(1024),
+        };
+        assert_eq!(
+            vec![String::from("2000 bytes exceeds --max-bytes 1024")],
+            violations(&counts, &limits)
+        );
+        assert!(violations(&counts, &Limits::default()).is_empty());
+    }
+}
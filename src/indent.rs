@@ -0,0 +1,206 @@
+//! `--indent-histogram`, how many lines sit at each indentation depth, a cheap proxy for how
+//! deeply nested code is, and `--indent-stats`, whether lines are indented with tabs or spaces
+//! and by how much.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use std::collections::BTreeMap;
+
+pub const INDENT_HISTOGRAM: &str = "indent histogram";
+pub const TAB_INDENTED: &str = "tab indented";
+pub const SPACE_INDENTED: &str = "space indented";
+pub const INDENT_WIDTH: &str = "indent width";
+
+/// Columns a tab indents to the next multiple of.
+const TAB_WIDTH: u64 = 4;
+
+/// Histogram of the leading whitespace of non-blank lines. The depth of a line is its indent
+/// width divided by the smallest indent in the input, so 2 and 4 space indents both count
+/// levels.
+#[derive(Debug, Default)]
+pub struct IndentHistogram {
+    /// Lines by indent width in columns.
+    widths: BTreeMap<u64, u64>,
+    width: u64,
+    /// Whether the current line has had anything but whitespace.
+    past_indent: bool,
+}
+
+impl Metric for IndentHistogram {
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            match b {
+                b'\n' => {
+                    // blank lines have no depth
+                    self.past_indent = false;
+                    self.width = 0;
+                }
+                _ if self.past_indent => {}
+                b' ' => self.width += 1,
+                b'\t' => self.width += TAB_WIDTH - self.width % TAB_WIDTH,
+                b'\r' | b'\x0b' | b'\x0c' => {}
+                _ => {
+                    *self.widths.entry(self.width).or_insert(0) += 1;
+                    self.past_indent = true;
+                }
+            }
+        }
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        let unit = self.widths.keys().copied().find(|&w| w > 0).unwrap_or(1);
+        let mut depths = BTreeMap::new();
+        for (width, lines) in &self.widths {
+            *depths.entry(width / unit).or_insert(0) += lines;
+        }
+        MetricValue::Histogram(depths)
+    }
+}
+
+/// The `INDENT_HISTOGRAM` column.
+pub fn indent_histogram_metric() -> MetricFactory {
+    MetricFactory::new(INDENT_HISTOGRAM, IndentHistogram::default)
+}
+
+/// The indents of non-blank lines. An indent with any tab is a tab indent, and the width of a
+/// level is the most common increase in indent from one line to the next, in columns like
+/// `IndentHistogram`.
+#[derive(Debug, Default)]
+struct Indents {
+    tab: u64,
+    space: u64,
+    /// Increases in indent by how many columns.
+    increases: BTreeMap<u64, u64>,
+    /// The indent of the last non-blank line.
+    prev_width: u64,
+    width: u64,
+    has_tab: bool,
+    past_indent: bool,
+}
+
+impl Indents {
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            match b {
+                b'\n' => {
+                    self.past_indent = false;
+                    self.has_tab = false;
+                    self.width = 0;
+                }
+                _ if self.past_indent => {}
+                b' ' => self.width += 1,
+                b'\t' => {
+                    self.width += TAB_WIDTH - self.width % TAB_WIDTH;
+                    self.has_tab = true;
+                }
+                b'\r' | b'\x0b' | b'\x0c' => {}
+                _ => {
+                    if self.has_tab {
+                        self.tab += 1;
+                    } else if self.width > 0 {
+                        self.space += 1;
+                    }
+                    if self.width > self.prev_width {
+                        *self
+                            .increases
+                            .entry(self.width - self.prev_width)
+                            .or_insert(0) += 1;
+                    }
+                    self.prev_width = self.width;
+                    self.past_indent = true;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TabIndented(Indents);
+
+impl Metric for TabIndented {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Count(self.0.tab)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SpaceIndented(Indents);
+
+impl Metric for SpaceIndented {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Count(self.0.space)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct IndentWidth(Indents);
+
+impl Metric for IndentWidth {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Mode(std::mem::take(&mut self.0.increases))
+    }
+}
+
+/// The `TAB_INDENTED`, `SPACE_INDENTED`, and `INDENT_WIDTH` columns.
+pub fn indent_stats_metrics() -> Vec<MetricFactory> {
+    vec![
+        MetricFactory::new(TAB_INDENTED, TabIndented::default),
+        MetricFactory::new(SPACE_INDENTED, SpaceIndented::default),
+        MetricFactory::new(INDENT_WIDTH, IndentWidth::default),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(input: &[u8], chunk: usize) -> MetricValue {
+        let mut metric = IndentHistogram::default();
+        for chunk in input.chunks(chunk) {
+            metric.update(chunk);
+        }
+        metric.finalize()
+    }
+
+    #[test]
+    fn test_indent_histogram() {
+        let input = b"fn main() {\n  if x {\n    y();\n\n      \n  }\n}\n";
+        for chunk in 1..input.len() {
+            assert_eq!("0:2 1:2 2:1", histogram(input, chunk).to_string());
+        }
+        let tabs = b"a\n\tb\n\t\tc\n  \td";
+        assert_eq!("0:1 1:2 2:1", histogram(tabs, 64).to_string());
+        assert_eq!("", histogram(b"", 64).to_string());
+    }
+
+    fn indents(input: &[u8], chunk: usize) -> (u64, u64, String) {
+        let mut indents = Indents::default();
+        for chunk in input.chunks(chunk) {
+            indents.update(chunk);
+        }
+        let width = MetricValue::Mode(indents.increases).to_string();
+        (indents.tab, indents.space, width)
+    }
+
+    #[test]
+    fn test_indents() {
+        let input = b"fn main() {\n  if x {\n    y();\n\n      \n  }\n\tz();\n}\n";
+        for chunk in 1..input.len() {
+            assert_eq!((1, 3, String::from("2")), indents(input, chunk));
+        }
+        let tabs = b"a\n\tb\n\t\tc\n  \td\ne\n\tf";
+        assert_eq!((4, 0, String::from("4")), indents(tabs, 64));
+        assert_eq!((0, 0, String::from("0")), indents(b"", 64));
+    }
+}
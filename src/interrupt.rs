@@ -0,0 +1,33 @@
+//! Interrupting a run, e.g. by Ctrl-C, so the results counted so far can still be printed.
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Stop every count in progress at its next read, each failing with its partial counts.
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Let counting go on after an interrupt, e.g. for the next path of `--batch`.
+pub fn reset() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// A reader which fails once the run is interrupted.
+pub(crate) struct Interruptible<R>(pub R);
+
+impl<R: Read> Read for Interruptible<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if is_interrupted() {
+            // not io::ErrorKind::Interrupted, which readers retry
+            return Err(io::Error::other("interrupted"));
+        }
+        self.0.read(buf)
+    }
+}
@@ -0,0 +1,170 @@
+//! `rwc langs`, which sums the counts of the files under some directories per language.
+
+use crate::code;
+use crate::count::{CountOptions, CountablePath, Counts};
+use crate::error::Error;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Languages by file extension.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("c", "C"),
+    ("h", "C Header"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("cxx", "C++"),
+    ("hpp", "C++ Header"),
+    ("cs", "C#"),
+    ("css", "CSS"),
+    ("go", "Go"),
+    ("hs", "Haskell"),
+    ("html", "HTML"),
+    ("java", "Java"),
+    ("js", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("json", "JSON"),
+    ("jsx", "JSX"),
+    ("kt", "Kotlin"),
+    ("lua", "Lua"),
+    ("md", "Markdown"),
+    ("markdown", "Markdown"),
+    ("ml", "OCaml"),
+    ("php", "PHP"),
+    ("pl", "Perl"),
+    ("proto", "Protocol Buffers"),
+    ("py", "Python"),
+    ("rb", "Ruby"),
+    ("rs", "Rust"),
+    ("rst", "reStructuredText"),
+    ("scala", "Scala"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("zsh", "Shell"),
+    ("sql", "SQL"),
+    ("swift", "Swift"),
+    ("tex", "TeX"),
+    ("toml", "TOML"),
+    ("ts", "TypeScript"),
+    ("tsx", "TSX"),
+    ("txt", "Plain Text"),
+    ("vim", "Vim Script"),
+    ("xml", "XML"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+];
+
+/// Languages of files without an extension, by file name.
+const FILE_NAMES: &[(&str, &str)] = &[
+    ("Makefile", "Makefile"),
+    ("makefile", "Makefile"),
+    ("Dockerfile", "Dockerfile"),
+    ("CMakeLists.txt", "CMake"),
+];
+
+/// The language of path, if it's one rwc knows about.
+pub fn language(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    if let Some((_, lang)) = FILE_NAMES.iter().find(|(file_name, _)| *file_name == name) {
+        return Some(lang);
+    }
+    let ext = path.extension()?.to_str()?;
+    EXTENSIONS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(ext))
+        .map(|(_, lang)| *lang)
+}
+
+/// Every file under dir with a known language, skipping hidden files and directories such as
+/// `.git`.
+fn files(dir: &Path) -> Vec<(&'static str, PathBuf)> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| Some((language(entry.path())?, entry.into_path())))
+        .collect()
+}
+
+/// A row for each language found under dirs with the sum of the counts of its files, labelled
+/// with the language and sorted by name. Files which can't be counted make their language's row
+/// an error.
+pub fn count_langs(dirs: &[PathBuf], opts: &CountOptions) -> Vec<(Result<Counts, Error>, PathBuf)> {
+    let mut by_lang: BTreeMap<&'static str, Vec<PathBuf>> = BTreeMap::new();
+    for dir in dirs {
+        for (lang, path) in files(dir) {
+            by_lang.entry(lang).or_default().push(path);
+        }
+    }
+
+    by_lang
+        .into_par_iter()
+        .map(|(lang, paths)| {
+            let (counts, errors): (Vec<_>, Vec<_>) = paths
+                .par_iter()
+                .map(|path| {
+                    path.count_with(code::for_path(path, opts.clone()))
+                        .map_err(|err| Error::for_path(path, err))
+                })
+                .partition(Result::is_ok);
+            let res = if errors.is_empty() {
+                Ok(counts.into_iter().map(Result::unwrap).sum())
+            } else {
+                Err(errors
+                    .into_iter()
+                    .map(Result::unwrap_err)
+                    .collect::<Vec<Error>>()
+                    .into())
+            };
+            (res, PathBuf::from(lang))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_language() {
+        assert_eq!(Some("Rust"), language(Path::new("src/main.rs")));
+        assert_eq!(Some("Markdown"), language(Path::new("README.MD")));
+        assert_eq!(Some("Makefile"), language(Path::new("a/Makefile")));
+        assert_eq!(Some("CMake"), language(Path::new("CMakeLists.txt")));
+        assert_eq!(None, language(Path::new("LICENSE")));
+        assert_eq!(None, language(Path::new("image.png")));
+    }
+
+    #[test]
+    fn test_count_langs() {
+        let dir = env::temp_dir().join(format!("rwc-langs-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("src/lib.rs"), "mod a;\nmod b;\n").unwrap();
+        fs::write(dir.join("README.md"), "# hi\n").unwrap();
+        fs::write(dir.join("LICENSE"), "MIT\n").unwrap();
+        fs::write(dir.join(".git/config.toml"), "x = 1\n").unwrap();
+
+        let opts = CountOptions::new().bytes().words().lines();
+        let rows: Vec<(Counts, PathBuf)> = count_langs(std::slice::from_ref(&dir), &opts)
+            .into_iter()
+            .map(|(res, lang)| (res.unwrap(), lang))
+            .collect();
+        assert_eq!(2, rows.len());
+        assert_eq!(PathBuf::from("Markdown"), rows[0].1);
+        assert_eq!(Some(5), rows[0].0.bytes.val);
+        assert_eq!(PathBuf::from("Rust"), rows[1].1);
+        assert_eq!(Some(27), rows[1].0.bytes.val);
+        assert_eq!(Some(7), rows[1].0.words.val);
+        assert_eq!(Some(3), rows[1].0.lines.val);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
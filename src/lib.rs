@@ -0,0 +1,68 @@
+pub mod badge;
+pub mod batch;
+pub mod blame;
+pub mod blank;
+pub mod cargo;
+pub mod chars;
+pub mod chunk;
+pub mod cli;
+pub mod code;
+pub mod compare;
+pub mod console;
+pub mod count;
+mod counter;
+#[cfg(unix)]
+pub mod daemon;
+pub mod detect_lang;
+pub mod endings;
+pub mod entropy;
+pub mod error;
+pub mod exit;
+pub mod fdlimit;
+#[cfg(feature = "rwc-ffi")]
+pub mod ffi;
+pub mod format;
+pub mod goal;
+pub mod graphemes;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod history;
+pub mod hook;
+pub mod indent;
+pub mod interrupt;
+pub mod langs;
+pub mod linelen;
+pub mod markdown;
+pub mod matches;
+pub mod metric;
+pub mod nonascii;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod output;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod perline;
+pub mod preset;
+pub mod print;
+pub mod prose;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod sink;
+pub mod size;
+pub mod snapshot;
+pub mod sort;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod status;
+pub mod timeout;
+pub mod tokenize;
+pub mod trailing;
+pub mod unique;
+pub mod urls;
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod width;
+pub mod wordlen;
@@ -0,0 +1,181 @@
+//! `--line-stats`, the min, mean, and median line length of each file, for enforcing style limits
+//! and spotting minified files whose few lines are very long.
+
+use crate::count::Eol;
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use std::collections::BTreeMap;
+
+pub const MIN_LINE_LENGTH: &str = "min line length";
+pub const MEAN_LINE_LENGTH: &str = "mean line length";
+pub const MEDIAN_LINE_LENGTH: &str = "median line length";
+
+/// Lines by their length in chars, not counting the terminator which ends them as the line count
+/// does, nor a `\r` before the `\n` ending a line with `Eol::Lf`. Chars are counted without
+/// decoding, as the bytes which don't continue a utf-8 sequence, and a last line without a
+/// terminator is only a line if it isn't empty.
+#[derive(Debug)]
+struct LineLengths {
+    eol: Eol,
+    lengths: BTreeMap<u64, u64>,
+    /// Chars of the current line so far.
+    length: u64,
+    /// Whether the last byte was a `\r`, which is only counted once the next byte shows it isn't
+    /// part of a `\r\n` with `Eol::Lf` or `Eol::Crlf`.
+    prev_cr: bool,
+}
+
+impl LineLengths {
+    fn new(eol: Eol) -> LineLengths {
+        LineLengths {
+            eol,
+            lengths: BTreeMap::new(),
+            length: 0,
+            prev_cr: false,
+        }
+    }
+
+    /// Whether a `\r` waits for the next byte before it's counted.
+    fn holds_cr(&self) -> bool {
+        matches!(self.eol, Eol::Lf | Eol::Crlf)
+    }
+
+    fn end_line(&mut self) {
+        *self.lengths.entry(self.length).or_insert(0) += 1;
+        self.length = 0;
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            let prev_cr = std::mem::replace(&mut self.prev_cr, b == b'\r');
+            if prev_cr && b != b'\n' && self.holds_cr() {
+                self.length += 1;
+            }
+            match (self.eol, b) {
+                (Eol::Lf, b'\n') | (Eol::Cr, b'\r') | (Eol::Any, b'\r') => self.end_line(),
+                (Eol::Crlf, b'\n') if prev_cr => self.end_line(),
+                // the \r of a \r\n ended the line
+                (Eol::Any, b'\n') if prev_cr => {}
+                (Eol::Any, b'\n') => self.end_line(),
+                (Eol::Byte(end), _) if b == end => self.end_line(),
+                (_, b'\r') if self.holds_cr() => {}
+                _ => self.length += u64::from(b & 0xc0 != 0x80),
+            }
+        }
+    }
+
+    fn finish(&mut self) -> BTreeMap<u64, u64> {
+        if std::mem::take(&mut self.prev_cr) && self.holds_cr() {
+            self.length += 1;
+        }
+        if self.length > 0 {
+            self.end_line();
+        }
+        std::mem::take(&mut self.lengths)
+    }
+}
+
+#[derive(Debug)]
+pub struct MinLineLength(LineLengths);
+
+impl Metric for MinLineLength {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Min(self.0.finish().keys().next().copied().unwrap_or(0))
+    }
+}
+
+#[derive(Debug)]
+pub struct MeanLineLength(LineLengths);
+
+impl Metric for MeanLineLength {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Mean(self.0.finish())
+    }
+}
+
+#[derive(Debug)]
+pub struct MedianLineLength(LineLengths);
+
+impl Metric for MedianLineLength {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Median(self.0.finish())
+    }
+}
+
+/// The `MIN_LINE_LENGTH`, `MEAN_LINE_LENGTH`, and `MEDIAN_LINE_LENGTH` columns, with lines
+/// ending like the line count's.
+pub fn line_stats_metrics(eol: Eol) -> Vec<MetricFactory> {
+    vec![
+        MetricFactory::new(MIN_LINE_LENGTH, move || {
+            MinLineLength(LineLengths::new(eol))
+        }),
+        MetricFactory::new(MEAN_LINE_LENGTH, move || {
+            MeanLineLength(LineLengths::new(eol))
+        }),
+        MetricFactory::new(MEDIAN_LINE_LENGTH, move || {
+            MedianLineLength(LineLengths::new(eol))
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(eol: Eol, input: &str, chunk: usize) -> Vec<String> {
+        line_stats_metrics(eol)
+            .iter()
+            .map(|factory| {
+                let mut metric = factory.create();
+                for chunk in input.as_bytes().chunks(chunk) {
+                    metric.update(chunk);
+                }
+                metric.finalize().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_stats_metrics() {
+        let input = "abc\r\nde\n\nfghij\n東京\nlast";
+        for chunk in 1..input.len() {
+            assert_eq!(vec!["0", "2.667", "2.500"], stats(Eol::Lf, input, chunk));
+        }
+        assert_eq!(
+            vec!["2", "3.000", "3.000"],
+            stats(Eol::Lf, "ab\nabc\nabcd\n", 64)
+        );
+        assert_eq!(vec!["0", "0.000", "0.000"], stats(Eol::Lf, "", 64));
+    }
+
+    #[test]
+    fn test_line_stats_eol() {
+        // a \r is only left out before the \n ending a line
+        let input = "a\rb\r\r\nc\r";
+        for chunk in 1..input.len() {
+            assert_eq!(vec!["2", "3.000", "3.000"], stats(Eol::Lf, input, chunk));
+            assert_eq!(vec!["0", "0.750", "1.000"], stats(Eol::Any, input, chunk));
+            // a \n is a char when only \r ends lines
+            assert_eq!(vec!["0", "1.000", "1.000"], stats(Eol::Cr, input, chunk));
+        }
+        assert_eq!(
+            vec!["3", "3.500", "3.500"],
+            stats(Eol::Crlf, "a\nb\r\nc\rde", 64)
+        );
+        assert_eq!(
+            vec!["1", "1.500", "1.500"],
+            stats(Eol::Byte(b';'), "a;bc;", 64)
+        );
+    }
+}
@@ -1,118 +1,512 @@
 use rayon::prelude::*;
-use std::cmp::Ordering;
+use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
-use structopt::StructOpt;
-
-mod cli;
-mod count;
-mod error;
-mod format;
-mod print;
-
-use cli::{Cli, Options};
-use count::{Countable, CountablePath, Counts};
-use error::Error;
-use format::Format;
-use print::print;
-
-/// Read and return null separated utf8 paths from readable
-fn read_paths0_from<R: Read>(readable: R) -> Result<Vec<PathBuf>, Error> {
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rwc::badge;
+use rwc::batch;
+use rwc::blame;
+use rwc::cargo;
+use rwc::chars;
+use rwc::chunk;
+use rwc::cli::{Cli, Command, HistoryCommand, Options, SnapshotCommand};
+use rwc::code;
+use rwc::compare;
+use rwc::console;
+use rwc::count::{CountOptions, Countable, CountablePath, Counts};
+#[cfg(unix)]
+use rwc::daemon;
+use rwc::error::Error;
+use rwc::exit;
+use rwc::fdlimit::{OpenFiles, Permit};
+use rwc::format::{Format, Formats};
+use rwc::goal;
+use rwc::history;
+use rwc::hook::{self, Limits};
+use rwc::interrupt;
+use rwc::langs;
+use rwc::markdown;
+use rwc::output::{self, AtomicFile};
+use rwc::print::{self, print, print_statusline};
+use rwc::sink;
+use rwc::size;
+use rwc::snapshot;
+use rwc::sort;
+use rwc::status;
+use rwc::timeout::{with_timeout, Deadline};
+use rwc::verify;
+
+/// Read and return null separated utf8 paths from readable, errors are attributed to from
+fn read_paths0_from<R: Read>(from: &Path, readable: R) -> Result<Vec<PathBuf>, Error> {
     let (fnames, errors): (Vec<_>, Vec<_>) = BufReader::new(readable)
         .split(b'\0')
         .partition(Result::is_ok);
-    if errors.len() > 0 {
+    if !errors.is_empty() {
         return Err(errors
             .into_iter()
             .map(Result::unwrap_err)
-            .map(Error::from)
+            .map(|err| Error::for_path(from, err.into()))
             .collect::<Vec<Error>>()
             .into());
     }
     let (fnames, errors): (Vec<_>, Vec<_>) = fnames
         .into_iter()
         .map(Result::unwrap)
-        .map(|fname| String::from_utf8(fname))
+        .map(String::from_utf8)
         .partition(Result::is_ok);
-    if errors.len() > 0 {
+    if !errors.is_empty() {
         return Err(errors
             .into_iter()
             .map(Result::unwrap_err)
-            .map(Error::from)
+            .map(|err| Error::for_path(from, err.into()))
             .collect::<Vec<Error>>()
             .into());
     }
-    Ok(fnames
-        .into_iter()
-        .map(Result::unwrap)
-        .map(|fname| PathBuf::from(fname))
-        .collect())
+    let mut fnames: Vec<String> = fnames.into_iter().map(Result::unwrap).collect();
+    // paths typed at a console or written by echo end with a newline, CRLF on Windows
+    if let Some(last) = fnames.last_mut() {
+        let len = last.trim_end_matches(&['\r', '\n'][..]).len();
+        last.truncate(len);
+    }
+    Ok(fnames.into_iter().map(PathBuf::from).collect())
 }
 
 fn count_paths(paths: Vec<PathBuf>, opts: &Options) -> Vec<(Result<Counts, Error>, PathBuf)> {
+    let markdown_aware = opts.markdown_aware;
+    let open_files = Arc::new(OpenFiles::new(opts.max_open.unwrap_or(usize::MAX)));
+    let paths = match opts.chunk {
+        // the chunks of a file are in order, so the files are sorted before rather than after
+        Some(_) => {
+            let mut paths: Vec<_> = paths.into_iter().map(|path| ((), path)).collect();
+            sort::sort(&mut paths, opts.sort);
+            paths.into_iter().map(|(_, path)| path).collect()
+        }
+        None => paths,
+    };
     paths
         .into_par_iter()
-        .map(|path| {
-            let c = (&path).count(opts.bytes, opts.chars, opts.words, opts.lines);
-            (c, path)
+        // files which weren't started before an interrupt are left out
+        .filter(|_| !interrupt::is_interrupted())
+        .flat_map_iter(|path| {
+            let count = {
+                let (path, chunk) = (path.clone(), opts.chunk);
+                let opts = code::for_path(&path, CountOptions::from(opts));
+                move |permit: Permit, deadline: Option<Deadline>| {
+                    let _permit = permit;
+                    status::start(&path);
+                    let opts = CountOptions { deadline, ..opts };
+                    let rows = match chunk {
+                        Some(chunk) => chunk::count_chunks(&path, chunk, opts),
+                        None if markdown_aware => {
+                            Ok(vec![(markdown::count_path(&path, opts), path.clone())])
+                        }
+                        None => Ok(vec![((&path).count_with(opts), path.clone())]),
+                    };
+                    // here rather than by the caller, which stops waiting when the deadline passes
+                    status::finish(&path);
+                    rows
+                }
+            };
+            let c = with_permit(&open_files, opts.timeout, count);
+            let rows = c.unwrap_or_else(|err| vec![(Err(err), path)]);
+            rows.into_iter().map(|(c, path)| {
+                let c = size::add_sizes(c, &path, opts).map_err(|err| Error::for_path(&path, err));
+                (c, path)
+            })
         })
         .collect()
 }
 
+/// Run count once a file handle is free. With a timeout, waiting for the handle counts towards
+/// it, so count only gets what's left of it.
+fn with_permit<T, F>(
+    open_files: &Arc<OpenFiles>,
+    timeout: Option<Duration>,
+    count: F,
+) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce(Permit, Option<Deadline>) -> Result<T, Error> + Send + 'static,
+{
+    match timeout {
+        Some(timeout) => {
+            let deadline = Deadline::after(timeout);
+            match open_files.acquire_timeout(deadline.remaining()) {
+                Some(permit) => with_timeout(deadline, move || count(permit, Some(deadline))),
+                None => Err(Error::CUSTOM(String::from(
+                    "timed out waiting for a file handle",
+                ))),
+            }
+        }
+        None => count(open_files.acquire(), None),
+    }
+}
+
+/// Print results to output and copy the same text to the clipboard, colors are stripped so the
+/// pasted text doesn't contain escape codes.
+fn print_to_clipboard<W: Write>(
+    fmt: Format,
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    mut output: W,
+) -> Result<(), Error> {
+    let mut buf = Vec::new();
+    print(fmt, results, opts, console::Uncolored::new(&mut buf))?;
+    output.write_all(&buf)?;
+
+    let text = String::from_utf8_lossy(&buf).into_owned();
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|err| Error::CUSTOM(format!("cannot copy to clipboard: {}", err)))
+}
+
 fn run<R: Read, W: Write>(
     mut opts: Options,
     files0_from: Option<PathBuf>,
     files: Vec<PathBuf>,
     input: R,
-    output: W,
+    mut output: W,
     fmt: Format,
-) -> Result<(), Error> {
-    let mut counts = if let Some(from) = files0_from {
-        if files.len() > 0 {
+) -> Result<i32, Error> {
+    #[cfg(feature = "otel")]
+    let start = std::time::Instant::now();
+
+    if opts.statusline {
+        if files0_from.is_some() || !files.is_empty() {
+            return Err(String::from("--statusline only reads standard input").into());
+        }
+        let counts = input.count_with(CountOptions::from(&opts))?;
+        return print_statusline(&counts, &opts, output).map(|_| exit::SUCCESS);
+    }
+
+    let mut counts = if opts.cargo {
+        if files0_from.is_some() {
+            return Err(String::from("--cargo cannot be combined with --files0-from").into());
+        }
+        let roots = if files.is_empty() {
+            vec![PathBuf::from(".")]
+        } else {
+            files
+        };
+        let mut counts = Vec::new();
+        for root in roots {
+            counts.extend(cargo::count_workspace(&root, &CountOptions::from(&opts))?);
+        }
+        counts
+    } else if opts.git_blame {
+        if files0_from.is_some() || files.is_empty() {
+            return Err(String::from("--git-blame needs file operands").into());
+        }
+        opts.show_totals = true;
+        blame::count_blame(&files, &CountOptions::from(&opts))
+    } else if let Some(from) = files0_from {
+        if !files.is_empty() {
             return Err(String::from("file operands cannot be combined with --files0-from").into());
         }
 
-        let paths = if *from == PathBuf::from("-") {
+        let paths = if from.as_os_str() == "-" {
             // read null separated paths from stdin
-            read_paths0_from(input)?
+            read_paths0_from(&from, input)?
         } else {
             // read null separated paths from file
-            match File::open(from) {
-                Ok(f) => read_paths0_from(f)?,
-                Err(e) => return Err(e.into()),
+            match File::open(&from) {
+                Ok(f) => read_paths0_from(&from, f)?,
+                Err(e) => return Err(Error::for_path(&from, e.into())),
             }
         };
         count_paths(paths, &opts)
-    } else if files.len() > 0 {
+    } else if !files.is_empty() {
         count_paths(files, &opts)
     } else {
         opts.show_totals = true;
+        opts.stdin = true;
+        let path = PathBuf::from(print::STDIN);
         vec![(
-            input.count(opts.bytes, opts.chars, opts.words, opts.lines),
-            PathBuf::from("Stdin"),
+            input
+                .count_with(CountOptions::from(&opts))
+                .map_err(|err| Error::for_path(&path, err)),
+            path,
         )]
     };
 
-    counts.par_sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or_else(|| Ordering::Less));
+    // by default rows are in byte order of each path component, whatever the locale
+    if opts.chunk.is_none() {
+        sort::sort(&mut counts, opts.sort);
+    }
+    let code = if interrupt::is_interrupted() {
+        exit::INTERRUPTED
+    } else {
+        exit::for_results(&counts)
+    };
+    let summary = print::error_summary(&counts);
+
+    if let Some(target) = opts.log_to {
+        sink::log(target, &counts)?;
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(db) = &opts.output_db {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        rwc::sqlite::append(db, &counts, now)?;
+    }
 
-    print(fmt, counts, &opts, output)?;
+    #[cfg(feature = "otel")]
+    let stats = rwc::otel::RunStats::new(&counts, start.elapsed());
+
+    let goal = opts
+        .goal
+        .map(|goal| goal::goal_lines(&goal, &counts))
+        .unwrap_or_default();
+
+    for tee in &opts.tee {
+        output::write_tee(tee, &counts, &opts)?;
+    }
+
+    if let Some(kind) = opts.badge {
+        write!(output, "{}", badge::badge_json(kind, counts)?)?;
+    } else {
+        if opts.clipboard {
+            print_to_clipboard(fmt, &counts, &opts, &mut output)?;
+        } else {
+            print(fmt, &counts, &opts, &mut output)?;
+        }
+        goal::print_goal(&goal, &mut output)?;
+    }
+    if let Some(summary) = summary {
+        eprintln!("{}", summary);
+    }
+    if code == exit::INTERRUPTED {
+        eprintln!("Interrupted, the results are incomplete");
+    }
+
+    #[cfg(feature = "otel")]
+    if let Some(endpoint) = &opts.otel_endpoint {
+        rwc::otel::export(endpoint, &stats)?;
+    }
+    Ok(code)
+}
+
+/// Print the progress of the run to stderr whenever rwc gets SIGUSR1, or SIGINFO (Ctrl-T) where
+/// it exists.
+#[cfg(unix)]
+fn report_status_on_signal() -> Result<(), Error> {
+    use signal_hook::consts::signal::*;
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([
+        SIGUSR1,
+        #[cfg(any(
+            target_os = "macos",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd",
+            target_os = "dragonfly"
+        ))]
+        SIGINFO,
+    ])?;
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            eprintln!("{}", status::report());
+        }
+    });
     Ok(())
 }
 
+/// Make the first Ctrl-C stop counting so what was counted is printed, and a second one exit at
+/// once. Only counts are interrupted this way, other modes like the daemon exit on the first.
+fn handle_interrupts() {
+    let _ = ctrlc::set_handler(|| {
+        if interrupt::is_interrupted() {
+            process::exit(exit::INTERRUPTED);
+        }
+        interrupt::interrupt();
+    });
+}
+
+#[cfg(feature = "tracing")]
+fn init_tracing() {
+    use tracing_subscriber::fmt::format::FmtSpan;
+    use tracing_subscriber::EnvFilter;
+
+    // closing a span logs its duration
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(io::stderr)
+        .init();
+}
+
 fn main() {
-    let cli = Cli::from_args();
-    let opts = Options::from(&cli);
+    #[cfg(feature = "tracing")]
+    init_tracing();
+
+    console::init();
+    let cli =
+        Cli::from_iter_with_formats(env::args_os(), &Formats::default()).with_external_files();
+    #[cfg(unix)]
+    let _ = report_status_on_signal();
+    let mut opts = Options::from(&cli);
+    let fmt = match cli.format_template {
+        Some(template) => Format::Template(template),
+        None if cli.print0 => Format::Null,
+        None => cli.format,
+    };
+
+    #[cfg(feature = "grpc")]
+    if let Some(addr) = cli.grpc_listen {
+        let res = tokio::runtime::Runtime::new()
+            .map_err(Error::from)
+            .and_then(|rt| rt.block_on(rwc::grpc::serve(addr)));
+        if let Err(e) = res {
+            eprintln!("{}", e.report());
+            process::exit(exit::USAGE);
+        }
+        return;
+    }
+
+    if let Some(cmd) = cli.cmd {
+        let res = match cmd {
+            Command::Hook {
+                max_lines,
+                max_bytes,
+            } => {
+                let limits = Limits {
+                    max_lines,
+                    max_bytes,
+                };
+                hook::run(&limits, io::stdout())
+            }
+            Command::Verify {
+                with_wc,
+                wc_bin,
+                files,
+            } => {
+                let wc = if with_wc { Some(wc_bin.as_str()) } else { None };
+                verify::run(&files, wc, io::stdout())
+            }
+            Command::History { store, cmd } => store
+                .map(Ok)
+                .unwrap_or_else(history::default_store)
+                .and_then(|store| match cmd {
+                    HistoryCommand::Record { files } => {
+                        let now = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or(0);
+                        history::record(&store, &files, now).map(|_| true)
+                    }
+                    HistoryCommand::Show { pattern } => {
+                        history::show(&store, &pattern, io::stdout())
+                    }
+                }),
+            Command::Compare { dir_a, dir_b } => {
+                compare::run(&dir_a, &dir_b, &opts, fmt, io::stdout())
+            }
+            Command::Snapshot { dir, cmd } => dir
+                .map(Ok)
+                .unwrap_or_else(|| history::data_dir().map(|dir| dir.join("snapshots")))
+                .and_then(|dir| match cmd {
+                    SnapshotCommand::Save { name, paths } => {
+                        let paths = if paths.is_empty() {
+                            vec![PathBuf::from(".")]
+                        } else {
+                            paths
+                        };
+                        snapshot::save(&dir, &name, &paths).map(|_| true)
+                    }
+                    SnapshotCommand::Diff { name } => {
+                        snapshot::diff(&dir, &name, &opts, fmt, io::stdout())
+                    }
+                }),
+            Command::Langs { dirs } => {
+                let dirs = if dirs.is_empty() {
+                    vec![PathBuf::from(".")]
+                } else {
+                    dirs
+                };
+                opts.show_totals = true;
+                let rows = langs::count_langs(&dirs, &CountOptions::from(&opts));
+                print(fmt, &rows, &opts, io::stdout()).map(|_| true)
+            }
+            Command::Chars { bytes, files, .. } => {
+                chars::run(&files, bytes, io::stdin(), &opts, fmt, io::stdout())
+            }
+            #[cfg(unix)]
+            Command::Daemon { socket } => {
+                let socket = socket.unwrap_or_else(daemon::default_socket);
+                daemon::bind(&socket).and_then(daemon::serve).map(|_| true)
+            }
+            Command::Files(_) => unreachable!("moved to files by Cli::with_external_files"),
+        };
+        match res {
+            Ok(true) => {}
+            Ok(false) => process::exit(exit::ASSERTION_FAILED),
+            Err(e) => {
+                eprintln!("{}", e.report());
+                process::exit(exit::USAGE);
+            }
+        }
+        return;
+    }
+
+    #[cfg(unix)]
+    if let Some(path) = cli.ask {
+        let res = daemon::ask(&daemon::default_socket(), &path)
+            .map_err(|err| Error::for_path(&path, err));
+        let results = vec![(res, path)];
+        let code = exit::for_results(&results);
+        if let Err(e) = print(fmt, &results, &opts, io::stdout()) {
+            eprintln!("{}", e.report());
+            process::exit(exit::USAGE);
+        }
+        process::exit(code);
+    }
+
+    if cli.batch {
+        handle_interrupts();
+        let res = if cli.files.is_empty() {
+            let separator = if cli.null_data { b'\0' } else { b'\n' };
+            let count = |path| count_paths(vec![path], &opts);
+            batch::run(&opts, io::stdin().lock(), io::stdout(), separator, count)
+        } else {
+            Err(String::from("--batch reads paths from standard input, not file operands").into())
+        };
+        match res {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{}", e.report());
+                process::exit(exit::USAGE);
+            }
+        }
+    }
+
     let files0_from = cli.files0_from;
     let files = cli.files;
-    let fmt = cli.format;
+    handle_interrupts();
 
-    match run(opts, files0_from, files, io::stdin(), io::stdout(), fmt) {
-        Ok(()) => {}
+    let res = match opts.output.clone() {
+        Some(path) => AtomicFile::create(&path).and_then(|file| {
+            // colors are for terminals, not reports
+            let mut file = console::Uncolored::new(file);
+            let code = run(opts, files0_from, files, io::stdin(), &mut file, fmt)?;
+            file.into_inner().commit()?;
+            Ok(code)
+        }),
+        None => run(opts, files0_from, files, io::stdin(), io::stdout(), fmt),
+    };
+    match res {
+        Ok(exit::SUCCESS) => {}
+        Ok(code) => process::exit(code),
         Err(e) => {
-            eprintln!("{}", e);
-            process::exit(1);
+            eprintln!("{}", e.report());
+            process::exit(exit::USAGE);
         }
     }
 }
@@ -120,6 +514,11 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rwc::count::{Eol, InvalidUtf8};
+    use rwc::format::parse_format;
+    use rwc::sort::Sort;
+    use std::thread;
+    use structopt::StructOpt;
 
     fn default_opts() -> Options {
         Options {
@@ -127,7 +526,37 @@ mod tests {
             chars: false,
             words: true,
             lines: true,
+            max_line_length: false,
             show_totals: false,
+            stdin: false,
+            statusline: false,
+            cargo: false,
+            git_blame: false,
+            markdown_aware: false,
+            log_to: None,
+            clipboard: false,
+            badge: None,
+            goal: None,
+            csv_quote: true,
+            csv_delimiter: ',',
+            #[cfg(feature = "otel")]
+            otel_endpoint: None,
+            #[cfg(feature = "sqlite")]
+            output_db: None,
+            output: None,
+            tee: Vec::new(),
+            tokenizer: None,
+            invalid_utf8: InvalidUtf8::Strict,
+            eol: Eol::Lf,
+            count_last_line: false,
+            no_stat_fastpath: false,
+            apparent_size: false,
+            disk_usage: false,
+            sort: Sort::Path,
+            timeout: None,
+            max_open: None,
+            chunk: None,
+            metrics: Vec::new(),
         }
     }
 
@@ -138,10 +567,77 @@ mod tests {
             chars: false,
             words: false,
             lines: false,
+            max_line_length: false,
             show_totals: false,
-            format: format::Format::Table,
+            stdin: false,
+            statusline: false,
+            cargo: false,
+            git_blame: false,
+            markdown_aware: false,
+            markdown_excluded_words: false,
+            avg_word_length: false,
+            word_length_histogram: false,
+            unique_words: false,
+            words_per_line: false,
+            entropy: false,
+            indent_histogram: false,
+            indent_stats: false,
+            detect_lang: false,
+            count_urls: false,
+            count_preset: vec![],
+            count_matches: vec![],
+            count_lines_matching: None,
+            prose_stats: false,
+            readability: false,
+            line_stats: false,
+            blank_lines: false,
+            nonblank_lines: false,
+            code: false,
+            line_endings: false,
+            trailing_ws: false,
+            non_ascii: false,
+            graphemes: false,
+            batch: false,
+            null_data: false,
+            log_to: None,
+            clipboard: false,
+            badge: None,
+            goal: None,
+            goal_per_file: false,
+            tokenizer: None,
+            word_pattern: None,
+            words_unicode: false,
+            invalid_utf8: InvalidUtf8::Strict,
+            eol: Eol::Lf,
+            record_delim: None,
+            zero_terminated: false,
+            count_last_line: false,
+            no_stat_fastpath: false,
+            apparent_size: false,
+            disk_usage: false,
+            sort: Sort::Path,
+            timeout: None,
+            max_open: None,
+            chunk_lines: None,
+            chunk_bytes: None,
+            format: parse_format("table"),
+            format_template: None,
+            print0: false,
+            no_csv_quote: false,
+            delimiter: ',',
             files0_from: None,
+            #[cfg(unix)]
+            ask: None,
+            #[cfg(feature = "grpc")]
+            grpc_listen: None,
+            #[cfg(feature = "otel")]
+            otel_endpoint: None,
+            #[cfg(feature = "sqlite")]
+            output_db: None,
+            output: None,
+            tee: Vec::new(),
             files: Vec::new(),
+            cmd: None,
         };
         let opts = Options::from(&cli);
         assert!(opts.bytes);
@@ -151,6 +647,19 @@ mod tests {
         assert!(!opts.show_totals);
     }
 
+    #[test]
+    fn test_files_named_like_subcommands() {
+        let cli = Cli::from_iter(&["rwc", "-w", "hooks", "--", "hook"]).with_external_files();
+        assert!(cli.cmd.is_none());
+        assert_eq!(
+            vec![PathBuf::from("hooks"), PathBuf::from("hook")],
+            cli.files
+        );
+
+        let cli = Cli::from_iter(&["rwc", "hook"]).with_external_files();
+        assert!(matches!(cli.cmd, Some(Command::Hook { .. })));
+    }
+
     #[test]
     #[should_panic]
     fn test_run_cannot_combine_files0_from_and_files() {
@@ -162,7 +671,7 @@ mod tests {
             files,
             io::stdin(),
             io::stdout(),
-            Format::CSV,
+            parse_format("csv"),
         )
         .unwrap();
     }
@@ -178,7 +687,7 @@ mod tests {
             Vec::new(),
             &stdin[..],
             &mut stdout,
-            Format::CSV,
+            parse_format("csv"),
         )
         .unwrap();
         assert_eq!(
@@ -189,6 +698,24 @@ test_data/ten_mb.txt,10000000,2000000,1000000",
         );
     }
 
+    #[test]
+    fn test_run_files0_from_console() {
+        let stdin = b"test_data/default.txt\0test_data/ten_mb.txt\r\n";
+        let mut stdout = Vec::new();
+        run(
+            default_opts(),
+            Some(PathBuf::from("-")),
+            Vec::new(),
+            &stdin[..],
+            &mut stdout,
+            parse_format("csv"),
+        )
+        .unwrap();
+        assert!(String::from_utf8(stdout)
+            .unwrap()
+            .ends_with("test_data/ten_mb.txt,10000000,2000000,1000000"));
+    }
+
     #[test]
     fn test_run_files0_from_paths() {
         let files0_from = Some(PathBuf::from("test_data/files0_from.txt"));
@@ -199,7 +726,7 @@ test_data/ten_mb.txt,10000000,2000000,1000000",
             Vec::new(),
             io::stdin(),
             &mut stdout,
-            Format::CSV,
+            parse_format("csv"),
         )
         .unwrap();
         assert_eq!(
@@ -222,7 +749,7 @@ test_data/ten_mb.txt,10000000,2000000,1000000",
             ],
             io::stdin(),
             &mut stdout,
-            Format::CSV,
+            parse_format("csv"),
         )
         .unwrap();
         assert_eq!(
@@ -233,6 +760,49 @@ test_data/ten_mb.txt,10000000,2000000,1000000",
         );
     }
 
+    #[test]
+    fn test_run_statusline() {
+        let stdin = "this is some text\nthis is another line\n".repeat(1000);
+        let mut stdout = Vec::new();
+        let opts = Options {
+            chars: true,
+            statusline: true,
+            ..default_opts()
+        };
+        run(
+            opts,
+            None,
+            Vec::new(),
+            stdin.as_bytes(),
+            &mut stdout,
+            parse_format("table"),
+        )
+        .unwrap();
+        assert_eq!(
+            "8,000w 39,000c 39,000b 2,000l",
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_run_exit_code() {
+        let code = |files: &[&str]| {
+            let files = files.iter().map(PathBuf::from).collect();
+            run(
+                default_opts(),
+                None,
+                files,
+                io::empty(),
+                Vec::new(),
+                parse_format("csv"),
+            )
+            .unwrap()
+        };
+        assert_eq!(exit::SUCCESS, code(&["Cargo.toml"]));
+        assert_eq!(exit::SOME_FAILED, code(&["Cargo.toml", "does-not-exist"]));
+        assert_eq!(exit::ALL_FAILED, code(&["does-not-exist"]));
+    }
+
     #[test]
     fn test_run_stdin() {
         let stdin = b"this is some text\nthis is another line";
@@ -243,7 +813,7 @@ test_data/ten_mb.txt,10000000,2000000,1000000",
             Vec::new(),
             &stdin[..],
             &mut stdout,
-            Format::CSV,
+            parse_format("csv"),
         )
         .unwrap();
         assert_eq!(
@@ -252,5 +822,49 @@ Stdin,38,8,1
 Totals,38,8,1",
             String::from_utf8(stdout).unwrap()
         );
+
+        let mut stdout = Vec::new();
+        let opts = Options {
+            count_last_line: true,
+            ..default_opts()
+        };
+        run(
+            opts,
+            None,
+            Vec::new(),
+            &stdin[..],
+            &mut stdout,
+            parse_format("csv"),
+        )
+        .unwrap();
+        assert_eq!(
+            r"path,bytes,words,lines
+Stdin,38,8,2
+Totals,38,8,2",
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_with_permit_shares_timeout() {
+        let open_files = Arc::new(OpenFiles::new(1));
+        let held = open_files.acquire();
+        let release = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            drop(held);
+        });
+        // waiting for the handle eats most of the timeout, leaving too little for the count
+        let res = with_permit(&open_files, Some(Duration::from_millis(400)), |_, _| {
+            thread::sleep(Duration::from_millis(200));
+            Ok(())
+        });
+        release.join().unwrap();
+        match res {
+            Err(Error::CUSTOM(msg)) => assert_eq!("timed out after 0.4s", msg),
+            res => panic!("expected a timeout, got {:?}", res),
+        }
+
+        let res = with_permit(&open_files, None, |_, deadline| Ok(deadline));
+        assert_eq!(None, res.unwrap());
     }
 }
@@ -0,0 +1,210 @@
+//! `--markdown-aware`, which leaves front matter and fenced code blocks out of the word counts of
+//! markdown files.
+
+use crate::count::{Count, CountOptions, CountablePath, Counts};
+use crate::error::Error;
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use crate::tokenize::{AsciiWhitespace, Tokenizer};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// The name of the column with the words left out of the word count.
+pub const EXCLUDED_WORDS: &str = "excluded words";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownWords {
+    pub prose: u64,
+    /// Words in front matter and fenced code blocks, including the fences.
+    pub excluded: u64,
+}
+
+pub fn is_markdown(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"),
+        None => false,
+    }
+}
+
+/// The fence character and length if line opens or closes a fenced code block.
+fn fence(line: &[u8]) -> Option<(u8, usize)> {
+    let indent = line.iter().take_while(|&&b| b == b' ').count();
+    if indent > 3 {
+        return None;
+    }
+    let line = &line[indent..];
+    let c = *line.first()?;
+    if c != b'`' && c != b'~' {
+        return None;
+    }
+    let len = line.iter().take_while(|&&b| b == c).count();
+    if len >= 3 {
+        Some((c, len))
+    } else {
+        None
+    }
+}
+
+fn trim_end(line: &[u8]) -> &[u8] {
+    let end = line
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |i| i + 1);
+    &line[..end]
+}
+
+/// Split the words of a markdown document into prose and everything else. Front matter is a
+/// block delimited by `---` (YAML) or `+++` (TOML) lines at the very start of the document.
+pub fn markdown_words<R: Read>(
+    readable: R,
+    tokenizer: &dyn Tokenizer,
+) -> Result<MarkdownWords, Error> {
+    let mut reader = BufReader::new(readable);
+    let mut words = MarkdownWords::default();
+    let mut line = Vec::new();
+    let mut first = true;
+    let mut front_matter: Option<&[u8]> = None;
+    let mut open_fence: Option<(u8, usize)> = None;
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let n = tokenizer.count_words(&line) as u64;
+        let trimmed = trim_end(&line);
+        if first && (trimmed == b"---" || trimmed == b"+++") {
+            front_matter = Some(if trimmed == b"---" { b"---" } else { b"+++" });
+            words.excluded += n;
+        } else if let Some(delim) = front_matter {
+            words.excluded += n;
+            if trimmed == delim || (delim == b"---" && trimmed == b"...") {
+                front_matter = None;
+            }
+        } else if let Some((c, len)) = open_fence {
+            words.excluded += n;
+            // a closing fence has no info string
+            if let Some((close_c, close_len)) = fence(trimmed) {
+                if close_c == c && close_len >= len && trimmed.trim_ascii_start().len() == close_len
+                {
+                    open_fence = None;
+                }
+            }
+        } else if let Some(opened) = fence(trimmed) {
+            words.excluded += n;
+            open_fence = Some(opened);
+        } else {
+            words.prose += n;
+        }
+        first = false;
+        line.clear();
+    }
+    Ok(words)
+}
+
+/// Reports 0 so every row has the column, `count_path` fills it in for markdown files.
+struct Placeholder;
+
+impl Metric for Placeholder {
+    fn update(&mut self, _chunk: &[u8]) {}
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Count(0)
+    }
+}
+
+/// The `EXCLUDED_WORDS` column.
+pub fn excluded_words_metric() -> MetricFactory {
+    MetricFactory::new(EXCLUDED_WORDS, || Placeholder)
+}
+
+/// Count path like `CountablePath::count_with`, but if it's a markdown file only count the words
+/// of its prose.
+pub fn count_path(path: &Path, opts: CountOptions) -> Result<Counts, Error> {
+    let tokenizer = opts.tokenizer.clone();
+    let count_words = opts.words;
+    let mut counts = path.count_with(opts)?;
+    if !is_markdown(path) {
+        return Ok(counts);
+    }
+
+    // markdown files are usually small so the second read is cheap
+    let words = match &tokenizer {
+        Some(tokenizer) => markdown_words(File::open(path)?, tokenizer.as_ref())?,
+        None => markdown_words(File::open(path)?, &AsciiWhitespace)?,
+    };
+    if count_words {
+        counts.words = Count {
+            val: Some(words.prose),
+        };
+    }
+    for (name, value) in counts.metrics.iter_mut() {
+        if name == EXCLUDED_WORDS {
+            *value = MetricValue::Count(words.excluded);
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(doc: &str) -> MarkdownWords {
+        markdown_words(doc.as_bytes(), &AsciiWhitespace).unwrap()
+    }
+
+    #[test]
+    fn test_is_markdown() {
+        assert!(is_markdown(Path::new("README.md")));
+        assert!(is_markdown(Path::new("notes.MARKDOWN")));
+        assert!(!is_markdown(Path::new("main.rs")));
+        assert!(!is_markdown(Path::new("md")));
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let doc = "---\ntitle: A Title\ntags: [a, b]\n---\nsome prose here\n";
+        assert_eq!(
+            MarkdownWords {
+                prose: 3,
+                excluded: 8
+            },
+            words(doc)
+        );
+        let doc = "+++\ntitle = 'x'\n+++\nprose\n";
+        assert_eq!(1, words(doc).prose);
+        // only at the start of the document
+        let doc = "prose\n---\nmore prose\n---\n";
+        assert_eq!(5, words(doc).prose);
+    }
+
+    #[test]
+    fn test_code_fences() {
+        let doc = "intro text\n```rust\nfn main() {}\n```\n  ~~~~\n``` not closed\n~~~~\nthe end\n";
+        assert_eq!(
+            MarkdownWords {
+                prose: 4,
+                excluded: 10
+            },
+            words(doc)
+        );
+        // indented four spaces is an indented code block, not a fence
+        assert_eq!(3, words("    ```\nsome prose\n").prose);
+        // an unclosed fence runs to the end of the document
+        assert_eq!(1, words("prose\n```\ncode code\n").prose);
+    }
+
+    #[test]
+    fn test_count_path() {
+        let path = std::env::temp_dir().join(format!("rwc-markdown-{}.md", std::process::id()));
+        std::fs::write(&path, "# Title\n\n```\nlet x = 1;\n```\n").unwrap();
+        let opts = CountOptions::new()
+            .bytes()
+            .words()
+            .metric(excluded_words_metric());
+        let counts = count_path(&path, opts).unwrap();
+        assert_eq!(Some(28), counts.bytes.val);
+        assert_eq!(Some(2), counts.words.val);
+        assert_eq!(
+            vec![(String::from(EXCLUDED_WORDS), MetricValue::Count(6))],
+            counts.metrics
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+//! `--count-matches` and `--count-lines-matching`, the matches of a regex in each file and the
+//! lines with one like `grep -c`, counted in the same pass as the other columns rather than with
+//! a second grep run.
+
+use crate::error::Error;
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use regex::bytes::Regex;
+
+pub const MATCHING_LINES: &str = "matching lines";
+
+/// Parse the `--count-matches` argument.
+pub fn parse_count_matches(src: &str) -> Result<Regex, Error> {
+    Regex::new(src)
+        .map_err(|err| Error::CUSTOM(format!("invalid --count-matches {}: {}", src, err)))
+}
+
+/// Parse the `--count-lines-matching` argument.
+pub fn parse_count_lines_matching(src: &str) -> Result<Regex, Error> {
+    Regex::new(src)
+        .map_err(|err| Error::CUSTOM(format!("invalid --count-lines-matching {}: {}", src, err)))
+}
+
+/// A column named after regex, like `TODO`, counting its matches. If regex has named groups, like
+/// `(?P<err>ERROR)|(?P<warn>WARN)`, there's a column named after each group instead, counting the
+/// matches it took part in.
+pub fn count_matches_metrics(regex: Regex) -> Vec<MetricFactory> {
+    let groups: Vec<(usize, String)> = regex
+        .capture_names()
+        .enumerate()
+        .filter_map(|(i, name)| name.map(|name| (i, String::from(name))))
+        .collect();
+    if groups.is_empty() {
+        let name = String::from(regex.as_str());
+        return vec![MetricFactory::new(name, move || {
+            CountMatches::new(regex.clone(), Counting::Matches)
+        })];
+    }
+    groups
+        .into_iter()
+        .map(|(i, name)| {
+            let regex = regex.clone();
+            MetricFactory::new(name, move || {
+                CountMatches::new(regex.clone(), Counting::Group(i))
+            })
+        })
+        .collect()
+}
+
+/// The `MATCHING_LINES` column counting the lines regex matches.
+pub fn count_lines_matching_metric(regex: Regex) -> MetricFactory {
+    MetricFactory::new(MATCHING_LINES, move || {
+        CountMatches::new(regex.clone(), Counting::Lines)
+    })
+}
+
+/// What `CountMatches` counts.
+#[derive(Debug, Clone, Copy)]
+enum Counting {
+    Matches,
+    /// The matches in which the capture group with this index took part.
+    Group(usize),
+    /// The lines with any match.
+    Lines,
+}
+
+/// Matches of a regex within each line like grep, so a match never spans lines and the input is
+/// matched up to its last `\n` with the rest carried over to the next chunk.
+#[derive(Debug)]
+pub struct CountMatches {
+    regex: Regex,
+    counting: Counting,
+    /// A line which spans chunks.
+    line: Vec<u8>,
+    matches: u64,
+}
+
+impl CountMatches {
+    fn new(regex: Regex, counting: Counting) -> CountMatches {
+        CountMatches {
+            regex,
+            counting,
+            line: Vec::new(),
+            matches: 0,
+        }
+    }
+
+    fn count(&mut self, lines: &[u8]) {
+        let regex = &self.regex;
+        let counting = self.counting;
+        self.matches += lines
+            .split(|&b| b == b'\n')
+            .map(|line| match counting {
+                Counting::Matches => regex.find_iter(line).count() as u64,
+                Counting::Group(i) => regex
+                    .captures_iter(line)
+                    .filter(|caps| caps.get(i).is_some())
+                    .count() as u64,
+                Counting::Lines => u64::from(regex.is_match(line)),
+            })
+            .sum::<u64>();
+    }
+}
+
+impl Metric for CountMatches {
+    fn update(&mut self, chunk: &[u8]) {
+        let split = match chunk.iter().rposition(|&b| b == b'\n') {
+            Some(i) => i,
+            None => {
+                self.line.extend_from_slice(chunk);
+                return;
+            }
+        };
+        if self.line.is_empty() {
+            self.count(&chunk[..split]);
+        } else {
+            let mut lines = std::mem::take(&mut self.line);
+            lines.extend_from_slice(&chunk[..split]);
+            self.count(&lines);
+        }
+        self.line.extend_from_slice(&chunk[split + 1..]);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        let line = std::mem::take(&mut self.line);
+        if !line.is_empty() {
+            self.count(&line);
+        }
+        MetricValue::Count(self.matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_matches() {
+        let factories = count_matches_metrics(parse_count_matches(r"ERROR|WARN$").unwrap());
+        assert_eq!(1, factories.len());
+        let factory = &factories[0];
+        assert_eq!("ERROR|WARN$", factory.name);
+        let input = "ERROR a\nb WARN\nERRORERROR WARN c\n\nWARN";
+        for chunk in 1..=input.len() {
+            let mut metric = factory.create();
+            for chunk in input.as_bytes().chunks(chunk) {
+                metric.update(chunk);
+            }
+            assert_eq!(MetricValue::Count(5), metric.finalize());
+        }
+
+        let mut metric = count_matches_metrics(parse_count_matches("a\\sb").unwrap())[0].create();
+        metric.update(b"a\nb a b\n");
+        assert_eq!(MetricValue::Count(1), metric.finalize());
+
+        assert!(parse_count_matches("(").is_err());
+    }
+
+    #[test]
+    fn test_count_matches_groups() {
+        let regex = parse_count_matches(r"(?P<err>ERROR)|(?P<warn>WARN)|(INFO)").unwrap();
+        let factories = count_matches_metrics(regex);
+        let names: Vec<_> = factories
+            .iter()
+            .map(|factory| factory.name.as_str())
+            .collect();
+        assert_eq!(vec!["err", "warn"], names);
+        let input = "ERROR WARN\nINFO ERROR\nWARNWARN\n";
+        for chunk in 1..=input.len() {
+            let values: Vec<_> = factories
+                .iter()
+                .map(|factory| {
+                    let mut metric = factory.create();
+                    for chunk in input.as_bytes().chunks(chunk) {
+                        metric.update(chunk);
+                    }
+                    metric.finalize()
+                })
+                .collect();
+            assert_eq!(vec![MetricValue::Count(2), MetricValue::Count(3)], values);
+        }
+    }
+
+    #[test]
+    fn test_count_lines_matching() {
+        let factory = count_lines_matching_metric(parse_count_lines_matching("o+").unwrap());
+        let input = "foo boo\n\nbar\nno";
+        for chunk in 1..=input.len() {
+            let mut metric = factory.create();
+            for chunk in input.as_bytes().chunks(chunk) {
+                metric.update(chunk);
+            }
+            assert_eq!(MetricValue::Count(2), metric.finalize());
+        }
+    }
+}
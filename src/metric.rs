@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+/// An additional per-input metric computed from the raw bytes of the input in the same pass as the
+/// built-in counts, e.g. a hash or a domain-specific count.
+pub trait Metric: Send {
+    /// Called with each consecutive chunk of the input.
+    fn update(&mut self, chunk: &[u8]);
+
+    /// Called once after the last chunk.
+    fn finalize(&mut self) -> MetricValue;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    /// Counts are summed in the totals row.
+    Count(u64),
+    /// The smallest of a column, like the shortest line, is its value in the totals row.
+    Min(u64),
+    /// The largest of a column, like the widest line, is its value in the totals row.
+    Max(u64),
+    Float(f64),
+    /// A quotient like words per line, printed like a float and 0 if the divisor is. The totals
+    /// row divides the totals of each part.
+    Ratio(u64, u64),
+    Text(String),
+    /// Occurrences by value, printed like `1:10 2:4`. Histograms are merged in the totals row.
+    Histogram(BTreeMap<u64, u64>),
+    /// Hashes of distinct values like words, printed as how many there are. The totals row has
+    /// the values distinct across all inputs rather than a sum.
+    Distinct(HashSet<u64>),
+    /// Occurrences by value like a histogram, printed as the most common value, see `mode`.
+    /// Merged in the totals row.
+    Mode(BTreeMap<u64, u64>),
+    /// Occurrences by value printed as their mean, see `mean`. Merged in the totals row.
+    Mean(BTreeMap<u64, u64>),
+    /// Occurrences by value printed as their median, see `median`. Merged in the totals row.
+    Median(BTreeMap<u64, u64>),
+}
+
+/// The most common value of histogram, the smallest of any ties, or 0 if it's empty.
+pub fn mode(histogram: &BTreeMap<u64, u64>) -> u64 {
+    histogram
+        .iter()
+        .rev()
+        .max_by_key(|(_, n)| **n)
+        .map_or(0, |(value, _)| *value)
+}
+
+/// The mean of the values of histogram, or 0 if it's empty.
+pub fn mean(histogram: &BTreeMap<u64, u64>) -> f64 {
+    let n: u64 = histogram.values().sum();
+    let sum: f64 = histogram
+        .iter()
+        .map(|(value, n)| *value as f64 * *n as f64)
+        .sum();
+    if n == 0 {
+        0.0
+    } else {
+        sum / n as f64
+    }
+}
+
+/// The median of the values of histogram, the mean of the two middle values if there's an even
+/// number of them, or 0 if it's empty.
+pub fn median(histogram: &BTreeMap<u64, u64>) -> f64 {
+    let n: u64 = histogram.values().sum();
+    if n == 0 {
+        return 0.0;
+    }
+    // the value at i in order
+    let nth = |i: u64| {
+        let mut seen = 0;
+        for (value, n) in histogram {
+            seen += n;
+            if i < seen {
+                return *value;
+            }
+        }
+        0
+    };
+    let upper = nth(n / 2);
+    let lower = if n % 2 == 0 { nth(n / 2 - 1) } else { upper };
+    (lower as f64 + upper as f64) / 2.0
+}
+
+impl MetricValue {
+    /// The total of two values of a column, if they add up.
+    pub fn add(&self, other: &MetricValue) -> Option<MetricValue> {
+        match (self, other) {
+            (MetricValue::Count(a), MetricValue::Count(b)) => {
+                a.checked_add(*b).map(MetricValue::Count)
+            }
+            (MetricValue::Min(a), MetricValue::Min(b)) => Some(MetricValue::Min(*a.min(b))),
+            (MetricValue::Max(a), MetricValue::Max(b)) => Some(MetricValue::Max(*a.max(b))),
+            (MetricValue::Histogram(a), MetricValue::Histogram(b)) => {
+                merge(a, b).map(MetricValue::Histogram)
+            }
+            (MetricValue::Mode(a), MetricValue::Mode(b)) => merge(a, b).map(MetricValue::Mode),
+            (MetricValue::Mean(a), MetricValue::Mean(b)) => merge(a, b).map(MetricValue::Mean),
+            (MetricValue::Median(a), MetricValue::Median(b)) => {
+                merge(a, b).map(MetricValue::Median)
+            }
+            (MetricValue::Ratio(n1, d1), MetricValue::Ratio(n2, d2)) => Some(MetricValue::Ratio(
+                n1.checked_add(*n2)?,
+                d1.checked_add(*d2)?,
+            )),
+            (MetricValue::Distinct(a), MetricValue::Distinct(b)) => {
+                Some(MetricValue::Distinct(a.union(b).copied().collect()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// n / d as a float, 0 if d is.
+pub fn ratio(n: u64, d: u64) -> f64 {
+    if d == 0 {
+        0.0
+    } else {
+        n as f64 / d as f64
+    }
+}
+
+/// The sum of the occurrences of each value, if none overflow.
+fn merge(a: &BTreeMap<u64, u64>, b: &BTreeMap<u64, u64>) -> Option<BTreeMap<u64, u64>> {
+    let mut sum = a.clone();
+    for (value, n) in b {
+        let total = sum.entry(*value).or_insert(0);
+        *total = total.checked_add(*n)?;
+    }
+    Some(sum)
+}
+
+impl fmt::Display for MetricValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricValue::Count(n) | MetricValue::Min(n) | MetricValue::Max(n) => {
+                write!(f, "{}", n)
+            }
+            MetricValue::Float(n) => write!(f, "{:.3}", n),
+            MetricValue::Ratio(n, d) => write!(f, "{:.3}", ratio(*n, *d)),
+            MetricValue::Text(s) => write!(f, "{}", s),
+            MetricValue::Distinct(hashes) => write!(f, "{}", hashes.len()),
+            MetricValue::Mode(histogram) => write!(f, "{}", mode(histogram)),
+            MetricValue::Mean(histogram) => write!(f, "{:.3}", mean(histogram)),
+            MetricValue::Median(histogram) => write!(f, "{:.3}", median(histogram)),
+            MetricValue::Histogram(histogram) => {
+                let buckets: Vec<String> = histogram
+                    .iter()
+                    .map(|(value, n)| format!("{}:{}", value, n))
+                    .collect();
+                write!(f, "{}", buckets.join(" "))
+            }
+        }
+    }
+}
+
+/// A named `Metric` registered with `CountOptions::metric`, a new metric is created for each
+/// input.
+#[derive(Clone)]
+pub struct MetricFactory {
+    pub name: String,
+    new: Arc<dyn Fn() -> Box<dyn Metric> + Send + Sync>,
+}
+
+impl MetricFactory {
+    pub fn new<N, F, M>(name: N, new: F) -> MetricFactory
+    where
+        N: Into<String>,
+        F: Fn() -> M + Send + Sync + 'static,
+        M: Metric + 'static,
+    {
+        MetricFactory {
+            name: name.into(),
+            new: Arc::new(move || Box::new(new())),
+        }
+    }
+
+    pub fn create(&self) -> Box<dyn Metric> {
+        (self.new)()
+    }
+}
+
+impl fmt::Debug for MetricFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricFactory")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        let a = MetricValue::Histogram(vec![(1, 2), (3, 1)].into_iter().collect());
+        let b = MetricValue::Histogram(vec![(1, 1), (2, 5)].into_iter().collect());
+        assert_eq!("1:3 2:5 3:1", a.add(&b).unwrap().to_string());
+        assert_eq!(
+            Some(MetricValue::Count(5)),
+            MetricValue::Count(2).add(&MetricValue::Count(3))
+        );
+        assert_eq!(None, MetricValue::Count(2).add(&a));
+        assert_eq!(
+            Some(MetricValue::Min(2)),
+            MetricValue::Min(2).add(&MetricValue::Min(3))
+        );
+        assert_eq!(
+            Some(MetricValue::Max(3)),
+            MetricValue::Max(2).add(&MetricValue::Max(3))
+        );
+        let a = MetricValue::Distinct(vec![1, 2].into_iter().collect());
+        let b = MetricValue::Distinct(vec![2, 3].into_iter().collect());
+        assert_eq!("3", a.add(&b).unwrap().to_string());
+        let a = MetricValue::Mode(vec![(2, 3), (4, 1)].into_iter().collect());
+        let b = MetricValue::Mode(vec![(4, 2), (8, 1)].into_iter().collect());
+        assert_eq!("2", a.to_string());
+        assert_eq!("2", a.add(&b).unwrap().to_string());
+        assert_eq!("0", MetricValue::Mode(BTreeMap::new()).to_string());
+        let a: BTreeMap<_, _> = vec![(1, 2), (4, 1)].into_iter().collect();
+        let b: BTreeMap<_, _> = vec![(6, 1)].into_iter().collect();
+        let mean = MetricValue::Mean(a.clone()).add(&MetricValue::Mean(b.clone()));
+        assert_eq!("3.000", mean.unwrap().to_string());
+        assert_eq!("1.000", MetricValue::Median(a.clone()).to_string());
+        let median = MetricValue::Median(a).add(&MetricValue::Median(b));
+        assert_eq!("2.500", median.unwrap().to_string());
+        assert_eq!("0.000", MetricValue::Median(BTreeMap::new()).to_string());
+        let total = MetricValue::Ratio(3, 1)
+            .add(&MetricValue::Ratio(7, 3))
+            .unwrap();
+        assert_eq!("2.500", total.to_string());
+        assert_eq!("0.000", MetricValue::Ratio(3, 0).to_string());
+        assert_eq!(None, MetricValue::Float(1.0).add(&MetricValue::Float(2.0)));
+    }
+}
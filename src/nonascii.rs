@@ -0,0 +1,44 @@
+//! `--non-ascii`, the chars above U+007F in each file, to find non-ascii chars in sources which
+//! should be ascii only.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+
+pub const NON_ASCII: &str = "non-ascii";
+
+/// Chars counted without decoding, as the bytes which start a multi-byte utf-8 sequence. A stray
+/// continuation byte isn't counted, but the bytes 0xf8 to 0xff which are never valid are.
+#[derive(Debug, Default)]
+pub struct NonAscii {
+    chars: u64,
+}
+
+impl Metric for NonAscii {
+    fn update(&mut self, chunk: &[u8]) {
+        self.chars += chunk.iter().filter(|&&b| b >= 0xc0).count() as u64;
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Count(self.chars)
+    }
+}
+
+/// The `NON_ASCII` column.
+pub fn non_ascii_metric() -> MetricFactory {
+    MetricFactory::new(NON_ASCII, NonAscii::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_ascii() {
+        let mut metric = non_ascii_metric().create();
+        metric.update("caf\u{e9} \u{201c}東京\u{201d} 🦀 \u{7f}".as_bytes());
+        assert_eq!(MetricValue::Count(6), metric.finalize());
+
+        let mut metric = non_ascii_metric().create();
+        metric.update(b"plain ascii\n");
+        assert_eq!(MetricValue::Count(0), metric.finalize());
+    }
+}
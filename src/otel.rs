@@ -0,0 +1,121 @@
+//! `--otel-endpoint`, which exports metrics about a run over OTLP.
+
+use crate::count::Counts;
+use crate::error::Error;
+use opentelemetry::metrics::MeterProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{MetricExporter, WithExportConfig};
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Run-level metrics, the same for every output format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunStats {
+    pub files: u64,
+    /// None if bytes weren't counted.
+    pub bytes: Option<u64>,
+    pub errors: u64,
+    pub duration: Duration,
+}
+
+impl RunStats {
+    pub fn new(results: &[(Result<Counts, Error>, PathBuf)], duration: Duration) -> RunStats {
+        let counts: Vec<&Counts> = results
+            .iter()
+            .filter_map(|(res, _)| res.as_ref().ok())
+            .collect();
+        RunStats {
+            files: counts.len() as u64,
+            bytes: counts
+                .iter()
+                // unavailable if any file's bytes are or the total overflows
+                .try_fold(0u64, |total, counts| total.checked_add(counts.bytes.val?)),
+            errors: (results.len() - counts.len()) as u64,
+            duration,
+        }
+    }
+}
+
+fn otel_err<E: std::fmt::Display>(err: E) -> Error {
+    Error::CUSTOM(format!("OpenTelemetry export failed: {}", err))
+}
+
+/// Export stats to the OTLP/gRPC collector at endpoint, blocking until they're flushed.
+pub fn export(endpoint: &str, stats: &RunStats) -> Result<(), Error> {
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(async {
+        let exporter = MetricExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(otel_err)?;
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter, runtime::Tokio).build())
+            .with_resource(Resource::new(vec![KeyValue::new("service.name", "rwc")]))
+            .build();
+
+        let meter = provider.meter("rwc");
+        meter
+            .u64_counter("rwc.files")
+            .with_description("Files counted")
+            .build()
+            .add(stats.files, &[]);
+        meter
+            .u64_counter("rwc.errors")
+            .with_description("Files which couldn't be counted")
+            .build()
+            .add(stats.errors, &[]);
+        if let Some(bytes) = stats.bytes {
+            meter
+                .u64_counter("rwc.bytes")
+                .with_description("Bytes processed")
+                .with_unit("By")
+                .build()
+                .add(bytes, &[]);
+        }
+        meter
+            .f64_histogram("rwc.duration")
+            .with_description("Duration of the run")
+            .with_unit("s")
+            .build()
+            .record(stats.duration.as_secs_f64(), &[]);
+
+        // shutting down flushes the reader
+        provider.shutdown().map_err(otel_err)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::Count;
+
+    fn counts(bytes: Option<u64>) -> Counts {
+        Counts {
+            bytes: Count { val: bytes },
+            chars: Count { val: None },
+            words: Count { val: None },
+            lines: Count { val: Some(1) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_run_stats() {
+        let results = vec![
+            (Ok(counts(Some(10))), PathBuf::from("a")),
+            (Err(Error::UTF8()), PathBuf::from("b")),
+            (Ok(counts(Some(5))), PathBuf::from("c")),
+        ];
+        let stats = RunStats::new(&results, Duration::from_secs(1));
+        assert_eq!(2, stats.files);
+        assert_eq!(Some(15), stats.bytes);
+        assert_eq!(1, stats.errors);
+
+        let results = vec![(Ok(counts(None)), PathBuf::from("a"))];
+        assert_eq!(None, RunStats::new(&results, Duration::from_secs(1)).bytes);
+    }
+}
@@ -0,0 +1,144 @@
+//! `--output` and `--tee`, which write the results to files through a temporary file renamed over
+//! each once complete, so a run which crashes or fails partway never leaves a truncated report
+//! behind.
+
+use crate::cli::Options;
+use crate::console::Uncolored;
+use crate::count::Counts;
+use crate::error::Error;
+use crate::format::{parse_format, Format};
+use crate::print::print;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// A file written next to its destination and renamed over it by `commit`. It's removed if
+/// dropped before then.
+pub struct AtomicFile {
+    path: PathBuf,
+    tmp: PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    /// Create the temporary file for path in the same directory, so the rename can't cross file
+    /// systems.
+    pub fn create(path: &Path) -> Result<AtomicFile, Error> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error::for_path(path, Error::CUSTOM(String::from("not a file"))))?;
+        let mut tmp_name = std::ffi::OsString::from(".");
+        tmp_name.push(name);
+        tmp_name.push(format!(".rwc-{}.tmp", std::process::id()));
+        let tmp = path.with_file_name(tmp_name);
+        let file = File::create(&tmp).map_err(|err| Error::for_path(&tmp, err.into()))?;
+        Ok(AtomicFile {
+            path: path.to_path_buf(),
+            tmp,
+            file: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// Flush and sync what was written, then rename it to the destination.
+    pub fn commit(mut self) -> Result<(), Error> {
+        let res = (|| -> io::Result<()> {
+            if let Some(file) = self.file.take() {
+                let file = file.into_inner().map_err(|err| err.into_error())?;
+                file.sync_all()?;
+            }
+            fs::rename(&self.tmp, &self.path)
+        })();
+        res.map_err(|err| Error::for_path(&self.path, err.into()))
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.file {
+            Some(file) => file.write(buf),
+            None => Err(io::Error::other("already committed")),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        // committed files were renamed away, so this only cleans up after failed runs
+        let _ = fs::remove_file(&self.tmp);
+    }
+}
+
+/// A `--tee` like `csv=report.csv`, the results written to another file in another format.
+#[derive(Debug, Clone)]
+pub struct Tee {
+    pub format: Format,
+    pub path: PathBuf,
+}
+
+pub fn parse_tee(src: &str) -> Result<Tee, Error> {
+    let mut parts = src.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(format), Some(path)) if !path.is_empty() => Ok(Tee {
+            format: parse_format(format),
+            path: PathBuf::from(path),
+        }),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --tee {}, expected <format>=<path> like csv=report.csv",
+            src
+        ))),
+    }
+}
+
+/// Print results to the file of tee in its format, without colors.
+pub fn write_tee(
+    tee: &Tee,
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+) -> Result<(), Error> {
+    let mut file = Uncolored::new(AtomicFile::create(&tee.path)?);
+    print(tee.format.clone(), results, opts, &mut file)?;
+    file.into_inner().commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_parse_tee() {
+        let tee = parse_tee("csv=out/a=b.csv").unwrap();
+        assert_eq!(Some("csv"), tee.format.name());
+        assert_eq!(PathBuf::from("out/a=b.csv"), tee.path);
+        assert!(parse_tee("csv").is_err());
+        assert!(parse_tee("csv=").is_err());
+    }
+
+    #[test]
+    fn test_atomic_file() {
+        let dir = env::temp_dir().join(format!("rwc-output-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("report.csv");
+        fs::write(&path, "old").unwrap();
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"new").unwrap();
+        assert_eq!("old", fs::read_to_string(&path).unwrap());
+        drop(file);
+        assert_eq!("old", fs::read_to_string(&path).unwrap());
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"new").unwrap();
+        file.commit().unwrap();
+        assert_eq!("new", fs::read_to_string(&path).unwrap());
+        assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+//! `--format parquet`, which writes the counts of each file to an Apache Parquet file so runs over
+//! millions of files can be loaded straight into DuckDB or Spark.
+
+use crate::cli::Options;
+use crate::console;
+use crate::count::Counts;
+use crate::error::Error;
+use crate::print::{plain_row_error, Printer};
+use ::parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use ::parquet::errors::ParquetError;
+use ::parquet::file::properties::WriterProperties;
+use ::parquet::file::writer::SerializedFileWriter;
+use ::parquet::schema::parser::parse_message_type;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn parquet_error(err: ParquetError) -> Error {
+    Error::CUSTOM(format!("Parquet error: {}", err))
+}
+
+/// A row for each file, in the same shape as the `--output-db` table. Counts which weren't
+/// computed are null, and `error` is set if the file couldn't be counted, or only partially.
+const SCHEMA: &str = "message counts {
+    REQUIRED BYTE_ARRAY path (UTF8);
+    OPTIONAL INT64 bytes;
+    OPTIONAL INT64 chars;
+    OPTIONAL INT64 words;
+    OPTIONAL INT64 lines;
+    OPTIONAL BYTE_ARRAY error (UTF8);
+}";
+
+/// The values and definition levels of an optional column.
+fn optional<T>(vals: Vec<Option<T>>) -> (Vec<T>, Vec<i16>) {
+    let levels = vals.iter().map(|val| val.is_some() as i16).collect();
+    (vals.into_iter().flatten().collect(), levels)
+}
+
+/// Write results to w as a Parquet file, which is built in memory first since the writer needs to
+/// own its sink.
+pub fn write<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    mut w: W,
+) -> Result<(), Error> {
+    let res = (|| {
+        let schema = Arc::new(parse_message_type(SCHEMA)?);
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(Vec::new(), schema, props)?;
+        let mut group = writer.next_row_group()?;
+
+        let paths: Vec<ByteArray> = results
+            .iter()
+            .map(|(_, path)| ByteArray::from(console::display_path(path).as_str()))
+            .collect();
+        if let Some(mut col) = group.next_column()? {
+            col.typed::<ByteArrayType>()
+                .write_batch(&paths, None, None)?;
+            col.close()?;
+        }
+
+        let counts: Vec<Option<&Counts>> = results
+            .iter()
+            .map(|(res, _)| match res {
+                Ok(counts) => Some(counts),
+                Err(err) => err.partial_counts(),
+            })
+            .collect();
+        let getters: [fn(&Counts) -> Option<u64>; 4] = [
+            |c| c.bytes.val,
+            |c| c.chars.val,
+            |c| c.words.val,
+            |c| c.lines.val,
+        ];
+        for get in getters.iter() {
+            let (vals, levels) = optional(
+                counts
+                    .iter()
+                    .map(|counts| counts.and_then(get).map(|n| n as i64))
+                    .collect(),
+            );
+            if let Some(mut col) = group.next_column()? {
+                col.typed::<Int64Type>()
+                    .write_batch(&vals, Some(&levels), None)?;
+                col.close()?;
+            }
+        }
+
+        let (errors, levels) = optional(
+            results
+                .iter()
+                .map(|(res, _)| {
+                    res.as_ref()
+                        .err()
+                        .map(|err| ByteArray::from(plain_row_error(err).as_str()))
+                })
+                .collect(),
+        );
+        if let Some(mut col) = group.next_column()? {
+            col.typed::<ByteArrayType>()
+                .write_batch(&errors, Some(&levels), None)?;
+            col.close()?;
+        }
+
+        group.close()?;
+        writer.into_inner()
+    })();
+    w.write_all(&res.map_err(parquet_error)?)?;
+    Ok(())
+}
+
+/// The `parquet` format, see `write`.
+#[derive(Debug)]
+pub struct ParquetPrinter;
+
+impl Printer for ParquetPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        _: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        write(results, w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::Count;
+    use ::parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::env;
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_write() {
+        let out = env::temp_dir().join(format!("rwc-parquet-{}.parquet", std::process::id()));
+        let counts = Counts {
+            bytes: Count { val: Some(1) },
+            chars: Count { val: None },
+            words: Count { val: Some(2) },
+            lines: Count { val: Some(3) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts), PathBuf::from("a")),
+            (Err(Error::UTF8()), PathBuf::from("b")),
+        ];
+        write(&results, File::create(&out).unwrap()).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&out).unwrap()).unwrap();
+        let rows: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        fs::remove_file(&out).unwrap();
+        assert_eq!(
+            vec![
+                String::from(
+                    "{path: \"a\", bytes: 1, chars: null, words: 2, lines: 3, error: null}"
+                ),
+                format!(
+                    "{{path: \"b\", bytes: null, chars: null, words: null, lines: null, error: {:?}}}",
+                    Error::UTF8().plain_report()
+                ),
+            ],
+            rows
+        );
+    }
+}
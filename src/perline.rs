@@ -0,0 +1,88 @@
+//! `--words-per-line`, the words of each file divided by its lines, also for the totals row so
+//! it needn't be worked out in a spreadsheet.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use crate::tokenize::Tokenizer;
+use std::sync::Arc;
+
+pub const WORDS_PER_LINE: &str = "words/line";
+
+/// Words split by a tokenizer like the word count, divided by the `\n`s like wc's lines, so the
+/// words of a last line without a `\n` count but the line doesn't.
+#[derive(Debug)]
+pub struct WordsPerLine {
+    tokenizer: Arc<dyn Tokenizer>,
+    words: u64,
+    lines: u64,
+    /// A line which spans chunks.
+    line: Vec<u8>,
+}
+
+impl Metric for WordsPerLine {
+    fn update(&mut self, chunk: &[u8]) {
+        for piece in chunk.split_inclusive(|&b| b == b'\n') {
+            match piece.split_last() {
+                Some((b'\n', line)) if self.line.is_empty() => {
+                    self.words += self.tokenizer.count_words(line) as u64;
+                    self.lines += 1;
+                }
+                Some((b'\n', line)) => {
+                    self.line.extend_from_slice(line);
+                    self.words += self.tokenizer.count_words(&self.line) as u64;
+                    self.lines += 1;
+                    self.line.clear();
+                }
+                _ => self.line.extend_from_slice(piece),
+            }
+        }
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        let line = std::mem::take(&mut self.line);
+        self.words += self.tokenizer.count_words(&line) as u64;
+        MetricValue::Ratio(self.words, self.lines)
+    }
+}
+
+/// The `WORDS_PER_LINE` column with words split by tokenizer.
+pub fn words_per_line_metric(tokenizer: Arc<dyn Tokenizer>) -> MetricFactory {
+    MetricFactory::new(WORDS_PER_LINE, move || WordsPerLine {
+        tokenizer: tokenizer.clone(),
+        words: 0,
+        lines: 0,
+        line: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize::{AsciiWhitespace, UnicodeWords};
+
+    fn words_per_line(tokenizer: Arc<dyn Tokenizer>, input: &str, chunk: usize) -> MetricValue {
+        let mut metric = words_per_line_metric(tokenizer).create();
+        for chunk in input.as_bytes().chunks(chunk) {
+            metric.update(chunk);
+        }
+        metric.finalize()
+    }
+
+    #[test]
+    fn test_words_per_line() {
+        let input = "one two\n\nthree,four five\nsix";
+        for chunk in 1..input.len() {
+            assert_eq!(
+                MetricValue::Ratio(5, 3),
+                words_per_line(Arc::new(AsciiWhitespace), input, chunk)
+            );
+        }
+        assert_eq!(
+            MetricValue::Ratio(6, 3),
+            words_per_line(Arc::new(UnicodeWords), input, 64)
+        );
+        assert_eq!(
+            "0.000",
+            words_per_line(Arc::new(AsciiWhitespace), "", 64).to_string()
+        );
+    }
+}
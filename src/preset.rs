@@ -0,0 +1,185 @@
+//! `--count-preset`, columns counting common patterns in logs without writing the regex.
+
+use crate::error::Error;
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use crate::urls::{count_urls_metric, URLS};
+use regex::bytes::Regex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Emails,
+    Ipv4,
+    Uuids,
+    Urls,
+}
+
+pub fn parse_preset(src: &str) -> Result<Preset, Error> {
+    match src {
+        "emails" => Ok(Preset::Emails),
+        "ipv4" => Ok(Preset::Ipv4),
+        "uuids" => Ok(Preset::Uuids),
+        "urls" => Ok(Preset::Urls),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --count-preset {}, expected emails, ipv4, uuids or urls",
+            src
+        ))),
+    }
+}
+
+/// An address octet, 0 to 255 without leading zeros.
+const OCTET: &str = "(?:25[0-5]|2[0-4][0-9]|1[0-9]{2}|[1-9]?[0-9])";
+
+/// Leftover bytes without whitespace which are matched on their own rather than carried over
+/// to the next chunk, much longer than anything a preset matches.
+const MAX_CARRY: usize = 64 * 1024;
+
+impl Preset {
+    /// The name of the column.
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Emails => "emails",
+            Preset::Ipv4 => "ipv4",
+            Preset::Uuids => "uuids",
+            Preset::Urls => URLS,
+        }
+    }
+
+    fn regex(self) -> Regex {
+        let pattern = match self {
+            Preset::Emails => {
+                String::from(r"(?i-u)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b")
+            }
+            Preset::Ipv4 => format!(r"(?-u)\b(?:{0}\.){{3}}{0}\b", OCTET),
+            Preset::Uuids => String::from(
+                r"(?i-u)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b",
+            ),
+            Preset::Urls => unreachable!("urls are counted by CountUrls"),
+        };
+        Regex::new(&pattern).unwrap()
+    }
+}
+
+/// The column counting preset.
+pub fn preset_metric(preset: Preset) -> MetricFactory {
+    if preset == Preset::Urls {
+        return count_urls_metric();
+    }
+    let regex = preset.regex();
+    MetricFactory::new(preset.name(), move || PresetMatches {
+        preset,
+        regex: regex.clone(),
+        carry: Vec::new(),
+        matches: 0,
+    })
+}
+
+/// Matches of a preset regex. None of the presets match whitespace, so the input is matched up
+/// to its last whitespace and the rest is carried over to the next chunk.
+#[derive(Debug)]
+pub struct PresetMatches {
+    preset: Preset,
+    regex: Regex,
+    carry: Vec<u8>,
+    matches: u64,
+}
+
+impl PresetMatches {
+    fn count(&mut self, haystack: &[u8]) {
+        let preset = self.preset;
+        self.matches += self
+            .regex
+            .find_iter(haystack)
+            .filter(|m| preset != Preset::Ipv4 || !dotted_further(haystack, m.start(), m.end()))
+            .count() as u64;
+    }
+}
+
+/// Whether an address match is part of a longer dotted number like the version `1.2.3.4.5`,
+/// which the regex can't rule out without lookaround.
+fn dotted_further(haystack: &[u8], start: usize, end: usize) -> bool {
+    let after = &haystack[end..];
+    (start > 0 && haystack[start - 1] == b'.')
+        || (after.first() == Some(&b'.') && after.get(1).is_some_and(u8::is_ascii_digit))
+}
+
+impl Metric for PresetMatches {
+    fn update(&mut self, chunk: &[u8]) {
+        let split = match chunk.iter().rposition(u8::is_ascii_whitespace) {
+            Some(i) => i + 1,
+            None => {
+                self.carry.extend_from_slice(chunk);
+                if self.carry.len() > MAX_CARRY {
+                    let carry = std::mem::take(&mut self.carry);
+                    self.count(&carry);
+                }
+                return;
+            }
+        };
+        if self.carry.is_empty() {
+            self.count(&chunk[..split]);
+        } else {
+            let mut haystack = std::mem::take(&mut self.carry);
+            haystack.extend_from_slice(&chunk[..split]);
+            self.count(&haystack);
+        }
+        self.carry.extend_from_slice(&chunk[split..]);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        let carry = std::mem::take(&mut self.carry);
+        self.count(&carry);
+        MetricValue::Count(self.matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(preset: Preset, input: &str) -> Vec<MetricValue> {
+        let factory = preset_metric(preset);
+        (1..=input.len())
+            .map(|chunk| {
+                let mut metric = factory.create();
+                for chunk in input.as_bytes().chunks(chunk) {
+                    metric.update(chunk);
+                }
+                metric.finalize()
+            })
+            .collect()
+    }
+
+    fn assert_counts(expected: u64, preset: Preset, input: &str) {
+        for count in count(preset, input) {
+            assert_eq!(MetricValue::Count(expected), count, "{:?}", preset);
+        }
+    }
+
+    #[test]
+    fn test_parse_preset() {
+        assert_eq!(Preset::Ipv4, parse_preset("ipv4").unwrap());
+        assert!(parse_preset("phones").is_err());
+    }
+
+    #[test]
+    fn test_presets() {
+        assert_counts(
+            3,
+            Preset::Emails,
+            "from=<Jane.Doe+logs@Example.co.uk> to=ops@example.com,a_b@c-d.io x@y @z.com a@b.c",
+        );
+        assert_counts(
+            3,
+            Preset::Ipv4,
+            "10.0.0.1 - GET (192.168.1.255) 255.255.255.255:80 256.1.1.1 1.2.3 v1.2.3.4.5 01.2.3.4",
+        );
+        assert_counts(
+            2,
+            Preset::Uuids,
+            "id=123E4567-e89b-12d3-a456-426614174000 550e8400-e29b-41d4-a716-446655440000 \
+             550e8400-e29b-41d4-a716-44665544000 g50e8400-e29b-41d4-a716-446655440000",
+        );
+        assert_counts(1, Preset::Urls, "see https://example.com");
+        assert_counts(0, Preset::Emails, "");
+    }
+}
@@ -1,36 +1,392 @@
 use crate::cli::Options;
-use crate::count::Counts;
-use crate::error::Error;
-use crate::format::Format;
+use crate::console;
+use crate::count::{Count, CountKind, Counts, Total};
+use crate::error::{Error, Report};
+use crate::format::{Format, Formats, Template};
+use crate::metric::{mean, median, mode, ratio, MetricValue};
+use crate::size;
 use colored::*;
+use num_format::{Locale, ToFormattedString};
+use serde_json::{json, Map, Value};
 use std::fmt;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use term_table::row::Row;
 use term_table::table_cell::Alignment;
 use term_table::table_cell::TableCell;
-use term_table::{Table, TableStyle};
+use term_table::Table;
+
+/// Writes the results of a run in some format. Each built-in format is one, registered by name in
+/// `format::Formats::default()`, and library users can add their own with `Formats::register`.
+pub trait Printer: fmt::Debug + Send + Sync {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error>;
+}
+
+impl Printer for Format {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        match self {
+            Format::Named(_, Some(printer)) => printer.print(results, opts, w),
+            Format::Named(name, None) => Formats::default().parse(name)?.print(results, opts, w),
+            Format::Template(template) => print_template(template, results, opts, w),
+            Format::Null => print0(results, opts, w),
+        }
+    }
+}
+
+/// The `table` format, see `print_table`.
+#[derive(Debug)]
+pub struct TablePrinter;
+
+impl Printer for TablePrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_table(results, opts, w)
+    }
+}
+
+/// The `csv` format, see `print_csv`.
+#[derive(Debug)]
+pub struct CsvPrinter;
+
+impl Printer for CsvPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_csv(results, opts, w)
+    }
+}
+
+/// The `json` format, an array of objects, see `print_json`.
+#[derive(Debug)]
+pub struct JsonPrinter;
+
+impl Printer for JsonPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_json(results, opts, w)
+    }
+}
+
+/// The `xml` format, a `<rwc>` element, see `print_xml`.
+#[derive(Debug)]
+pub struct XmlPrinter;
+
+impl Printer for XmlPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_xml(results, opts, w)
+    }
+}
+
+/// The `wc` format, the layout of GNU wc, see `print_wc`.
+#[derive(Debug)]
+pub struct WcPrinter;
+
+impl Printer for WcPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_wc(results, opts, w)
+    }
+}
+
+/// The `prometheus` format, the Prometheus text exposition format, see `print_prometheus`.
+#[derive(Debug)]
+pub struct PrometheusPrinter;
+
+impl Printer for PrometheusPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_prometheus(results, opts, w)
+    }
+}
+
+/// The `org` format, an Emacs org-mode table, see `print_org`.
+#[derive(Debug)]
+pub struct OrgPrinter;
+
+impl Printer for OrgPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_org(results, opts, w)
+    }
+}
+
+/// The `latex` format, a LaTeX `tabular`, see `print_latex`.
+#[derive(Debug)]
+pub struct LatexPrinter;
+
+impl Printer for LatexPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        opts: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_latex(results, opts, w)
+    }
+}
+
+/// The `arrow` format, an Arrow IPC file, see `print_arrow`.
+#[cfg(feature = "arrow")]
+#[derive(Debug)]
+pub struct ArrowPrinter;
+
+#[cfg(feature = "arrow")]
+impl Printer for ArrowPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        _: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        print_arrow(results, w)
+    }
+}
 
 pub fn print<W: Write>(
     fmt: Format,
-    results: Vec<(Result<Counts, Error>, PathBuf)>,
+    results: &[(Result<Counts, Error>, PathBuf)],
     opts: &Options,
-    w: W,
+    mut w: W,
 ) -> Result<(), Error> {
-    match fmt {
-        Format::Table => print_table(results, opts, w)?,
-        Format::CSV => print_csv(results, opts, w)?,
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("print", format = ?fmt, rows = results.len()).entered();
+    fmt.print(results, opts, &mut w)
+}
+
+/// The label of the row counting standard input.
+pub const STDIN: &str = "Stdin";
+
+/// The column of `--max-line-length`.
+pub const MAX_LINE_LENGTH: &str = "max line length";
+
+/// Print a single line like `12,345w 1,234l` without colors, for editor statuslines.
+pub fn print_statusline<W: Write>(counts: &Counts, opts: &Options, mut w: W) -> Result<(), Error> {
+    fn compact(count: Count, suffix: &str) -> String {
+        match count.val {
+            Some(n) => format!("{}{}", n.to_formatted_string(&Locale::en), suffix),
+            None => format!("N/A{}", suffix),
+        }
+    }
+
+    let mut parts = Vec::new();
+    if opts.words {
+        parts.push(compact(counts.words, "w"));
+    }
+    if opts.chars {
+        parts.push(compact(counts.chars, "c"));
+    }
+    if opts.bytes {
+        parts.push(compact(counts.bytes, "b"));
+    }
+    if opts.lines {
+        parts.push(compact(counts.lines, "l"));
     }
+    write!(w, "{}", parts.join(" "))?;
     Ok(())
 }
 
+/// At most this many paths are listed in the summary of unreadable files.
+const SUMMARY_PATHS: usize = 10;
+
+/// A line like `3 files unreadable: a, b, c` if any of results failed, so errors aren't lost in
+/// a long table.
+pub fn error_summary(results: &[(Result<Counts, Error>, PathBuf)]) -> Option<String> {
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|(res, _)| res.is_err())
+        .map(|(_, path)| console::display_path(path))
+        .collect();
+    if failed.is_empty() {
+        return None;
+    }
+    let noun = if failed.len() == 1 { "file" } else { "files" };
+    let mut summary = format!(
+        "{}: {}",
+        format!("{} {} unreadable", failed.len(), noun).red().bold(),
+        failed[..failed.len().min(SUMMARY_PATHS)].join(", ")
+    );
+    if failed.len() > SUMMARY_PATHS {
+        summary.push_str(&format!(", and {} more", failed.len() - SUMMARY_PATHS));
+    }
+    Some(summary)
+}
+
+/// The row already shows the path so don't repeat it in the error.
+pub(crate) fn row_error(err: &Error) -> Report<'_> {
+    match err {
+        Error::FORPATH { source, .. } => source.report(),
+        _ => err.report(),
+    }
+}
+
+/// Like `row_error` without colors.
+pub(crate) fn plain_row_error(err: &Error) -> String {
+    match err {
+        Error::FORPATH { source, .. } => source.plain_report(),
+        _ => err.plain_report(),
+    }
+}
+
+/// Whether any of results stopped partway, so needs the `partial` column.
+fn any_partial(results: &[(Result<Counts, Error>, PathBuf)]) -> bool {
+    results
+        .iter()
+        .any(|(res, _)| matches!(res, Err(err) if err.partial_counts().is_some()))
+}
+
+/// The counts of a row, if any, and the error which stopped them partway.
+pub(crate) fn row_counts(res: &Result<Counts, Error>) -> Option<(&Counts, Option<&Error>)> {
+    match res {
+        Ok(counts) => Some((counts, None)),
+        Err(err) => err.partial_counts().map(|counts| (counts, Some(err))),
+    }
+}
+
+/// The label of the totals row, `Totals*` if any of results failed so the totals are too low.
+fn totals_label(failed: usize) -> &'static str {
+    if failed > 0 {
+        "Totals*"
+    } else {
+        "Totals"
+    }
+}
+
+/// Explains the `*` of `Totals*`.
+fn totals_footnote(failed: usize) -> String {
+    let noun = if failed == 1 { "file" } else { "files" };
+    format!(
+        "* {} {} couldn't be counted, or only partially, so the totals are too low",
+        failed, noun
+    )
+}
+
+/// The metrics and size columns, in the order of `Counts::metrics`.
+fn metric_columns(opts: &Options) -> usize {
+    opts.metrics.len() + size::columns(opts).len()
+}
+
+/// The total of a metric column, only available while every value adds up, see
+/// `MetricValue::add`. A missing value like the size of stdin makes it unavailable too.
+#[derive(Debug, Clone)]
+enum MetricTotal {
+    Empty,
+    Sum(MetricValue),
+    Unavailable,
+}
+
+impl MetricTotal {
+    fn add(self, value: Option<&MetricValue>) -> MetricTotal {
+        match (self, value) {
+            (MetricTotal::Empty, Some(value @ MetricValue::Count(_)))
+            | (MetricTotal::Empty, Some(value @ MetricValue::Min(_)))
+            | (MetricTotal::Empty, Some(value @ MetricValue::Max(_)))
+            | (MetricTotal::Empty, Some(value @ MetricValue::Histogram(_)))
+            | (MetricTotal::Empty, Some(value @ MetricValue::Distinct(_)))
+            | (MetricTotal::Empty, Some(value @ MetricValue::Mode(_)))
+            | (MetricTotal::Empty, Some(value @ MetricValue::Mean(_)))
+            | (MetricTotal::Empty, Some(value @ MetricValue::Median(_)))
+            | (MetricTotal::Empty, Some(value @ MetricValue::Ratio(..))) => {
+                MetricTotal::Sum(value.clone())
+            }
+            (MetricTotal::Sum(total), Some(value)) => match total.add(value) {
+                Some(sum) => MetricTotal::Sum(sum),
+                None => MetricTotal::Unavailable,
+            },
+            _ => MetricTotal::Unavailable,
+        }
+    }
+}
+
+impl fmt::Display for MetricTotal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricTotal::Empty => write!(f, "0"),
+            MetricTotal::Sum(total) => write!(f, "{}", total),
+            MetricTotal::Unavailable => write!(f, "N/A"),
+        }
+    }
+}
+
+/// The totals row of results.
+struct Totals {
+    bytes: Total,
+    chars: Total,
+    words: Total,
+    lines: Total,
+    /// The longest of the max line lengths, rather than their sum.
+    max_line_length: Total,
+    metrics: Vec<MetricTotal>,
+}
+
+fn totals(results: &[(Result<Counts, Error>, PathBuf)], opts: &Options) -> Totals {
+    let mut totals = Totals {
+        bytes: Total::default(),
+        chars: Total::default(),
+        words: Total::default(),
+        lines: Total::default(),
+        max_line_length: Total::default(),
+        metrics: vec![MetricTotal::Empty; metric_columns(opts)],
+    };
+    for (c, _) in results.iter().filter_map(|(res, _)| row_counts(res)) {
+        totals.bytes = totals.bytes + c.bytes;
+        totals.chars = totals.chars + c.chars;
+        totals.words = totals.words + c.words;
+        totals.lines = totals.lines + c.lines;
+        if let Some(n) = c.max_line_length.val {
+            totals.max_line_length.val = totals.max_line_length.val.max(n);
+        }
+        for (i, total) in totals.metrics.iter_mut().enumerate() {
+            *total = total.clone().add(c.metrics.get(i).map(|(_, value)| value));
+        }
+    }
+    totals
+}
+
 fn print_table<W: Write>(
-    results: Vec<(Result<Counts, Error>, PathBuf)>,
+    results: &[(Result<Counts, Error>, PathBuf)],
     opts: &Options,
     mut w: W,
 ) -> Result<(), Error> {
     let mut table = Table::new();
-    table.style = TableStyle::rounded();
+    table.style = console::table_style();
 
     fn make_cell<'a, T: fmt::Display>(data: &T) -> TableCell<'a> {
         TableCell::new_with_alignment_and_padding(data, 1, Alignment::Left, true)
@@ -49,38 +405,63 @@ fn print_table<W: Write>(
     if opts.lines {
         header.push(make_cell(&"lines".blue().bold()));
     }
+    if opts.max_line_length {
+        header.push(make_cell(&MAX_LINE_LENGTH.blue().bold()));
+    }
+    for metric in &opts.metrics {
+        header.push(make_cell(&metric.name.blue().bold()));
+    }
+    for column in size::columns(opts) {
+        header.push(make_cell(&column.blue().bold()));
+    }
+    let partial = any_partial(results);
+    if partial {
+        header.push(make_cell(&"partial".blue().bold()));
+    }
+    let failed = results.iter().filter(|(res, _)| res.is_err()).count();
     table.add_row(Row::new(header));
+    let total = totals(results, opts);
 
-    let mut total_bytes: usize = 0;
-    let mut total_chars: usize = 0;
-    let mut total_words: usize = 0;
-    let mut total_lines: usize = 0;
-
-    for pair in results {
-        let (res, path) = pair;
-        let mut cells = vec![make_cell(&path.display().to_string().green().bold())];
-        match res {
-            Ok(c) => {
+    for (res, path) in results {
+        let path = console::display_path(path);
+        let path = if res.is_ok() {
+            path.green()
+        } else {
+            path.red()
+        };
+        let mut cells = vec![make_cell(&path.bold())];
+        match row_counts(res) {
+            Some((c, err)) => {
                 if opts.bytes {
                     cells.push(make_cell(&c.bytes));
-                    total_bytes = total_bytes + c.bytes;
                 }
                 if opts.chars {
                     cells.push(make_cell(&c.chars));
-                    total_chars = total_chars + c.chars;
                 }
                 if opts.words {
                     cells.push(make_cell(&c.words));
-                    total_words = total_words + c.words;
                 }
                 if opts.lines {
                     cells.push(make_cell(&c.lines));
-                    total_lines = total_lines + c.lines;
+                }
+                if opts.max_line_length {
+                    cells.push(make_cell(&c.max_line_length));
+                }
+                for i in 0..metric_columns(opts) {
+                    let value = c.metrics.get(i).map(|(_, value)| value);
+                    cells.push(make_cell(
+                        &value.map(ToString::to_string).unwrap_or_default(),
+                    ));
+                }
+                if partial {
+                    let marker = err.map(|err| row_error(err).to_string());
+                    cells.push(make_cell(&marker.unwrap_or_default()));
                 }
             }
-            Err(err) => {
+            None => {
+                let err = res.as_ref().unwrap_err();
                 cells.push(TableCell::new_with_alignment_and_padding(
-                    err,
+                    row_error(err),
                     table.rows[0].cells.len() - 1,
                     Alignment::Center,
                     false,
@@ -91,107 +472,847 @@ fn print_table<W: Write>(
     }
 
     if opts.show_totals {
-        let mut totals = vec![make_cell(&"Totals".magenta().bold())];
+        let mut totals = vec![make_cell(&totals_label(failed).magenta().bold())];
         if opts.bytes {
-            totals.push(make_cell(&total_bytes));
+            totals.push(make_cell(&total.bytes));
         }
         if opts.chars {
-            totals.push(make_cell(&total_chars));
+            totals.push(make_cell(&total.chars));
         }
         if opts.words {
-            totals.push(make_cell(&total_words));
+            totals.push(make_cell(&total.words));
         }
         if opts.lines {
-            totals.push(make_cell(&total_lines));
+            totals.push(make_cell(&total.lines));
+        }
+        if opts.max_line_length {
+            totals.push(make_cell(&total.max_line_length));
+        }
+        for metric in &total.metrics {
+            totals.push(make_cell(metric));
+        }
+        if partial {
+            totals.push(make_cell(&""));
         }
         table.add_row(Row::new(totals));
     }
 
     write!(w, "{}", table.render())?;
+    if opts.show_totals && failed > 0 {
+        write!(w, "\n{}", totals_footnote(failed).yellow())?;
+    }
     Ok(())
 }
 
 fn print_csv<W: Write>(
-    results: Vec<(Result<Counts, Error>, PathBuf)>,
+    results: &[(Result<Counts, Error>, PathBuf)],
     opts: &Options,
     mut w: W,
 ) -> Result<(), Error> {
-    let mut rows = Vec::new();
+    let partial = any_partial(results);
+    let mut rows = vec![csv_record(&csv_header(opts, partial), opts)];
+    for (res, path) in results {
+        rows.push(csv_record(&csv_row(res, path, opts, partial), opts));
+    }
+    if opts.show_totals {
+        rows.push(csv_record(&csv_totals(results, opts, partial), opts));
+    }
+    write!(w, "{}", rows.join("\n"))?;
+    Ok(())
+}
+
+/// Join fields with the `--delimiter`, quoting those containing it, a quote, or a line break as in
+/// RFC 4180 unless `--no-csv-quote`.
+pub(crate) fn csv_record<S: AsRef<str>>(fields: &[S], opts: &Options) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            let special = [opts.csv_delimiter, '"', '\n', '\r'];
+            if opts.csv_quote && field.contains(&special[..]) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                String::from(field)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&opts.csv_delimiter.to_string())
+}
 
-    let mut header = vec!["path"];
+/// The column names of the csv output, with a `partial` column saying why a row's counts are
+/// incomplete if partial.
+pub(crate) fn csv_header(opts: &Options, partial: bool) -> Vec<String> {
+    let mut header = vec![String::from("path")];
     if opts.bytes {
-        header.push("bytes");
+        header.push(String::from("bytes"));
     }
     if opts.chars {
-        header.push("chars");
+        header.push(String::from("chars"));
     }
     if opts.words {
-        header.push("words");
+        header.push(String::from("words"));
     }
     if opts.lines {
-        header.push("lines");
+        header.push(String::from("lines"));
+    }
+    if opts.max_line_length {
+        header.push(String::from(MAX_LINE_LENGTH));
+    }
+    for metric in &opts.metrics {
+        header.push(metric.name.clone());
+    }
+    header.extend(size::columns(opts).into_iter().map(String::from));
+    if partial {
+        header.push(String::from("partial"));
     }
-    rows.push(header.join(","));
+    header
+}
 
-    let mut total_bytes: usize = 0;
-    let mut total_chars: usize = 0;
-    let mut total_words: usize = 0;
-    let mut total_lines: usize = 0;
+pub(crate) fn csv_row(
+    res: &Result<Counts, Error>,
+    path: &Path,
+    opts: &Options,
+    partial: bool,
+) -> Vec<String> {
+    row_cells(res, path, opts, partial, |err| row_error(err).to_string())
+}
 
-    for pair in results {
-        let (res, path) = pair;
-        let mut cells = vec![path.display().to_string()];
-        match res {
-            Ok(c) => {
-                if opts.bytes {
-                    cells.push(c.bytes.to_string());
-                    total_bytes = total_bytes + c.bytes;
-                }
-                if opts.chars {
-                    cells.push(c.chars.to_string());
-                    total_chars = total_chars + c.chars;
-                }
-                if opts.words {
-                    cells.push(c.words.to_string());
-                    total_words = total_words + c.words;
-                }
-                if opts.lines {
-                    cells.push(c.lines.to_string());
-                    total_lines = total_lines + c.lines;
+/// Like `csv_row` with errors without colors, for formats which never end up on a terminal.
+pub(crate) fn plain_csv_row(
+    res: &Result<Counts, Error>,
+    path: &Path,
+    opts: &Options,
+    partial: bool,
+) -> Vec<String> {
+    row_cells(res, path, opts, partial, plain_row_error)
+}
+
+fn row_cells(
+    res: &Result<Counts, Error>,
+    path: &Path,
+    opts: &Options,
+    partial: bool,
+    error: fn(&Error) -> String,
+) -> Vec<String> {
+    let mut cells = vec![console::display_path(path)];
+    match row_counts(res) {
+        Some((c, err)) => {
+            if opts.bytes {
+                cells.push(c.bytes.to_string());
+            }
+            if opts.chars {
+                cells.push(c.chars.to_string());
+            }
+            if opts.words {
+                cells.push(c.words.to_string());
+            }
+            if opts.lines {
+                cells.push(c.lines.to_string());
+            }
+            if opts.max_line_length {
+                cells.push(c.max_line_length.to_string());
+            }
+            for i in 0..metric_columns(opts) {
+                let value = c.metrics.get(i).map(|(_, value)| value);
+                cells.push(value.map(ToString::to_string).unwrap_or_default());
+            }
+            if partial {
+                cells.push(err.map(error).unwrap_or_default());
+            }
+        }
+        None => {
+            cells.push(error(res.as_ref().unwrap_err()));
+        }
+    }
+    cells
+}
+
+pub(crate) fn csv_totals(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    partial: bool,
+) -> Vec<String> {
+    let total = totals(results, opts);
+    let failed = results.iter().filter(|(res, _)| res.is_err()).count();
+    let mut totals = vec![String::from(totals_label(failed))];
+    if opts.bytes {
+        totals.push(total.bytes.to_string());
+    }
+    if opts.chars {
+        totals.push(total.chars.to_string());
+    }
+    if opts.words {
+        totals.push(total.words.to_string());
+    }
+    if opts.lines {
+        totals.push(total.lines.to_string());
+    }
+    if opts.max_line_length {
+        totals.push(total.max_line_length.to_string());
+    }
+    for metric in &total.metrics {
+        totals.push(metric.to_string());
+    }
+    if partial {
+        totals.push(String::new());
+    }
+    totals
+}
+
+/// An object for each of results like `{"path": "a.txt", "lines": 3}` with the counts enabled in
+/// opts, null if unavailable, the metrics by name, and an `error` if it couldn't be counted, or
+/// only partially. With `--show-totals` the last object is the totals like
+/// `{"totals": true, "failed": 0, "lines": 3}`.
+fn print_json<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    mut w: W,
+) -> Result<(), Error> {
+    let mut objects = Vec::new();
+    for (res, path) in results {
+        let mut object = Map::new();
+        object.insert(String::from("path"), json!(console::display_path(path)));
+        if let Some((c, _)) = row_counts(res) {
+            let counts = [c.bytes, c.chars, c.words, c.lines, c.max_line_length]
+                .map(|count| json!(count.val));
+            insert_counts(&mut object, opts, counts);
+            let metrics = c
+                .metrics
+                .iter()
+                .map(|(name, value)| (name.clone(), metric_json(value)));
+            insert_metrics(&mut object, metrics.collect());
+        }
+        if let Err(err) = res {
+            object.insert(String::from("error"), json!(plain_row_error(err)));
+        }
+        objects.push(Value::Object(object));
+    }
+
+    if opts.show_totals {
+        let total = totals(results, opts);
+        let failed = results.iter().filter(|(res, _)| res.is_err()).count();
+        let mut object = Map::new();
+        object.insert(String::from("totals"), json!(true));
+        object.insert(String::from("failed"), json!(failed));
+        let counts = [
+            total.bytes,
+            total.chars,
+            total.words,
+            total.lines,
+            total.max_line_length,
+        ]
+        .map(|total| {
+            if total.overflowed {
+                Value::Null
+            } else {
+                json!(total.val)
+            }
+        });
+        insert_counts(&mut object, opts, counts);
+        let names = opts.metrics.iter().map(|metric| metric.name.as_str());
+        let metrics = names
+            .chain(size::columns(opts))
+            .zip(&total.metrics)
+            .map(|(name, metric)| {
+                let value = match metric {
+                    MetricTotal::Empty => json!(0),
+                    MetricTotal::Sum(value) => metric_json(value),
+                    MetricTotal::Unavailable => Value::Null,
+                };
+                (String::from(name), value)
+            });
+        insert_metrics(&mut object, metrics.collect());
+        objects.push(Value::Object(object));
+    }
+
+    serde_json::to_writer_pretty(&mut w, &objects).map_err(|err| Error::CUSTOM(err.to_string()))?;
+    Ok(())
+}
+
+/// Insert the bytes, chars, words, lines, and max line length enabled in opts.
+fn insert_counts(object: &mut Map<String, Value>, opts: &Options, counts: [Value; 5]) {
+    let enabled = [
+        opts.bytes,
+        opts.chars,
+        opts.words,
+        opts.lines,
+        opts.max_line_length,
+    ];
+    let names = ["bytes", "chars", "words", "lines", MAX_LINE_LENGTH];
+    for ((enabled, name), count) in enabled.iter().zip(&names).zip(counts) {
+        if *enabled {
+            object.insert(String::from(*name), count);
+        }
+    }
+}
+
+/// Insert the metric columns as a `metrics` object if there are any, so a metric named like a
+/// count or `path` can't overwrite it.
+fn insert_metrics(object: &mut Map<String, Value>, metrics: Map<String, Value>) {
+    if !metrics.is_empty() {
+        object.insert(String::from("metrics"), Value::Object(metrics));
+    }
+}
+
+/// Counts and floats are numbers and histograms are objects like `{"1": 10, "2": 4}`.
+fn metric_json(value: &MetricValue) -> Value {
+    match value {
+        MetricValue::Count(n) | MetricValue::Min(n) | MetricValue::Max(n) => json!(n),
+        MetricValue::Float(n) => json!(n),
+        MetricValue::Ratio(n, d) => json!(ratio(*n, *d)),
+        MetricValue::Text(s) => json!(s),
+        MetricValue::Distinct(hashes) => json!(hashes.len()),
+        MetricValue::Mode(histogram) => json!(mode(histogram)),
+        MetricValue::Mean(histogram) => json!(mean(histogram)),
+        MetricValue::Median(histogram) => json!(median(histogram)),
+        MetricValue::Histogram(histogram) => Value::Object(
+            histogram
+                .iter()
+                .map(|(value, n)| (value.to_string(), json!(n)))
+                .collect(),
+        ),
+    }
+}
+
+/// Escape s for xml text or a double quoted attribute.
+pub(crate) fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// An element like `<lines>3</lines>`, or `<chars/>` if the value is unavailable.
+pub(crate) fn xml_element(name: &str, value: Option<String>) -> String {
+    match value {
+        Some(value) => format!("<{0}>{1}</{0}>", name, xml_escape(&value)),
+        None => format!("<{}/>", name),
+    }
+}
+
+/// The counts enabled in opts as elements.
+fn xml_counts(opts: &Options, counts: [Option<String>; 5]) -> Vec<String> {
+    let enabled = [
+        opts.bytes,
+        opts.chars,
+        opts.words,
+        opts.lines,
+        opts.max_line_length,
+    ];
+    let names = ["bytes", "chars", "words", "lines", "max_line_length"];
+    enabled
+        .iter()
+        .zip(&names)
+        .zip(counts)
+        .filter(|((enabled, _), _)| **enabled)
+        .map(|((_, name), count)| xml_element(name, count))
+        .collect()
+}
+
+/// An element like `<metric name="language">eng</metric>`, or empty if unavailable.
+fn xml_metric(name: &str, value: Option<String>) -> String {
+    match value {
+        Some(value) => format!(
+            "<metric name=\"{}\">{}</metric>",
+            xml_escape(name),
+            xml_escape(&value)
+        ),
+        None => format!("<metric name=\"{}\"/>", xml_escape(name)),
+    }
+}
+
+/// A `<rwc>` element with a `<file path="...">` for each of results, holding an element for each
+/// count enabled in opts, empty if unavailable, a `<metric name="...">` for each metric, and an
+/// `<error>` if it couldn't be counted, or only partially. With `--show-totals` it ends with a
+/// `<totals failed="...">` holding the same elements.
+fn print_xml<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    mut w: W,
+) -> Result<(), Error> {
+    let mut lines = vec![
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"),
+        String::from("<rwc>"),
+    ];
+    for (res, path) in results {
+        let path = xml_escape(&console::display_path(path));
+        lines.push(format!("  <file path=\"{}\">", path));
+        if let Some((c, _)) = row_counts(res) {
+            let counts = [c.bytes, c.chars, c.words, c.lines, c.max_line_length]
+                .map(|count| count.val.map(|n| n.to_string()));
+            for element in xml_counts(opts, counts) {
+                lines.push(format!("    {}", element));
+            }
+            for (name, value) in &c.metrics {
+                lines.push(format!("    {}", xml_metric(name, Some(value.to_string()))));
+            }
+        }
+        if let Err(err) = res {
+            lines.push(format!(
+                "    {}",
+                xml_element("error", Some(plain_row_error(err)))
+            ));
+        }
+        lines.push(String::from("  </file>"));
+    }
+
+    if opts.show_totals {
+        let total = totals(results, opts);
+        let failed = results.iter().filter(|(res, _)| res.is_err()).count();
+        lines.push(format!("  <totals failed=\"{}\">", failed));
+        let counts = [
+            total.bytes,
+            total.chars,
+            total.words,
+            total.lines,
+            total.max_line_length,
+        ]
+        .map(|total| Some(total.to_string()).filter(|_| !total.overflowed));
+        for element in xml_counts(opts, counts) {
+            lines.push(format!("    {}", element));
+        }
+        let names = opts.metrics.iter().map(|metric| metric.name.as_str());
+        for (name, total) in names.chain(size::columns(opts)).zip(&total.metrics) {
+            let value = match total {
+                MetricTotal::Unavailable => None,
+                _ => Some(total.to_string()),
+            };
+            lines.push(format!("    {}", xml_metric(name, value)));
+        }
+        lines.push(String::from("  </totals>"));
+    }
+    lines.push(String::from("</rwc>"));
+
+    write!(w, "{}", lines.join("\n"))?;
+    Ok(())
+}
+
+/// An org-mode table with a rule under the header and above the footer rows, if any. Cells are
+/// padded to the width of their column, numbers right-aligned as org does, and rows shorter than
+/// the header are filled with empty cells.
+pub(crate) fn org_table(
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    footer: Vec<Vec<String>>,
+) -> String {
+    let escape = |row: Vec<String>| -> Vec<String> {
+        let mut row: Vec<String> = row
+            .iter()
+            .map(|cell| cell.replace('|', "\\vert{}").replace('\n', " "))
+            .collect();
+        if row.len() < header.len() {
+            row.resize(header.len(), String::new());
+        }
+        row
+    };
+    let header = escape(header.clone());
+    let rows: Vec<Vec<String>> = rows.into_iter().map(escape).collect();
+    let footer: Vec<Vec<String>> = footer.into_iter().map(escape).collect();
+
+    let mut widths = vec![0; header.len()];
+    for row in std::iter::once(&header).chain(&rows).chain(&footer) {
+        widths.resize(widths.len().max(row.len()), 0);
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |row: &Vec<String>| -> String {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(i, width)| {
+                let cell = row.get(i).map(String::as_str).unwrap_or("");
+                if cell.parse::<f64>().is_ok() {
+                    format!("{:>1$}", cell, width)
+                } else {
+                    format!("{:<1$}", cell, width)
                 }
+            })
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+    let dashes: Vec<String> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+    let rule = format!("|{}|", dashes.join("+"));
+
+    let mut lines = vec![line(&header), rule.clone()];
+    lines.extend(rows.iter().map(line));
+    if !footer.is_empty() {
+        lines.push(rule);
+        lines.extend(footer.iter().map(line));
+    }
+    lines.join("\n")
+}
+
+/// The csv columns as an org-mode table, for embedding counts in org notes with babel.
+fn print_org<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    mut w: W,
+) -> Result<(), Error> {
+    let partial = any_partial(results);
+    let rows = results
+        .iter()
+        .map(|(res, path)| plain_csv_row(res, path, opts, partial))
+        .collect();
+    let mut footer = Vec::new();
+    if opts.show_totals {
+        footer.push(csv_totals(results, opts, partial));
+    }
+    write!(w, "{}", org_table(csv_header(opts, partial), rows, footer))?;
+    Ok(())
+}
+
+/// Escape the characters LaTeX treats specially in text, so s is typeset as is.
+pub(crate) fn latex_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
             }
-            Err(err) => {
-                cells.push(err.to_string());
+            '\n' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A LaTeX `tabular` with the first column left-aligned and the rest right-aligned, and a rule
+/// under the header and above the footer rows, if any. Rows shorter than the header have their
+/// last cell span the remaining columns, like an error in place of the counts.
+pub(crate) fn latex_tabular(
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    footer: Vec<Vec<String>>,
+) -> String {
+    let columns = header.len();
+    let line = |row: &Vec<String>| -> String {
+        let mut cells: Vec<String> = row.iter().map(|cell| latex_escape(cell)).collect();
+        if cells.len() < columns {
+            if let Some(last) = cells.pop() {
+                let span = columns - cells.len();
+                cells.push(format!("\\multicolumn{{{}}}{{l}}{{{}}}", span, last));
             }
         }
-        rows.push(cells.join(","));
+        format!("{} \\\\", cells.join(" & "))
+    };
+
+    let mut lines = vec![
+        format!(
+            "\\begin{{tabular}}{{l{}}}",
+            "r".repeat(columns.saturating_sub(1))
+        ),
+        String::from("\\hline"),
+        line(&header),
+        String::from("\\hline"),
+    ];
+    lines.extend(rows.iter().map(line));
+    if !footer.is_empty() {
+        lines.push(String::from("\\hline"));
+        lines.extend(footer.iter().map(line));
     }
+    lines.push(String::from("\\hline"));
+    lines.push(String::from("\\end{tabular}"));
+    lines.join("\n")
+}
 
+/// The csv columns as a LaTeX `tabular`, for including counts in papers.
+fn print_latex<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    mut w: W,
+) -> Result<(), Error> {
+    let partial = any_partial(results);
+    let rows = results
+        .iter()
+        .map(|(res, path)| plain_csv_row(res, path, opts, partial))
+        .collect();
+    let mut footer = Vec::new();
     if opts.show_totals {
-        let mut totals = vec![String::from("Totals")];
-        if opts.bytes {
-            totals.push(total_bytes.to_string());
+        footer.push(csv_totals(results, opts, partial));
+    }
+    write!(
+        w,
+        "{}",
+        latex_tabular(csv_header(opts, partial), rows, footer)
+    )?;
+    Ok(())
+}
+
+/// Lines of values right-aligned to the width of the widest, each followed by its label if any,
+/// like the output of wc.
+pub(crate) fn wc_lines(rows: Vec<(Vec<String>, Option<String>)>) -> String {
+    let width = rows
+        .iter()
+        .flat_map(|(values, _)| values.iter().map(|value| value.chars().count()))
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for (values, label) in rows {
+        let mut fields: Vec<String> = values
+            .iter()
+            .map(|value| format!("{:>1$}", value, width))
+            .collect();
+        fields.extend(label);
+        out.push_str(&fields.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+/// Mimic GNU wc: the enabled counts in wc's order of lines, words, chars, bytes, then max line
+/// length, the path last, and a `total` line if there's more than one file. Files which couldn't be counted are
+/// left out, and standard input has no path. Metric columns aren't printed.
+fn print_wc<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    mut w: W,
+) -> Result<(), Error> {
+    let enabled = [
+        opts.lines,
+        opts.words,
+        opts.chars,
+        opts.bytes,
+        opts.max_line_length,
+    ];
+    let select = |counts: [String; 5]| -> Vec<String> {
+        enabled
+            .iter()
+            .zip(counts)
+            .filter(|(enabled, _)| **enabled)
+            .map(|(_, count)| count)
+            .collect()
+    };
+    let mut rows = Vec::new();
+    for (res, path) in results {
+        if let Some((c, _)) = row_counts(res) {
+            let counts = [c.lines, c.words, c.chars, c.bytes, c.max_line_length]
+                .map(|count| count.to_string());
+            let path = Some(console::display_path(path)).filter(|_| !opts.stdin);
+            rows.push((select(counts), path));
         }
-        if opts.chars {
-            totals.push(total_chars.to_string());
+    }
+    if results.len() > 1 {
+        let total = totals(results, opts);
+        let counts = [
+            total.lines,
+            total.words,
+            total.chars,
+            total.bytes,
+            total.max_line_length,
+        ]
+        .map(|total| total.to_string());
+        rows.push((select(counts), Some(String::from("total"))));
+    }
+    write!(w, "{}", wc_lines(rows))?;
+    Ok(())
+}
+
+/// A line from template for each of results which could be counted, at least partially, and
+/// the totals with `--show-totals`.
+fn print_template<W: Write>(
+    template: &Template,
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    mut w: W,
+) -> Result<(), Error> {
+    let mut lines = Vec::new();
+    for (res, path) in results {
+        if let Some((c, _)) = row_counts(res) {
+            let path = console::display_path(path);
+            lines.push(template.render(&path, |kind| c.get(kind).to_string()));
         }
-        if opts.words {
-            totals.push(total_words.to_string());
+    }
+    if opts.show_totals {
+        let total = totals(results, opts);
+        let failed = results.iter().filter(|(res, _)| res.is_err()).count();
+        lines.push(template.render(totals_label(failed), |kind| {
+            match kind {
+                CountKind::Bytes => total.bytes,
+                CountKind::Chars => total.chars,
+                CountKind::Words => total.words,
+                CountKind::Lines => total.lines,
+            }
+            .to_string()
+        }));
+    }
+    write!(w, "{}", lines.join("\n"))?;
+    Ok(())
+}
+
+/// Write each of records as NUL terminated fields, with the path of the rest written exactly.
+pub(crate) fn write0<W: Write>(
+    header: Vec<String>,
+    records: Vec<(Option<&Path>, Vec<String>)>,
+    mut w: W,
+) -> Result<(), Error> {
+    for field in header {
+        w.write_all(field.as_bytes())?;
+        w.write_all(b"\0")?;
+    }
+    for (path, fields) in records {
+        if let Some(path) = path {
+            w.write_all(&console::path_bytes(path))?;
+            w.write_all(b"\0")?;
         }
-        if opts.lines {
-            totals.push(total_lines.to_string());
+        for field in fields {
+            w.write_all(field.as_bytes())?;
+            w.write_all(b"\0")?;
         }
-        rows.push(totals.join(","));
     }
+    Ok(())
+}
 
-    write!(w, "{}", rows.join("\n"))?;
+/// The csv columns as NUL terminated fields, so any path round-trips. Every record has as many
+/// fields as the header, which ends with an `error` field, empty unless the file couldn't be
+/// counted, or only partially.
+fn print0<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    w: W,
+) -> Result<(), Error> {
+    let mut header = csv_header(opts, false);
+    let columns = header.len();
+    header.push(String::from("error"));
+    let mut records = Vec::new();
+    for (res, path) in results {
+        let mut fields = match row_counts(res) {
+            Some(_) => csv_row(res, path, opts, false),
+            None => vec![String::new(); columns],
+        };
+        // the path is written as is instead
+        fields.remove(0);
+        fields.push(res.as_ref().err().map(plain_row_error).unwrap_or_default());
+        records.push((Some(path.as_path()), fields));
+    }
+    if opts.show_totals {
+        let mut totals = csv_totals(results, opts, false);
+        totals.push(String::new());
+        records.push((None, totals));
+    }
+    write0(header, records, w)
+}
+
+/// Escape s for a double quoted Prometheus label value.
+pub(crate) fn prometheus_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// A gauge like `rwc_lines{path="a.txt"} 3` for each count enabled in opts of each of results
+/// where it's available, grouped by metric with its `# HELP` and `# TYPE`, for a Pushgateway or
+/// a node_exporter textfile collector. Files which couldn't be counted are left out.
+fn print_prometheus<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    opts: &Options,
+    mut w: W,
+) -> Result<(), Error> {
+    let kinds = [
+        (opts.bytes, CountKind::Bytes),
+        (opts.chars, CountKind::Chars),
+        (opts.words, CountKind::Words),
+        (opts.lines, CountKind::Lines),
+    ];
+    for kind in kinds
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, kind)| *kind)
+    {
+        writeln!(
+            w,
+            "# HELP rwc_{} The {} in each file counted by rwc.",
+            kind.name(),
+            kind.name()
+        )?;
+        writeln!(w, "# TYPE rwc_{} gauge", kind.name())?;
+        for (res, path) in results {
+            if let Some(n) = row_counts(res).and_then(|(c, _)| c.get(kind).val) {
+                let path = prometheus_label(&console::display_path(path));
+                writeln!(w, "rwc_{}{{path=\"{}\"}} {}", kind.name(), path, n)?;
+            }
+        }
+    }
     Ok(())
 }
 
+/// The columns of `--format arrow`, the same as `--output-db` and `--format parquet` without
+/// `run_at`.
+#[cfg(feature = "arrow")]
+fn arrow_batch(
+    results: &[(Result<Counts, Error>, PathBuf)],
+) -> Result<arrow_array::RecordBatch, arrow_schema::ArrowError> {
+    use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    let schema = Schema::new(vec![
+        Field::new("path", DataType::Utf8, false),
+        Field::new("bytes", DataType::UInt64, true),
+        Field::new("chars", DataType::UInt64, true),
+        Field::new("words", DataType::UInt64, true),
+        Field::new("lines", DataType::UInt64, true),
+        Field::new("error", DataType::Utf8, true),
+    ]);
+    let paths: StringArray = results
+        .iter()
+        .map(|(_, path)| Some(console::display_path(path)))
+        .collect();
+    let mut columns: Vec<ArrayRef> = vec![Arc::new(paths)];
+    for kind in &[
+        CountKind::Bytes,
+        CountKind::Chars,
+        CountKind::Words,
+        CountKind::Lines,
+    ] {
+        let counts: UInt64Array = results
+            .iter()
+            .map(|(res, _)| row_counts(res).and_then(|(c, _)| c.get(*kind).val))
+            .collect();
+        columns.push(Arc::new(counts));
+    }
+    let errors: StringArray = results
+        .iter()
+        .map(|(res, _)| res.as_ref().err().map(plain_row_error))
+        .collect();
+    columns.push(Arc::new(errors));
+    RecordBatch::try_new(Arc::new(schema), columns)
+}
+
+/// A record batch of results in the Arrow IPC file format, also known as Feather, for pandas or
+/// polars.
+#[cfg(feature = "arrow")]
+fn print_arrow<W: Write>(results: &[(Result<Counts, Error>, PathBuf)], w: W) -> Result<(), Error> {
+    let res = arrow_batch(results).and_then(|batch| {
+        let mut writer = arrow_ipc::writer::FileWriter::try_new(w, &batch.schema())?;
+        writer.write(&batch)?;
+        writer.finish()
+    });
+    res.map_err(|err| Error::CUSTOM(format!("Arrow error: {}", err)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::count::Count;
+    use crate::count::{Eol, InvalidUtf8};
+    use crate::metric::{Metric, MetricFactory};
+    use crate::sort::Sort;
+    use crate::tokenize::AsciiWhitespace;
+    use crate::wordlen::word_length_histogram_metric;
+    use std::iter;
+    use std::sync::Arc;
 
     fn default_opts() -> Options {
         Options {
@@ -199,7 +1320,37 @@ mod tests {
             chars: false,
             words: true,
             lines: true,
+            max_line_length: false,
             show_totals: false,
+            stdin: false,
+            statusline: false,
+            cargo: false,
+            git_blame: false,
+            markdown_aware: false,
+            log_to: None,
+            clipboard: false,
+            badge: None,
+            goal: None,
+            csv_quote: true,
+            csv_delimiter: ',',
+            #[cfg(feature = "otel")]
+            otel_endpoint: None,
+            #[cfg(feature = "sqlite")]
+            output_db: None,
+            output: None,
+            tee: Vec::new(),
+            tokenizer: None,
+            invalid_utf8: InvalidUtf8::Strict,
+            eol: Eol::Lf,
+            count_last_line: false,
+            no_stat_fastpath: false,
+            apparent_size: false,
+            disk_usage: false,
+            sort: Sort::Path,
+            timeout: None,
+            max_open: None,
+            chunk: None,
+            metrics: Vec::new(),
         }
     }
 
@@ -212,6 +1363,8 @@ mod tests {
                     chars: Count { val: Some(7) },
                     words: Count { val: Some(8) },
                     lines: Count { val: Some(9) },
+                    max_line_length: Count { val: None },
+                    metrics: Vec::new(),
                 }),
                 PathBuf::from("foobar"),
             ),
@@ -221,12 +1374,14 @@ mod tests {
                     chars: Count { val: Some(3) },
                     words: Count { val: Some(4) },
                     lines: Count { val: Some(5) },
+                    max_line_length: Count { val: None },
+                    metrics: Vec::new(),
                 }),
                 PathBuf::from("baz"),
             ),
         ];
         let mut stdout = Vec::new();
-        print_csv(results, &default_opts(), &mut stdout).unwrap();
+        print_csv(&results, &default_opts(), &mut stdout).unwrap();
         assert_eq!(
             r"path,bytes,words,lines
 foobar,6,8,9
@@ -234,4 +1389,524 @@ baz,2,4,5",
             String::from_utf8(stdout).unwrap()
         );
     }
+
+    #[test]
+    fn test_csv_record() {
+        let fields = ["a,b", "say \"hi\"", "two\nlines", "plain"];
+        assert_eq!(
+            "\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",plain",
+            csv_record(&fields, &default_opts())
+        );
+        let opts = Options {
+            csv_quote: false,
+            ..default_opts()
+        };
+        assert_eq!(
+            "a,b,say \"hi\",two\nlines,plain",
+            csv_record(&fields, &opts)
+        );
+        let opts = Options {
+            csv_delimiter: ';',
+            ..default_opts()
+        };
+        assert_eq!(
+            "a,b;\"x;y\";plain",
+            csv_record(&["a,b", "x;y", "plain"], &opts)
+        );
+    }
+
+    #[test]
+    fn test_print_csv_partial() {
+        let counts = |n| Counts {
+            bytes: Count { val: Some(n) },
+            chars: Count { val: None },
+            words: Count { val: Some(n) },
+            lines: Count { val: Some(n) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let partial = Error::partial(counts(2), Error::UTF8());
+        let results = vec![
+            (Ok(counts(1)), PathBuf::from("foo")),
+            (Err(Error::for_path("bar", partial)), PathBuf::from("bar")),
+            (Err(Error::UTF8()), PathBuf::from("baz")),
+        ];
+        let opts = Options {
+            show_totals: true,
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_csv(&results, &opts, &mut stdout).unwrap();
+        let partial = Error::partial(counts(2), Error::UTF8());
+        assert_eq!(
+            format!(
+                "path,bytes,words,lines,partial\nfoo,1,1,1,\nbar,2,2,2,{}\nbaz,{}\nTotals*,3,3,3,",
+                partial.report(),
+                Error::UTF8().report()
+            ),
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_print_json() {
+        let counts = |n| Counts {
+            bytes: Count { val: Some(n) },
+            chars: Count { val: None },
+            words: Count { val: Some(n) },
+            lines: Count { val: Some(n) },
+            max_line_length: Count { val: None },
+            metrics: vec![(
+                String::from("word lengths"),
+                MetricValue::Histogram(vec![(1, n)].into_iter().collect()),
+            )],
+        };
+        let partial = Error::partial(counts(2), Error::UTF8());
+        let results = vec![
+            (Ok(counts(1)), PathBuf::from("foo")),
+            (Err(Error::for_path("bar", partial)), PathBuf::from("bar")),
+            (Err(Error::UTF8()), PathBuf::from("baz")),
+        ];
+        let opts = Options {
+            show_totals: true,
+            chars: true,
+            metrics: vec![word_length_histogram_metric(Arc::new(AsciiWhitespace))],
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_json(&results, &opts, &mut stdout).unwrap();
+        let json: Value = serde_json::from_slice(&stdout).unwrap();
+        let partial = Error::partial(counts(2), Error::UTF8()).plain_report();
+        assert_eq!(
+            json!([
+                {"path": "foo", "bytes": 1, "chars": null, "words": 1, "lines": 1, "metrics": {"word lengths": {"1": 1}}},
+                {"path": "bar", "bytes": 2, "chars": null, "words": 2, "lines": 2, "metrics": {"word lengths": {"1": 2}}, "error": partial},
+                {"path": "baz", "error": Error::UTF8().plain_report()},
+                {"totals": true, "failed": 2, "bytes": 3, "chars": 0, "words": 3, "lines": 3, "metrics": {"word lengths": {"1": 3}}},
+            ]),
+            json
+        );
+    }
+
+    #[test]
+    fn test_print_xml() {
+        let counts = Counts {
+            bytes: Count { val: Some(6) },
+            chars: Count { val: None },
+            words: Count { val: Some(8) },
+            lines: Count { val: Some(9) },
+            max_line_length: Count { val: None },
+            metrics: vec![(
+                String::from("language"),
+                MetricValue::Text(String::from("eng?")),
+            )],
+        };
+        let results = vec![
+            (Ok(counts), PathBuf::from("a&b<c>.txt")),
+            (Err(Error::for_path("d", Error::UTF8())), PathBuf::from("d")),
+        ];
+        let opts = Options {
+            show_totals: true,
+            chars: true,
+            metrics: vec![crate::detect_lang::detect_lang_metric()],
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_xml(&results, &opts, &mut stdout).unwrap();
+        assert_eq!(
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<rwc>
+  <file path="a&amp;b&lt;c&gt;.txt">
+    <bytes>6</bytes>
+    <chars/>
+    <words>8</words>
+    <lines>9</lines>
+    <metric name="language">eng?</metric>
+  </file>
+  <file path="d">
+    <error>{}</error>
+  </file>
+  <totals failed="1">
+    <bytes>6</bytes>
+    <chars>0</chars>
+    <words>8</words>
+    <lines>9</lines>
+    <metric name="language"/>
+  </totals>
+</rwc>"#,
+                Error::UTF8().plain_report()
+            ),
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_print_wc() {
+        let counts = |n| Counts {
+            bytes: Count { val: Some(n * 100) },
+            chars: Count { val: Some(n * 10) },
+            words: Count { val: Some(n) },
+            lines: Count { val: Some(n) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts(1)), PathBuf::from("a")),
+            (Err(Error::UTF8()), PathBuf::from("b")),
+            (Ok(counts(12)), PathBuf::from("c")),
+        ];
+        let mut stdout = Vec::new();
+        print_wc(&results, &default_opts(), &mut stdout).unwrap();
+        assert_eq!(
+            "   1    1  100 a\n  12   12 1200 c\n  13   13 1300 total\n",
+            String::from_utf8(stdout).unwrap()
+        );
+
+        let results = vec![(Ok(counts(3)), PathBuf::from(STDIN))];
+        let opts = Options {
+            lines: false,
+            show_totals: true,
+            stdin: true,
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_wc(&results, &opts, &mut stdout).unwrap();
+        assert_eq!("  3 300\n", String::from_utf8(stdout).unwrap());
+
+        // a file named like the stdin row still has its name
+        let results = vec![(Ok(counts(3)), PathBuf::from(STDIN))];
+        let mut stdout = Vec::new();
+        print_wc(&results, &default_opts(), &mut stdout).unwrap();
+        assert_eq!("  3   3 300 Stdin\n", String::from_utf8(stdout).unwrap());
+    }
+
+    #[test]
+    fn test_max_line_length_totals() {
+        let counts = |n| Counts {
+            bytes: Count { val: Some(n) },
+            chars: Count { val: None },
+            words: Count { val: Some(n) },
+            lines: Count { val: Some(n) },
+            max_line_length: Count { val: Some(n * 10) },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts(3)), PathBuf::from("a")),
+            (Ok(counts(1)), PathBuf::from("b")),
+        ];
+        let opts = Options {
+            bytes: false,
+            words: false,
+            max_line_length: true,
+            show_totals: true,
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_csv(&results, &opts, &mut stdout).unwrap();
+        assert_eq!(
+            "path,lines,max line length\na,3,30\nb,1,10\nTotals,4,30",
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_print_template() {
+        let counts = |n| Counts {
+            bytes: Count { val: Some(n) },
+            chars: Count { val: None },
+            words: Count { val: Some(n) },
+            lines: Count { val: Some(n * 2) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts(1)), PathBuf::from("a")),
+            (Err(Error::UTF8()), PathBuf::from("b")),
+            (Ok(counts(3)), PathBuf::from("c")),
+        ];
+        let opts = Options {
+            show_totals: true,
+            ..default_opts()
+        };
+        let template =
+            crate::format::parse_template("{path}: {lines} lines, {chars} chars").unwrap();
+        let mut stdout = Vec::new();
+        print_template(&template, &results, &opts, &mut stdout).unwrap();
+        assert_eq!(
+            "a: 2 lines, N/A chars\nc: 6 lines, N/A chars\nTotals*: 8 lines, 0 chars",
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_print0() {
+        let counts = Counts {
+            bytes: Count { val: Some(6) },
+            chars: Count { val: None },
+            words: Count { val: Some(8) },
+            lines: Count { val: Some(9) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts), PathBuf::from("a,\nb")),
+            (Err(Error::for_path("c", Error::UTF8())), PathBuf::from("c")),
+        ];
+        let opts = Options {
+            show_totals: true,
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print0(&results, &opts, &mut stdout).unwrap();
+        let fields: Vec<String> = String::from_utf8(stdout)
+            .unwrap()
+            .split_terminator('\0')
+            .map(String::from)
+            .collect();
+        let utf8 = Error::UTF8().plain_report();
+        assert_eq!(
+            vec![
+                "path", "bytes", "words", "lines", "error", "a,\nb", "6", "8", "9", "", "c", "",
+                "", "", &utf8, "Totals*", "6", "8", "9", "",
+            ],
+            fields
+        );
+    }
+
+    #[test]
+    fn test_print_prometheus() {
+        let counts = Counts {
+            bytes: Count { val: Some(6) },
+            chars: Count { val: None },
+            words: Count { val: Some(8) },
+            lines: Count { val: Some(9) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts), PathBuf::from("a \"b\"\\c")),
+            (Err(Error::UTF8()), PathBuf::from("d")),
+        ];
+        let opts = Options {
+            words: false,
+            chars: true,
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_prometheus(&results, &opts, &mut stdout).unwrap();
+        assert_eq!(
+            "# HELP rwc_bytes The bytes in each file counted by rwc.\n\
+             # TYPE rwc_bytes gauge\n\
+             rwc_bytes{path=\"a \\\"b\\\"\\\\c\"} 6\n\
+             # HELP rwc_chars The chars in each file counted by rwc.\n\
+             # TYPE rwc_chars gauge\n\
+             # HELP rwc_lines The lines in each file counted by rwc.\n\
+             # TYPE rwc_lines gauge\n\
+             rwc_lines{path=\"a \\\"b\\\"\\\\c\"} 9\n",
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_print_arrow() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::UInt64Type;
+        use arrow_array::Array;
+
+        let counts = Counts {
+            bytes: Count { val: Some(6) },
+            chars: Count { val: None },
+            words: Count { val: Some(8) },
+            lines: Count { val: Some(9) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts), PathBuf::from("a")),
+            (Err(Error::UTF8()), PathBuf::from("b")),
+        ];
+        let mut out = Vec::new();
+        print_arrow(&results, &mut out).unwrap();
+
+        let mut reader =
+            arrow_ipc::reader::FileReader::try_new(std::io::Cursor::new(out), None).unwrap();
+        let batch = reader.next().unwrap().unwrap();
+        assert!(reader.next().is_none());
+        let paths = batch.column(0).as_string::<i32>();
+        assert_eq!(vec![Some("a"), Some("b")], paths.iter().collect::<Vec<_>>());
+        let lines = batch.column(4).as_primitive::<UInt64Type>();
+        assert_eq!(vec![Some(9), None], lines.iter().collect::<Vec<_>>());
+        let chars = batch.column(2).as_primitive::<UInt64Type>();
+        assert_eq!(2, chars.null_count());
+        let errors = batch.column(5).as_string::<i32>();
+        let report = Error::UTF8().plain_report();
+        assert_eq!(
+            vec![None, Some(report.as_str())],
+            errors.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_print_latex() {
+        let counts = Counts {
+            bytes: Count { val: Some(6) },
+            chars: Count { val: None },
+            words: Count { val: Some(8) },
+            lines: Count { val: Some(12) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts), PathBuf::from("50%_{a}\\b")),
+            (Err(Error::UTF8()), PathBuf::from("c")),
+        ];
+        let opts = Options {
+            show_totals: true,
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_latex(&results, &opts, &mut stdout).unwrap();
+        assert_eq!(
+            format!(
+                "\\begin{{tabular}}{{lrrr}}\n\
+                 \\hline\n\
+                 path & bytes & words & lines \\\\\n\
+                 \\hline\n\
+                 50\\%\\_\\{{a\\}}\\textbackslash{{}}b & 6 & 8 & 12 \\\\\n\
+                 c & \\multicolumn{{3}}{{l}}{{{}}} \\\\\n\
+                 \\hline\n\
+                 Totals* & 6 & 8 & 12 \\\\\n\
+                 \\hline\n\
+                 \\end{{tabular}}",
+                Error::UTF8().plain_report()
+            ),
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_print_org() {
+        let counts = Counts {
+            bytes: Count { val: Some(6) },
+            chars: Count { val: None },
+            words: Count { val: Some(8) },
+            lines: Count { val: Some(12) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts), PathBuf::from("a|b")),
+            (Err(Error::UTF8()), PathBuf::from("c")),
+        ];
+        let opts = Options {
+            show_totals: true,
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_org(&results, &opts, &mut stdout).unwrap();
+        let error = Error::UTF8().plain_report();
+        let width = error.chars().count();
+        assert_eq!(
+            format!(
+                "| path      | {:<w$} | words | lines |\n\
+                 |-----------+-{:-<w$}-+-------+-------|\n\
+                 | a\\vert{{}}b | {:>w$} |     8 |    12 |\n\
+                 | c         | {:<w$} |       |       |\n\
+                 |-----------+-{:-<w$}-+-------+-------|\n\
+                 | Totals*   | {:>w$} |     8 |    12 |",
+                "bytes",
+                "",
+                6,
+                error,
+                "",
+                6,
+                w = width
+            ),
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_print_table_failed_totals() {
+        let results = vec![
+            (Ok(iter::empty().sum()), PathBuf::from("a")),
+            (Err(Error::UTF8()), PathBuf::from("b")),
+        ];
+        let opts = Options {
+            show_totals: true,
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_table(&results, &opts, &mut stdout).unwrap();
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains(&"Totals*".magenta().bold().to_string()));
+        assert!(stdout.ends_with(&totals_footnote(1).yellow().to_string()));
+    }
+
+    #[test]
+    fn test_error_summary() {
+        let ok = |name: &str| (Ok(iter::empty().sum()), PathBuf::from(name));
+        let err = |name: &str| (Err(Error::UTF8()), PathBuf::from(name));
+        assert_eq!(None, error_summary(&[ok("a")]));
+        assert_eq!(
+            Some(format!("{}: b", "1 file unreadable".red().bold())),
+            error_summary(&[ok("a"), err("b")])
+        );
+        let results: Vec<_> = (0..12).map(|i| err(&i.to_string())).collect();
+        assert_eq!(
+            Some(format!(
+                "{}: 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, and 2 more",
+                "12 files unreadable".red().bold()
+            )),
+            error_summary(&results)
+        );
+    }
+
+    #[test]
+    fn test_print_csv_metrics() {
+        struct Noop;
+        impl Metric for Noop {
+            fn update(&mut self, _: &[u8]) {}
+            fn finalize(&mut self) -> MetricValue {
+                MetricValue::Count(0)
+            }
+        }
+
+        let counts = |n, name: &str| Counts {
+            bytes: Count { val: Some(n) },
+            chars: Count { val: None },
+            words: Count { val: None },
+            lines: Count { val: None },
+            max_line_length: Count { val: None },
+            metrics: vec![
+                (String::from("count"), MetricValue::Count(n)),
+                (String::from("name"), MetricValue::Text(name.into())),
+            ],
+        };
+        let results = vec![
+            (Ok(counts(1, "foo")), PathBuf::from("foo")),
+            (Ok(counts(2, "bar")), PathBuf::from("bar")),
+        ];
+        let opts = Options {
+            words: false,
+            lines: false,
+            show_totals: true,
+            metrics: vec![
+                MetricFactory::new("count", || Noop),
+                MetricFactory::new("name", || Noop),
+            ],
+            ..default_opts()
+        };
+        let mut stdout = Vec::new();
+        print_csv(&results, &opts, &mut stdout).unwrap();
+        assert_eq!(
+            r"path,bytes,count,name
+foo,1,1,foo
+bar,2,2,bar
+Totals,3,3,N/A",
+            String::from_utf8(stdout).unwrap()
+        );
+    }
 }
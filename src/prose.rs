@@ -0,0 +1,279 @@
+//! `--prose-stats`, the sentences per paragraph and words per sentence of each file, which
+//! editors use to judge the structure of prose, and `--readability`, the Flesch scores built on
+//! them.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+
+pub const SENTENCES_PER_PARAGRAPH: &str = "sentences/paragraph";
+pub const WORDS_PER_SENTENCE: &str = "words/sentence";
+pub const READING_EASE: &str = "reading ease";
+pub const GRADE_LEVEL: &str = "grade level";
+
+/// Sentences and paragraphs of prose. Words are split on ascii whitespace, a sentence ends with
+/// a word ending in `.`, `!` or `?`, optionally followed by closing quotes or brackets, or with
+/// its paragraph, and paragraphs are separated by blank lines. Abbreviations like `e.g.` end a
+/// sentence too.
+///
+/// Syllables are estimated as the groups of consecutive ascii vowels in a word, `y` included,
+/// less a silent `e` at the end unless it's after an `l`, and at least one per word.
+#[derive(Debug, Default)]
+struct Prose {
+    words: u64,
+    sentences: u64,
+    paragraphs: u64,
+    syllables: u64,
+    in_word: bool,
+    /// Vowel groups of the current word so far.
+    vowel_groups: u64,
+    in_vowel: bool,
+    /// The last two letters of the current word, lowercase.
+    last_letters: [u8; 2],
+    /// Whether the current word so far ends a sentence.
+    ends_sentence: bool,
+    /// Words since the end of the last sentence.
+    sentence_words: u64,
+    in_paragraph: bool,
+    blank_line: bool,
+}
+
+impl Prose {
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            if b.is_ascii_whitespace() {
+                self.end_word();
+                if b == b'\n' {
+                    if self.blank_line {
+                        self.end_paragraph();
+                    }
+                    self.blank_line = true;
+                }
+                continue;
+            }
+            if !self.in_word {
+                self.in_word = true;
+                self.vowel_groups = 0;
+                self.in_vowel = false;
+                self.last_letters = [0; 2];
+                self.ends_sentence = false;
+                self.words += 1;
+                self.sentence_words += 1;
+                self.paragraphs += u64::from(!self.in_paragraph);
+                self.in_paragraph = true;
+            }
+            self.blank_line = false;
+            if b.is_ascii_alphabetic() {
+                let letter = b.to_ascii_lowercase();
+                let vowel = b"aeiouy".contains(&letter);
+                self.vowel_groups += u64::from(vowel && !self.in_vowel);
+                self.in_vowel = vowel;
+                self.last_letters = [self.last_letters[1], letter];
+            }
+            match b {
+                b'.' | b'!' | b'?' => self.ends_sentence = true,
+                b'"' | b'\'' | b')' | b']' => {}
+                // closing quotes like `”` are non-ascii and leave it be
+                _ if !b.is_ascii() => {}
+                _ => self.ends_sentence = false,
+            }
+        }
+    }
+
+    fn end_word(&mut self) {
+        if self.in_word {
+            let silent_e = self.last_letters[1] == b'e' && self.last_letters[0] != b'l';
+            let groups = self.vowel_groups - u64::from(silent_e && self.vowel_groups > 1);
+            self.syllables += groups.max(1);
+        }
+        if self.in_word && self.ends_sentence {
+            self.sentences += 1;
+            self.sentence_words = 0;
+        }
+        self.in_word = false;
+    }
+
+    fn end_paragraph(&mut self) {
+        if self.sentence_words > 0 {
+            self.sentences += 1;
+            self.sentence_words = 0;
+        }
+        self.in_paragraph = false;
+    }
+
+    fn finish(&mut self) {
+        self.end_word();
+        self.end_paragraph();
+    }
+}
+
+fn ratio(n: u64, d: u64) -> MetricValue {
+    if d == 0 {
+        MetricValue::Float(0.0)
+    } else {
+        MetricValue::Float(n as f64 / d as f64)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SentencesPerParagraph(Prose);
+
+impl Metric for SentencesPerParagraph {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        ratio(self.0.sentences, self.0.paragraphs)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WordsPerSentence(Prose);
+
+impl Metric for WordsPerSentence {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        ratio(self.0.words, self.0.sentences)
+    }
+}
+
+/// The Flesch reading ease, from about 100 for very easy text down to 0 and below for very hard
+/// text, 0 if there are no words.
+#[derive(Debug, Default)]
+pub struct ReadingEase(Prose);
+
+impl Metric for ReadingEase {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        let prose = &self.0;
+        if prose.words == 0 {
+            return MetricValue::Float(0.0);
+        }
+        let words_per_sentence = prose.words as f64 / prose.sentences as f64;
+        let syllables_per_word = prose.syllables as f64 / prose.words as f64;
+        MetricValue::Float(206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word)
+    }
+}
+
+/// The Flesch-Kincaid grade level, roughly the US school grade needed to understand the text, 0
+/// if there are no words.
+#[derive(Debug, Default)]
+pub struct GradeLevel(Prose);
+
+impl Metric for GradeLevel {
+    fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.0.finish();
+        let prose = &self.0;
+        if prose.words == 0 {
+            return MetricValue::Float(0.0);
+        }
+        let words_per_sentence = prose.words as f64 / prose.sentences as f64;
+        let syllables_per_word = prose.syllables as f64 / prose.words as f64;
+        MetricValue::Float(0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59)
+    }
+}
+
+/// The `READING_EASE` and `GRADE_LEVEL` columns.
+pub fn readability_metrics() -> Vec<MetricFactory> {
+    vec![
+        MetricFactory::new(READING_EASE, ReadingEase::default),
+        MetricFactory::new(GRADE_LEVEL, GradeLevel::default),
+    ]
+}
+
+/// The `SENTENCES_PER_PARAGRAPH` and `WORDS_PER_SENTENCE` columns.
+pub fn prose_stats_metrics() -> Vec<MetricFactory> {
+    vec![
+        MetricFactory::new(SENTENCES_PER_PARAGRAPH, SentencesPerParagraph::default),
+        MetricFactory::new(WORDS_PER_SENTENCE, WordsPerSentence::default),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prose(input: &str, chunk: usize) -> (u64, u64, u64) {
+        let mut prose = Prose::default();
+        for chunk in input.as_bytes().chunks(chunk) {
+            prose.update(chunk);
+        }
+        prose.finish();
+        (prose.words, prose.sentences, prose.paragraphs)
+    }
+
+    #[test]
+    fn test_prose() {
+        let input =
+            "# A heading\n\nIt was late. \"Who's there?\" she asked (quietly.)\nNo answer!\n\
+                     \n  \t\n\nA list\n- without stops\n\n“Quoted.” Done";
+        for chunk in 1..input.len() {
+            assert_eq!((20, 8, 4), prose(input, chunk));
+        }
+        assert_eq!((0, 0, 0), prose("", 64));
+        assert_eq!((0, 0, 0), prose("\n\n \n", 64));
+    }
+
+    #[test]
+    fn test_prose_stats_metrics() {
+        let metrics = prose_stats_metrics();
+        let values: Vec<MetricValue> = metrics
+            .iter()
+            .map(|factory| {
+                let mut metric = factory.create();
+                metric.update(b"One two. Three four five six.\n\nSeven eight.\n");
+                metric.finalize()
+            })
+            .collect();
+        assert_eq!(
+            vec![MetricValue::Float(1.5), MetricValue::Float(8.0 / 3.0)],
+            values
+        );
+    }
+
+    #[test]
+    fn test_syllables() {
+        let syllables = |input: &str| {
+            let mut prose = Prose::default();
+            prose.update(input.as_bytes());
+            prose.finish();
+            prose.syllables
+        };
+        assert_eq!(1, syllables("late."));
+        assert_eq!(2, syllables("Table"));
+        assert_eq!(5, syllables("readability"));
+        assert_eq!(1, syllables("rhythm"));
+        assert_eq!(1, syllables("the"));
+        assert_eq!(1, syllables("42"));
+        assert_eq!(7, syllables("I see a queue-less river"));
+    }
+
+    #[test]
+    fn test_readability_metrics() {
+        let values: Vec<String> = readability_metrics()
+            .iter()
+            .map(|factory| {
+                let mut metric = factory.create();
+                metric.update(b"The cat sat on the mat.\n");
+                metric.finalize().to_string()
+            })
+            .collect();
+        assert_eq!(vec!["116.145", "-1.450"], values);
+
+        let mut metric = readability_metrics()[0].create();
+        metric.update(b"\n");
+        assert_eq!(MetricValue::Float(0.0), metric.finalize());
+    }
+}
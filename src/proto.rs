@@ -0,0 +1,94 @@
+//! `--format proto`, which writes a `FileCounts` message from proto/counts.proto for each file so
+//! services ingesting binary telemetry don't have to parse text.
+
+use crate::cli::Options;
+use crate::console;
+use crate::count::{CountKind, Counts};
+use crate::error::Error;
+use crate::print::{plain_row_error, row_counts, Printer};
+use prost::Message;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub mod schema {
+    include!(concat!(env!("OUT_DIR"), "/rwc.counts.rs"));
+}
+
+use schema::FileCounts;
+
+/// The message for a file. Counts which weren't computed are unset, and `error` is set if the file
+/// couldn't be counted, or only partially.
+fn file_counts(res: &Result<Counts, Error>, path: &Path) -> FileCounts {
+    let count = |kind| row_counts(res).and_then(|(c, _)| c.get(kind).val);
+    FileCounts {
+        path: console::display_path(path),
+        bytes: count(CountKind::Bytes),
+        chars: count(CountKind::Chars),
+        words: count(CountKind::Words),
+        lines: count(CountKind::Lines),
+        error: res.as_ref().err().map(plain_row_error),
+    }
+}
+
+/// Write a message for each of results to w, each prefixed with its length as a varint.
+pub fn write<W: Write>(
+    results: &[(Result<Counts, Error>, PathBuf)],
+    mut w: W,
+) -> Result<(), Error> {
+    for (res, path) in results {
+        w.write_all(&file_counts(res, path).encode_length_delimited_to_vec())?;
+    }
+    Ok(())
+}
+
+/// The `proto` format, see `write`.
+#[derive(Debug)]
+pub struct ProtoPrinter;
+
+impl Printer for ProtoPrinter {
+    fn print(
+        &self,
+        results: &[(Result<Counts, Error>, PathBuf)],
+        _: &Options,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        write(results, w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::Count;
+
+    #[test]
+    fn test_write() {
+        let counts = Counts {
+            bytes: Count { val: Some(6) },
+            chars: Count { val: None },
+            words: Count { val: Some(8) },
+            lines: Count { val: Some(9) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        let results = vec![
+            (Ok(counts), PathBuf::from("a")),
+            (Err(Error::UTF8()), PathBuf::from("b")),
+        ];
+        let mut out = Vec::new();
+        write(&results, &mut out).unwrap();
+
+        let mut buf = &out[..];
+        let a = FileCounts::decode_length_delimited(&mut buf).unwrap();
+        assert_eq!("a", a.path);
+        assert_eq!(
+            (Some(6), None, Some(8), Some(9), None),
+            (a.bytes, a.chars, a.words, a.lines, a.error)
+        );
+        let b = FileCounts::decode_length_delimited(&mut buf).unwrap();
+        assert_eq!("b", b.path);
+        assert_eq!(None, b.lines);
+        assert_eq!(Some(plain_row_error(&Error::UTF8())), b.error);
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! pyo3 bindings exposing the `rwc` Python module:
+//!
+//! ```python
+//! import rwc
+//! rwc.count_path("README.md", chars=True)
+//! rwc.count_paths(["a.txt", "b.txt"])
+//! ```
+
+use crate::count::{self, CountOptions, Countable, CountablePath, Eol, InvalidUtf8};
+use crate::error::Error;
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use std::path::PathBuf;
+
+/// Counts for one input, counts which weren't computed are `None`.
+#[pyclass(name = "Counts", module = "rwc", frozen, get_all)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyCounts {
+    pub bytes: Option<u64>,
+    pub chars: Option<u64>,
+    pub words: Option<u64>,
+    pub lines: Option<u64>,
+}
+
+#[pymethods]
+impl PyCounts {
+    fn __repr__(&self) -> String {
+        fn repr(n: Option<u64>) -> String {
+            n.map_or_else(|| String::from("None"), |n| n.to_string())
+        }
+        format!(
+            "Counts(bytes={}, chars={}, words={}, lines={})",
+            repr(self.bytes),
+            repr(self.chars),
+            repr(self.words),
+            repr(self.lines)
+        )
+    }
+
+    fn __eq__(&self, other: &PyCounts) -> bool {
+        self == other
+    }
+}
+
+impl From<count::Counts> for PyCounts {
+    fn from(counts: count::Counts) -> PyCounts {
+        PyCounts {
+            bytes: counts.bytes.val,
+            chars: counts.chars.val,
+            words: counts.words.val,
+            lines: counts.lines.val,
+        }
+    }
+}
+
+fn to_pyerr(err: Error) -> PyErr {
+    let msg = err.plain_report();
+    match err.cause() {
+        Error::IO(_) => PyOSError::new_err(msg),
+        _ => PyValueError::new_err(msg),
+    }
+}
+
+/// Count the file at `path`.
+#[pyfunction]
+#[pyo3(signature = (path, bytes=true, chars=false, words=true, lines=true))]
+fn count_path(
+    py: Python<'_>,
+    path: PathBuf,
+    bytes: bool,
+    chars: bool,
+    words: bool,
+    lines: bool,
+) -> PyResult<PyCounts> {
+    let opts = CountOptions {
+        bytes,
+        chars,
+        words,
+        lines,
+        max_line_length: false,
+        tokenizer: None,
+        metrics: Vec::new(),
+        invalid_utf8: InvalidUtf8::Strict,
+        eol: Eol::Lf,
+        count_last_line: false,
+        no_stat_fastpath: false,
+        deadline: None,
+    };
+    py.allow_threads(|| path.count_with(opts))
+        .map(PyCounts::from)
+        .map_err(to_pyerr)
+}
+
+/// Count the contents of a `bytes` object.
+#[pyfunction]
+#[pyo3(signature = (data, bytes=true, chars=false, words=true, lines=true))]
+fn count_bytes(
+    py: Python<'_>,
+    data: &[u8],
+    bytes: bool,
+    chars: bool,
+    words: bool,
+    lines: bool,
+) -> PyResult<PyCounts> {
+    let opts = CountOptions {
+        bytes,
+        chars,
+        words,
+        lines,
+        max_line_length: false,
+        tokenizer: None,
+        metrics: Vec::new(),
+        invalid_utf8: InvalidUtf8::Strict,
+        eol: Eol::Lf,
+        count_last_line: false,
+        no_stat_fastpath: false,
+        deadline: None,
+    };
+    py.allow_threads(|| data.count_with(opts))
+        .map(PyCounts::from)
+        .map_err(to_pyerr)
+}
+
+/// Count each of `paths` in parallel, returning the counts in the same order as `paths`. Raises
+/// the error for the first path which couldn't be counted.
+#[pyfunction]
+#[pyo3(signature = (paths, bytes=true, chars=false, words=true, lines=true))]
+fn count_paths(
+    py: Python<'_>,
+    paths: Vec<PathBuf>,
+    bytes: bool,
+    chars: bool,
+    words: bool,
+    lines: bool,
+) -> PyResult<Vec<PyCounts>> {
+    let opts = CountOptions {
+        bytes,
+        chars,
+        words,
+        lines,
+        max_line_length: false,
+        tokenizer: None,
+        metrics: Vec::new(),
+        invalid_utf8: InvalidUtf8::Strict,
+        eol: Eol::Lf,
+        count_last_line: false,
+        no_stat_fastpath: false,
+        deadline: None,
+    };
+    py.allow_threads(|| {
+        paths
+            .par_iter()
+            .map(|path| path.count_with(opts.clone()).map(PyCounts::from))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .map_err(to_pyerr)
+}
+
+#[pymodule]
+fn rwc(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyCounts>()?;
+    m.add_function(wrap_pyfunction!(count_path, m)?)?;
+    m.add_function(wrap_pyfunction!(count_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(count_paths, m)?)?;
+    Ok(())
+}
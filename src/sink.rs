@@ -0,0 +1,184 @@
+//! `--log-to`, which emits a structured record for each counted file to the system log.
+
+use crate::count::Counts;
+use crate::error::Error;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogTarget {
+    Syslog,
+    Journald,
+}
+
+pub fn parse_log_target(src: &str) -> Result<LogTarget, Error> {
+    match src {
+        "syslog" => Ok(LogTarget::Syslog),
+        "journald" => Ok(LogTarget::Journald),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --log-to {}, expected syslog or journald",
+            src
+        ))),
+    }
+}
+
+/// Counts as `(name, value)` fields, leaving out counts which weren't computed.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn fields(res: &Result<Counts, Error>) -> Vec<(&'static str, String)> {
+    match res {
+        Ok(counts) => [
+            ("bytes", counts.bytes),
+            ("chars", counts.chars),
+            ("words", counts.words),
+            ("lines", counts.lines),
+        ]
+        .iter()
+        .filter_map(|(name, count)| Some((*name, count.val?.to_string())))
+        .collect(),
+        Err(err) => vec![("error", err.plain_report())],
+    }
+}
+
+/// A syslog message like `path="foo.txt" bytes=12 lines=1`.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn syslog_message(path: &Path, res: &Result<Counts, Error>) -> String {
+    let mut msg = format!("path={:?}", path.display().to_string());
+    for (name, val) in fields(res) {
+        if name == "error" {
+            msg.push_str(&format!(" {}={:?}", name, val));
+        } else {
+            msg.push_str(&format!(" {}={}", name, val));
+        }
+    }
+    msg
+}
+
+/// A journald native protocol entry with `RWC_`-prefixed fields. Every field uses the binary
+/// encoding so values can contain newlines.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn journal_entry(path: &Path, res: &Result<Counts, Error>) -> Vec<u8> {
+    fn push(entry: &mut Vec<u8>, key: &str, val: &[u8]) {
+        entry.extend_from_slice(key.as_bytes());
+        entry.push(b'\n');
+        entry.extend_from_slice(&(val.len() as u64).to_le_bytes());
+        entry.extend_from_slice(val);
+        entry.push(b'\n');
+    }
+
+    let mut entry = Vec::new();
+    // 6 is LOG_INFO and 4 is LOG_WARNING
+    let priority = if res.is_ok() { "6" } else { "4" };
+    push(&mut entry, "PRIORITY", priority.as_bytes());
+    push(&mut entry, "SYSLOG_IDENTIFIER", b"rwc");
+    push(&mut entry, "MESSAGE", syslog_message(path, res).as_bytes());
+    push(
+        &mut entry,
+        "RWC_PATH",
+        path.display().to_string().as_bytes(),
+    );
+    for (name, val) in fields(res) {
+        push(
+            &mut entry,
+            &format!("RWC_{}", name.to_uppercase()),
+            val.as_bytes(),
+        );
+    }
+    entry
+}
+
+#[cfg(unix)]
+fn log_syslog(results: &[(Result<Counts, Error>, PathBuf)]) -> Result<(), Error> {
+    use std::ffi::CString;
+    use std::os::raw::c_char;
+
+    unsafe {
+        libc::openlog(
+            b"rwc\0".as_ptr() as *const c_char,
+            libc::LOG_PID,
+            libc::LOG_USER,
+        )
+    };
+    for (res, path) in results {
+        let priority = if res.is_ok() {
+            libc::LOG_INFO
+        } else {
+            libc::LOG_WARNING
+        };
+        let msg = CString::new(syslog_message(path, res).replace('\0', "\\0"))
+            .map_err(|err| Error::CUSTOM(err.to_string()))?;
+        unsafe { libc::syslog(priority, b"%s\0".as_ptr() as *const c_char, msg.as_ptr()) };
+    }
+    unsafe { libc::closelog() };
+    Ok(())
+}
+
+#[cfg(unix)]
+fn log_journald(results: &[(Result<Counts, Error>, PathBuf)]) -> Result<(), Error> {
+    use std::os::unix::net::UnixDatagram;
+
+    let socket = UnixDatagram::unbound()?;
+    socket.connect("/run/systemd/journal/socket")?;
+    for (res, path) in results {
+        socket.send(&journal_entry(path, res))?;
+    }
+    Ok(())
+}
+
+/// Emit one record per result to target.
+pub fn log(target: LogTarget, results: &[(Result<Counts, Error>, PathBuf)]) -> Result<(), Error> {
+    #[cfg(unix)]
+    match target {
+        LogTarget::Syslog => log_syslog(results),
+        LogTarget::Journald => log_journald(results),
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = results;
+        Err(Error::CUSTOM(format!(
+            "--log-to {:?} is only supported on unix",
+            target
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::Count;
+
+    fn counts() -> Counts {
+        Counts {
+            bytes: Count { val: Some(12) },
+            chars: Count { val: None },
+            words: Count { val: Some(2) },
+            lines: Count { val: Some(1) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_syslog_message() {
+        let path = PathBuf::from("foo bar.txt");
+        assert_eq!(
+            r#"path="foo bar.txt" bytes=12 words=2 lines=1"#,
+            syslog_message(&path, &Ok(counts()))
+        );
+        let err = Err(Error::CUSTOM(String::from("oops")));
+        assert!(syslog_message(&path, &err).starts_with(r#"path="foo bar.txt" error=""#));
+    }
+
+    #[test]
+    fn test_journal_entry() {
+        let entry = journal_entry(&PathBuf::from("a\nb"), &Ok(counts()));
+        let mut expected = Vec::new();
+        expected.extend_from_slice(b"RWC_PATH\n");
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        expected.extend_from_slice(b"a\nb\n");
+        expected.extend_from_slice(b"RWC_BYTES\n");
+        expected.extend_from_slice(&2u64.to_le_bytes());
+        expected.extend_from_slice(b"12\n");
+        assert!(entry
+            .windows(expected.len())
+            .any(|window| window == expected.as_slice()));
+    }
+}
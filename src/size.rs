@@ -0,0 +1,100 @@
+//! `--apparent-size` and `--disk-usage`, the storage a file takes next to what's in it, which
+//! differ for sparse files like VM images.
+
+use crate::cli::Options;
+use crate::count::Counts;
+use crate::error::Error;
+use crate::metric::MetricValue;
+use std::fs;
+use std::path::Path;
+
+pub const APPARENT_SIZE: &str = "apparent size";
+pub const DISK_USAGE: &str = "disk usage";
+
+/// The names of the size columns enabled in opts, printed after any metrics.
+pub fn columns(opts: &Options) -> Vec<&'static str> {
+    let mut columns = Vec::new();
+    if opts.apparent_size {
+        columns.push(APPARENT_SIZE);
+    }
+    if opts.disk_usage {
+        columns.push(DISK_USAGE);
+    }
+    columns
+}
+
+/// Bytes allocated for a file, which st_blocks counts in 512 byte units. Other platforms don't
+/// expose it so it's the apparent size there.
+#[cfg(unix)]
+fn disk_usage(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks().saturating_mul(512)
+}
+
+#[cfg(not(unix))]
+fn disk_usage(meta: &fs::Metadata) -> u64 {
+    meta.len()
+}
+
+/// Append the size columns enabled in opts to the metrics of counts, also for partial counts.
+pub fn add_sizes(res: Result<Counts, Error>, path: &Path, opts: &Options) -> Result<Counts, Error> {
+    if !opts.apparent_size && !opts.disk_usage {
+        return res;
+    }
+    let add = |counts: &mut Counts| -> Result<(), Error> {
+        let meta = fs::metadata(path)?;
+        if opts.apparent_size {
+            let size = MetricValue::Count(meta.len());
+            counts.metrics.push((APPARENT_SIZE.to_string(), size));
+        }
+        if opts.disk_usage {
+            let usage = MetricValue::Count(disk_usage(&meta));
+            counts.metrics.push((DISK_USAGE.to_string(), usage));
+        }
+        Ok(())
+    };
+    match res {
+        Ok(mut counts) => add(&mut counts).map(|_| counts),
+        Err(Error::PARTIAL { mut counts, source }) => {
+            add(&mut counts)?;
+            Err(Error::PARTIAL { counts, source })
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use crate::count::{CountOptions, CountablePath};
+    use std::fs::File;
+    use std::io::{Seek, SeekFrom, Write};
+    use structopt::StructOpt;
+
+    #[test]
+    fn test_add_sizes_sparse() {
+        let path = std::env::temp_dir().join(format!("rwc-sparse-{}", std::process::id()));
+        let mut f = File::create(&path).unwrap();
+        f.seek(SeekFrom::Start(64 * 1024 * 1024)).unwrap();
+        f.write_all(b"end\n").unwrap();
+        drop(f);
+
+        let cli = Cli::from_iter(&["rwc", "--apparent-size", "--disk-usage"]);
+        let opts = Options::from(&cli);
+        let counts = (&path).count_with(CountOptions::new().lines());
+        let counts = add_sizes(counts, &path, &opts).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (name, size) = &counts.metrics[0];
+        assert_eq!(APPARENT_SIZE, name);
+        assert_eq!(&MetricValue::Count(64 * 1024 * 1024 + 4), size);
+        match &counts.metrics[1] {
+            (name, MetricValue::Count(usage)) => {
+                assert_eq!(DISK_USAGE, name);
+                assert!(*usage < 64 * 1024 * 1024);
+            }
+            metric => panic!("expected the disk usage, got {:?}", metric),
+        }
+    }
+}
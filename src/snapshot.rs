@@ -0,0 +1,248 @@
+//! `rwc snapshot`, which saves the counts of every file under some paths under a name and later
+//! prints how the current counts differ from them.
+
+use crate::cli::Options;
+use crate::compare::{differences, print_differences};
+use crate::count::{count_all, Count, Counts};
+use crate::error::Error;
+use crate::format::Format;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SavedCounts {
+    bytes: u64,
+    /// None if the file wasn't valid utf-8.
+    chars: Option<u64>,
+    words: u64,
+    lines: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The canonicalized paths which were counted, diffs recount them.
+    pub roots: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, SavedCounts>,
+}
+
+/// `<name>.json` in dir, names can't contain path separators or start with a dot.
+fn snapshot_path(dir: &Path, name: &str) -> Result<PathBuf, Error> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(Error::CUSTOM(format!("invalid snapshot name {:?}", name)));
+    }
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+/// The counts of every file under roots, keyed by canonicalized path.
+fn count_roots(roots: &[PathBuf]) -> Result<BTreeMap<PathBuf, Result<Counts, Error>>, Error> {
+    let mut files = Vec::new();
+    for root in roots {
+        for entry in WalkDir::new(root) {
+            let entry = entry.map_err(|err| Error::CUSTOM(err.to_string()))?;
+            if entry.file_type().is_file() {
+                files.push(entry.into_path());
+            }
+        }
+    }
+    Ok(files
+        .into_par_iter()
+        .map(|path| {
+            let counts = count_all(&path).map_err(|err| Error::for_path(&path, err));
+            (path, counts)
+        })
+        .collect())
+}
+
+/// Count every file under paths and save them as name in dir, replacing any snapshot with the
+/// same name.
+pub fn save(dir: &Path, name: &str, paths: &[PathBuf]) -> Result<Snapshot, Error> {
+    let file = snapshot_path(dir, name)?;
+    let roots = paths
+        .iter()
+        .map(|path| {
+            path.canonicalize()
+                .map_err(|err| Error::for_path(path, err.into()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut files = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (path, counts) in count_roots(&roots)? {
+        match counts {
+            Ok(counts) => {
+                files.insert(
+                    path,
+                    SavedCounts {
+                        bytes: counts.bytes.val.unwrap_or(0),
+                        chars: counts.chars.val,
+                        words: counts.words.val.unwrap_or(0),
+                        lines: counts.lines.val.unwrap_or(0),
+                    },
+                );
+            }
+            Err(err) => errors.push(err),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let snapshot = Snapshot { roots, files };
+    let json =
+        serde_json::to_string_pretty(&snapshot).map_err(|err| Error::CUSTOM(err.to_string()))?;
+    fs::create_dir_all(dir)?;
+    fs::write(&file, json).map_err(|err| Error::for_path(&file, err.into()))?;
+    Ok(snapshot)
+}
+
+pub fn load(dir: &Path, name: &str) -> Result<Snapshot, Error> {
+    let file = snapshot_path(dir, name)?;
+    let json = fs::read_to_string(&file).map_err(|err| {
+        Error::for_path(
+            &file,
+            Error::CUSTOM(format!("cannot read snapshot {}: {}", name, err)),
+        )
+    })?;
+    serde_json::from_str(&json)
+        .map_err(|err| Error::for_path(&file, Error::CUSTOM(err.to_string())))
+}
+
+/// Recount the roots of the snapshot name and print the paths whose counts changed since it was
+/// saved, returning whether there were none.
+pub fn diff<W: std::io::Write>(
+    dir: &Path,
+    name: &str,
+    opts: &Options,
+    fmt: Format,
+    w: W,
+) -> Result<bool, Error> {
+    let snapshot = load(dir, name)?;
+    let saved = snapshot
+        .files
+        .into_iter()
+        .map(|(path, saved)| {
+            let counts = Counts {
+                bytes: Count {
+                    val: Some(saved.bytes),
+                },
+                chars: Count { val: saved.chars },
+                words: Count {
+                    val: Some(saved.words),
+                },
+                lines: Count {
+                    val: Some(saved.lines),
+                },
+                max_line_length: Count { val: None },
+                metrics: Vec::new(),
+            };
+            (path, Ok(counts))
+        })
+        .collect();
+    let current = count_roots(&snapshot.roots)?;
+    let mut diffs = differences(saved, current);
+
+    // show paths under the current directory relative to it
+    if let Ok(cwd) = env::current_dir() {
+        for diff in &mut diffs {
+            if let Ok(relative) = diff.path.strip_prefix(&cwd) {
+                diff.path = relative.to_path_buf();
+            }
+        }
+    }
+    print_differences(&diffs, opts, fmt, w)?;
+    Ok(diffs.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::{Eol, InvalidUtf8};
+    use crate::format::parse_format;
+    use crate::sort::Sort;
+
+    #[test]
+    fn test_snapshot_path() {
+        let dir = Path::new("snapshots");
+        assert_eq!(
+            PathBuf::from("snapshots/v1.json"),
+            snapshot_path(dir, "v1").unwrap()
+        );
+        assert!(snapshot_path(dir, "").is_err());
+        assert!(snapshot_path(dir, "../v1").is_err());
+        assert!(snapshot_path(dir, ".hidden").is_err());
+    }
+
+    #[test]
+    fn test_save_and_diff() {
+        let root = env::temp_dir().join(format!("rwc-snapshot-{}", std::process::id()));
+        let (store, tree) = (root.join("store"), root.join("tree"));
+        fs::create_dir_all(&tree).unwrap();
+        fs::write(tree.join("a.txt"), "one two\n").unwrap();
+        fs::write(tree.join("b.txt"), "three\n").unwrap();
+
+        let snapshot = save(&store, "before", std::slice::from_ref(&tree)).unwrap();
+        assert_eq!(2, snapshot.files.len());
+        assert_eq!(snapshot, load(&store, "before").unwrap());
+
+        let opts = Options {
+            bytes: false,
+            chars: false,
+            words: true,
+            lines: true,
+            max_line_length: false,
+            show_totals: false,
+            stdin: false,
+            statusline: false,
+            cargo: false,
+            git_blame: false,
+            markdown_aware: false,
+            log_to: None,
+            clipboard: false,
+            badge: None,
+            goal: None,
+            csv_quote: true,
+            csv_delimiter: ',',
+            #[cfg(feature = "otel")]
+            otel_endpoint: None,
+            #[cfg(feature = "sqlite")]
+            output_db: None,
+            output: None,
+            tee: Vec::new(),
+            tokenizer: None,
+            invalid_utf8: InvalidUtf8::Strict,
+            eol: Eol::Lf,
+            count_last_line: false,
+            no_stat_fastpath: false,
+            apparent_size: false,
+            disk_usage: false,
+            sort: Sort::Path,
+            timeout: None,
+            max_open: None,
+            chunk: None,
+            metrics: Vec::new(),
+        };
+        assert!(diff(&store, "before", &opts, parse_format("csv"), Vec::new()).unwrap());
+
+        fs::write(tree.join("a.txt"), "one two\nthree four\n").unwrap();
+        fs::remove_file(tree.join("b.txt")).unwrap();
+        let mut out = Vec::new();
+        assert!(!diff(&store, "before", &opts, parse_format("csv"), &mut out).unwrap());
+        let tree = tree.canonicalize().unwrap();
+        assert_eq!(
+            format!(
+                "path,status,words,lines\n{},changed,+2,+1\n{},removed,-1,-1",
+                tree.join("a.txt").display(),
+                tree.join("b.txt").display()
+            ),
+            String::from_utf8(out).unwrap()
+        );
+
+        assert!(load(&store, "missing").is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+//! `--sort`, the order in which rows are printed.
+
+use crate::error::Error;
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sort {
+    /// Component by component in byte order, independent of the locale, e.g. `file10.txt`
+    /// before `file2.txt`.
+    #[default]
+    Path,
+    /// Like `Path` except runs of digits compare by their value, e.g. `file2.txt` before
+    /// `file10.txt`.
+    PathNatural,
+}
+
+pub fn parse_sort(src: &str) -> Result<Sort, Error> {
+    match src {
+        "path" => Ok(Sort::Path),
+        "path-natural" => Ok(Sort::PathNatural),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --sort {}, expected path or path-natural",
+            src
+        ))),
+    }
+}
+
+/// Split s into alternating runs of digits and non-digits.
+fn chunks(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != first.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+fn is_digits(chunk: &str) -> bool {
+    chunk.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Compare a and b with runs of digits compared by value. Runs with equal values, like `7` and
+/// `007`, fall back to byte order so the ordering stays total.
+pub fn natural_cmp(a: &Path, b: &Path) -> Ordering {
+    let (a_str, b_str) = (a.to_string_lossy(), b.to_string_lossy());
+    let mut a_chunks = chunks(&a_str);
+    let mut b_chunks = chunks(&b_str);
+    loop {
+        let ord = match (a_chunks.next(), b_chunks.next()) {
+            (None, None) => break,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(x), Some(y)) if is_digits(x) && is_digits(y) => {
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            (Some(x), Some(y)) => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.cmp(b)
+}
+
+/// Sort results by path with order. The sort is stable, so rows for the same path keep their
+/// order.
+pub fn sort<T: Send>(results: &mut [(T, PathBuf)], order: Sort) {
+    match order {
+        Sort::Path => results.par_sort_by(|a, b| a.1.cmp(&b.1)),
+        Sort::PathNatural => results.par_sort_by(|a, b| natural_cmp(&a.1, &b.1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(order: Sort, paths: &[&str]) -> Vec<String> {
+        let mut results: Vec<((), PathBuf)> = paths.iter().map(|p| ((), p.into())).collect();
+        sort(&mut results, order);
+        results
+            .into_iter()
+            .map(|(_, path)| path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        let paths = [
+            "file10.txt",
+            "file2.txt",
+            "b/1",
+            "file02.txt",
+            "a10b",
+            "a9",
+            "file",
+        ];
+        assert_eq!(
+            vec![
+                "a10b",
+                "a9",
+                "b/1",
+                "file",
+                "file02.txt",
+                "file10.txt",
+                "file2.txt"
+            ],
+            sorted(Sort::Path, &paths)
+        );
+        assert_eq!(
+            vec![
+                "a9",
+                "a10b",
+                "b/1",
+                "file",
+                "file02.txt",
+                "file2.txt",
+                "file10.txt"
+            ],
+            sorted(Sort::PathNatural, &paths)
+        );
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let cmp = |a: &str, b: &str| natural_cmp(Path::new(a), Path::new(b));
+        assert_eq!(Ordering::Less, cmp("x2", "x10"));
+        assert_eq!(Ordering::Less, cmp("x2", "xa"));
+        assert_eq!(Ordering::Greater, cmp("xa", "x2"));
+        assert_eq!(Ordering::Less, cmp("x007", "x7"));
+        assert_eq!(Ordering::Equal, cmp("x7", "x7"));
+        assert_eq!(Ordering::Less, cmp("x", "x1"));
+    }
+}
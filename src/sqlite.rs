@@ -0,0 +1,112 @@
+//! `--output-db`, which appends the counts of each run to a SQLite table so the growth of a
+//! codebase can be queried with SQL.
+
+use crate::count::Counts;
+use crate::error::Error;
+use crate::print::plain_row_error;
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+
+fn db_error(err: rusqlite::Error) -> Error {
+    Error::CUSTOM(format!("SQLite error: {}", err))
+}
+
+/// A row for each file of each run, the time of the run in seconds since the unix epoch. Counts
+/// which weren't computed are null, and `error` is set if the file couldn't be counted, or only
+/// partially.
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS counts (
+    run_at INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    bytes INTEGER,
+    chars INTEGER,
+    words INTEGER,
+    lines INTEGER,
+    error TEXT
+)";
+
+/// Append results to the `counts` table of the database at db, creating both if needed, in one
+/// transaction.
+pub fn append(
+    db: &Path,
+    results: &[(Result<Counts, Error>, PathBuf)],
+    run_at: u64,
+) -> Result<(), Error> {
+    let res = (|| {
+        let mut conn = Connection::open(db)?;
+        conn.execute(SCHEMA, [])?;
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO counts (run_at, path, bytes, chars, words, lines, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for (res, path) in results {
+                let counts = match res {
+                    Ok(counts) => Some(counts),
+                    Err(err) => err.partial_counts(),
+                };
+                let count =
+                    |get: fn(&Counts) -> Option<u64>| counts.and_then(get).map(|n| n as i64);
+                insert.execute(params![
+                    run_at as i64,
+                    path.display().to_string(),
+                    count(|c| c.bytes.val),
+                    count(|c| c.chars.val),
+                    count(|c| c.words.val),
+                    count(|c| c.lines.val),
+                    res.as_ref().err().map(plain_row_error),
+                ])?;
+            }
+        }
+        tx.commit()
+    })();
+    res.map_err(|err| Error::for_path(db, db_error(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::Count;
+    use std::{env, fs};
+
+    #[test]
+    fn test_append() {
+        let db = env::temp_dir().join(format!("rwc-sqlite-{}.sqlite", std::process::id()));
+        let counts = |n| Counts {
+            bytes: Count { val: Some(n) },
+            chars: Count { val: None },
+            words: Count { val: Some(n) },
+            lines: Count { val: Some(n) },
+            max_line_length: Count { val: None },
+            metrics: Vec::new(),
+        };
+        append(&db, &[(Ok(counts(1)), PathBuf::from("a"))], 100).unwrap();
+        let results = vec![
+            (Ok(counts(2)), PathBuf::from("a")),
+            (Err(Error::UTF8()), PathBuf::from("b")),
+        ];
+        append(&db, &results, 200).unwrap();
+
+        let conn = Connection::open(&db).unwrap();
+        let mut query = conn
+            .prepare(
+                "SELECT printf('%d %s %s %s %s', run_at, path, ifnull(bytes, 'null'),
+                 ifnull(chars, 'null'), ifnull(error, 'null')) FROM counts ORDER BY rowid",
+            )
+            .unwrap();
+        let rows: Vec<String> = query
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        fs::remove_file(&db).unwrap();
+        assert_eq!(
+            vec![
+                String::from("100 a 1 null null"),
+                String::from("200 a 2 null null"),
+                format!("200 b null null {}", Error::UTF8().plain_report()),
+            ],
+            rows
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! The progress of the current run, printed on SIGUSR1 (or SIGINFO) like `dd` does.
+
+use num_format::{Locale, ToFormattedString};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+static FILES_DONE: AtomicU64 = AtomicU64::new(0);
+static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+static COUNTING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Record that path is being counted.
+pub fn start(path: &Path) {
+    if let Ok(mut counting) = COUNTING.lock() {
+        counting.push(path.to_path_buf());
+    }
+}
+
+/// Record that path is done, whether or not it could be counted.
+pub fn finish(path: &Path) {
+    if let Ok(mut counting) = COUNTING.lock() {
+        if let Some(i) = counting.iter().position(|p| p == path) {
+            counting.swap_remove(i);
+        }
+    }
+    FILES_DONE.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn add_bytes(n: u64) {
+    BYTES_READ.fetch_add(n, Ordering::Relaxed);
+}
+
+/// A line like `12 files done, 1,234,567 bytes read, counting: big.log`.
+pub fn report() -> String {
+    let files = FILES_DONE.load(Ordering::Relaxed);
+    let mut report = format!(
+        "{} {} done, {} bytes read",
+        files,
+        if files == 1 { "file" } else { "files" },
+        BYTES_READ
+            .load(Ordering::Relaxed)
+            .to_formatted_string(&Locale::en)
+    );
+    if let Ok(counting) = COUNTING.lock() {
+        if !counting.is_empty() {
+            let paths: Vec<String> = counting.iter().map(|p| p.display().to_string()).collect();
+            report.push_str(&format!(", counting: {}", paths.join(", ")));
+        }
+    }
+    report
+}
+
+/// A reader which adds the bytes it reads to the run's progress.
+pub(crate) struct Tracked<R>(pub R);
+
+impl<R: Read> Read for Tracked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.read(buf)?;
+        add_bytes(n as u64);
+        Ok(n)
+    }
+}
@@ -0,0 +1,113 @@
+//! `--timeout`, which bounds how long counting each input may take.
+
+use crate::error::Error;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Seconds like `30` or `0.5`.
+pub fn parse_timeout(src: &str) -> Result<Duration, Error> {
+    match src.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs > 0.0 => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(Error::CUSTOM(format!(
+            "invalid --timeout {}, expected a positive number of seconds",
+            src
+        ))),
+    }
+}
+
+/// When the `--timeout` for one input runs out. Counting checks it between reads, see
+/// `CountOptions::deadline`, so it stops on its own rather than running on unobserved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    start: Instant,
+    timeout: Duration,
+}
+
+impl Deadline {
+    pub fn after(timeout: Duration) -> Deadline {
+        Deadline {
+            start: Instant::now(),
+            timeout,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.timeout.saturating_sub(self.start.elapsed())
+    }
+
+    /// Fail with the timeout error if the deadline has passed.
+    pub fn check(&self) -> Result<(), Error> {
+        if self.remaining().is_zero() {
+            Err(self.error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn error(&self) -> Error {
+        Error::CUSTOM(format!("timed out after {}s", self.timeout.as_secs_f64()))
+    }
+}
+
+/// Run f on its own thread and fail if it hasn't finished by deadline. f should check the
+/// deadline itself to stop soon after, a thread blocked forever, e.g. opening a FIFO without a
+/// writer, is left behind rather than wedging the caller.
+pub fn with_timeout<T, F>(deadline: Deadline, f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        // the receiver is gone if we timed out
+        let _ = tx.send(f());
+    });
+    rx.recv_timeout(deadline.remaining())
+        .unwrap_or_else(|_| Err(deadline.error()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(Duration::from_millis(500), parse_timeout("0.5").unwrap());
+        assert_eq!(Duration::from_secs(30), parse_timeout("30").unwrap());
+        assert!(parse_timeout("0").is_err());
+        assert!(parse_timeout("-1").is_err());
+        assert!(parse_timeout("soon").is_err());
+    }
+
+    #[test]
+    fn test_with_timeout() {
+        let res = with_timeout(Deadline::after(Duration::from_secs(5)), || Ok(42));
+        assert_eq!(42, res.unwrap());
+
+        let deadline = Deadline::after(Duration::from_millis(10));
+        let res: Result<(), Error> = with_timeout(deadline, || {
+            thread::sleep(Duration::from_secs(1));
+            Ok(())
+        });
+        match res {
+            Err(Error::CUSTOM(msg)) => assert_eq!("timed out after 0.01s", msg),
+            res => panic!("expected a timeout, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_deadline() {
+        let deadline = Deadline::after(Duration::from_secs(5));
+        assert!(deadline.remaining() > Duration::from_secs(4));
+        assert!(deadline.check().is_ok());
+
+        let deadline = Deadline::after(Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(Duration::ZERO, deadline.remaining());
+        match deadline.check() {
+            Err(Error::CUSTOM(msg)) => assert_eq!("timed out after 0.01s", msg),
+            res => panic!("expected a timeout, got {:?}", res),
+        }
+    }
+}
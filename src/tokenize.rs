@@ -0,0 +1,175 @@
+use crate::error::Error;
+use regex::bytes::Regex;
+use std::fmt;
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Defines what a word is when counting words. Lines are tokenized independently so a word can
+/// never contain a newline.
+pub trait Tokenizer: fmt::Debug + Send + Sync {
+    /// Number of words in line, which doesn't include its terminating newline.
+    fn count_words(&self, line: &[u8]) -> usize;
+
+    /// Call f with each of the `count_words` words of line, for metrics which look at the words
+    /// themselves like `--avg-word-length`.
+    fn for_each_word(&self, line: &[u8], f: &mut dyn FnMut(&[u8]));
+}
+
+/// A word is a non-zero-length sequence of non-whitespace bytes delimited by ascii whitespace.
+/// This is the default and the counters have a faster specialized path for it when no tokenizer
+/// is given.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiWhitespace;
+
+impl Tokenizer for AsciiWhitespace {
+    fn count_words(&self, line: &[u8]) -> usize {
+        line.split(|b| b.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .count()
+    }
+
+    fn for_each_word(&self, line: &[u8], f: &mut dyn FnMut(&[u8])) {
+        line.split(|b| b.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .for_each(f)
+    }
+}
+
+/// A word is a non-zero-length sequence of chars delimited by Unicode whitespace, so a no-break
+/// space, an em space, or an ideographic space separate words too. Invalid UTF-8 is replaced with
+/// U+FFFD before splitting.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeWhitespace;
+
+impl Tokenizer for UnicodeWhitespace {
+    fn count_words(&self, line: &[u8]) -> usize {
+        String::from_utf8_lossy(line).split_whitespace().count()
+    }
+
+    fn for_each_word(&self, line: &[u8], f: &mut dyn FnMut(&[u8])) {
+        String::from_utf8_lossy(line)
+            .split_whitespace()
+            .for_each(|word| f(word.as_bytes()))
+    }
+}
+
+/// Words as defined by the Unicode word boundaries of UAX #29, ignoring punctuation and
+/// whitespace. Invalid UTF-8 is replaced with U+FFFD before segmenting.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeWords;
+
+impl Tokenizer for UnicodeWords {
+    fn count_words(&self, line: &[u8]) -> usize {
+        String::from_utf8_lossy(line).unicode_words().count()
+    }
+
+    fn for_each_word(&self, line: &[u8], f: &mut dyn FnMut(&[u8])) {
+        String::from_utf8_lossy(line)
+            .unicode_words()
+            .for_each(|word| f(word.as_bytes()))
+    }
+}
+
+/// A word is any non-overlapping match of the regex.
+#[derive(Debug, Clone)]
+pub struct RegexWords(pub Regex);
+
+impl Tokenizer for RegexWords {
+    fn count_words(&self, line: &[u8]) -> usize {
+        self.0.find_iter(line).count()
+    }
+
+    fn for_each_word(&self, line: &[u8], f: &mut dyn FnMut(&[u8])) {
+        self.0.find_iter(line).for_each(|m| f(m.as_bytes()))
+    }
+}
+
+/// Parse the `--tokenizer` argument, one of `ascii-whitespace`, `unicode-whitespace`,
+/// `unicode-words`, or `regex:<pattern>`.
+pub fn parse_tokenizer(src: &str) -> Result<Arc<dyn Tokenizer>, Error> {
+    match src {
+        "ascii-whitespace" => Ok(Arc::new(AsciiWhitespace)),
+        "unicode-whitespace" => Ok(Arc::new(UnicodeWhitespace)),
+        "unicode-words" => Ok(Arc::new(UnicodeWords)),
+        _ if src.starts_with("regex:") => match Regex::new(&src["regex:".len()..]) {
+            Ok(re) => Ok(Arc::new(RegexWords(re))),
+            Err(err) => Err(Error::PARSETOKENIZER(err.to_string())),
+        },
+        _ => Err(Error::PARSETOKENIZER(src.into())),
+    }
+}
+
+/// Parse the `--word-pattern` argument, a regex each match of which is a word.
+pub fn parse_word_pattern(src: &str) -> Result<Arc<dyn Tokenizer>, Error> {
+    match Regex::new(src) {
+        Ok(re) => Ok(Arc::new(RegexWords(re))),
+        Err(err) => Err(Error::PARSETOKENIZER(err.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizers() {
+        let line = "hello, wörld\u{a0}foo\tbar 東京".as_bytes();
+        assert_eq!(4, AsciiWhitespace.count_words(line));
+        assert_eq!(5, UnicodeWhitespace.count_words(line));
+        assert_eq!(
+            3,
+            UnicodeWhitespace.count_words("a\u{2003}b\u{3000}c".as_bytes())
+        );
+        assert_eq!(6, UnicodeWords.count_words(line));
+        assert_eq!(
+            2,
+            RegexWords(Regex::new("[a-z]{3}").unwrap()).count_words(b"abcdef ab")
+        );
+    }
+
+    #[test]
+    fn test_for_each_word() {
+        let words = |tokenizer: &dyn Tokenizer, line: &str| {
+            let mut words = Vec::new();
+            tokenizer.for_each_word(line.as_bytes(), &mut |word| {
+                words.push(String::from_utf8_lossy(word).into_owned())
+            });
+            words
+        };
+        let line = "hello, wörld\u{a0}foo\tbar";
+        assert_eq!(
+            vec!["hello,", "wörld\u{a0}foo", "bar"],
+            words(&AsciiWhitespace, line)
+        );
+        assert_eq!(
+            vec!["hello,", "wörld", "foo", "bar"],
+            words(&UnicodeWhitespace, line)
+        );
+        assert_eq!(
+            vec!["hello", "wörld", "foo", "bar"],
+            words(&UnicodeWords, line)
+        );
+        let three = RegexWords(Regex::new("[a-z]{3}").unwrap());
+        assert_eq!(vec!["hel", "rld", "foo", "bar"], words(&three, line));
+    }
+
+    #[test]
+    fn test_parse_tokenizer() {
+        assert_eq!(
+            1,
+            parse_tokenizer("regex:a+").unwrap().count_words(b"baaab")
+        );
+        assert!(parse_tokenizer("regex:(").is_err());
+        assert!(parse_tokenizer("foo").is_err());
+    }
+
+    #[test]
+    fn test_parse_word_pattern() {
+        let ips = parse_word_pattern(r"\d+\.\d+\.\d+\.\d+").unwrap();
+        assert_eq!(
+            2,
+            ips.count_words(b"from 10.0.0.1 to 192.168.1.20 at 12.30")
+        );
+        assert!(parse_word_pattern("(").is_err());
+    }
+}
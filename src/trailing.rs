@@ -0,0 +1,56 @@
+//! `--trailing-ws`, the lines of each file ending with spaces or tabs, for style checks.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+
+pub const TRAILING_WS: &str = "trailing ws";
+
+/// Lines whose last byte before the `\n`, and any `\r` before it, is a space or tab. A last
+/// line without a `\n` counts too.
+#[derive(Debug, Default)]
+pub struct TrailingWs {
+    lines: u64,
+    /// Whether the current line ends with a space or tab so far.
+    trailing: bool,
+}
+
+impl Metric for TrailingWs {
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            match b {
+                b'\n' => {
+                    self.lines += u64::from(self.trailing);
+                    self.trailing = false;
+                }
+                b'\r' => {}
+                _ => self.trailing = b == b' ' || b == b'\t',
+            }
+        }
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.lines += u64::from(std::mem::take(&mut self.trailing));
+        MetricValue::Count(self.lines)
+    }
+}
+
+/// The `TRAILING_WS` column.
+pub fn trailing_ws_metric() -> MetricFactory {
+    MetricFactory::new(TRAILING_WS, TrailingWs::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailing_ws() {
+        let input = b"a \nb\n\t\r\n  \n\nc\t d\ne\t";
+        for chunk in 1..input.len() {
+            let mut metric = trailing_ws_metric().create();
+            for chunk in input.chunks(chunk) {
+                metric.update(chunk);
+            }
+            assert_eq!(MetricValue::Count(4), metric.finalize());
+        }
+    }
+}
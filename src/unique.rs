@@ -0,0 +1,79 @@
+//! `--unique-words`, the distinct words of each file and of all of them in the totals row, for
+//! the vocabulary of a corpus.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::Hasher;
+
+pub const UNIQUE_WORDS: &str = "unique words";
+
+/// Words split on ascii whitespace like the default word count and compared byte for byte, so
+/// `The` and `the` are different words. Only a 64-bit hash of each word is kept rather than the
+/// word, with the same keys for every file so the totals row can merge them.
+#[derive(Debug, Default)]
+pub struct UniqueWords {
+    hashes: HashSet<u64>,
+    /// The current word so far, which may span chunks.
+    word: Vec<u8>,
+}
+
+impl UniqueWords {
+    fn end_word(&mut self) {
+        if !self.word.is_empty() {
+            let mut hasher = DefaultHasher::new();
+            hasher.write(&self.word);
+            self.hashes.insert(hasher.finish());
+            self.word.clear();
+        }
+    }
+}
+
+impl Metric for UniqueWords {
+    fn update(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            if b.is_ascii_whitespace() {
+                self.end_word();
+            } else {
+                self.word.push(b);
+            }
+        }
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        self.end_word();
+        MetricValue::Distinct(std::mem::take(&mut self.hashes))
+    }
+}
+
+/// The `UNIQUE_WORDS` column.
+pub fn unique_words_metric() -> MetricFactory {
+    MetricFactory::new(UNIQUE_WORDS, UniqueWords::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unique_words(input: &str, chunk: usize) -> MetricValue {
+        let mut metric = unique_words_metric().create();
+        for chunk in input.as_bytes().chunks(chunk) {
+            metric.update(chunk);
+        }
+        metric.finalize()
+    }
+
+    #[test]
+    fn test_unique_words() {
+        let input = "the cat and the hat\nThe cat\t東京 東京";
+        for chunk in 1..input.len() {
+            assert_eq!("6", unique_words(input, chunk).to_string());
+        }
+        assert_eq!("0", unique_words("", 64).to_string());
+
+        let total = unique_words("a b", 64)
+            .add(&unique_words("b c", 64))
+            .unwrap();
+        assert_eq!("3", total.to_string());
+    }
+}
@@ -0,0 +1,97 @@
+//! `--count-urls`, the http(s) URLs in each file, for auditing link density in documentation.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+
+pub const URLS: &str = "urls";
+
+/// What a URL starts with, matched case insensitively with the `s` optional.
+const SCHEME: &[u8] = b"https://";
+/// Index of the optional `s` in `SCHEME`.
+const SECURE: usize = 4;
+
+/// Counts `http://` and `https://` followed by a host, unlike a regex keeping its progress
+/// between chunks and skipping ahead to the next `h` between URLs. The scheme has to start a
+/// word, so `xhttp://a` isn't a URL.
+#[derive(Debug, Default)]
+pub struct CountUrls {
+    urls: u64,
+    /// Bytes of `SCHEME` matched so far.
+    matched: usize,
+    /// Whether the last byte was alphanumeric.
+    in_word: bool,
+}
+
+/// The first byte of a host, which rules out `http://` on its own or in prose.
+fn starts_host(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'[' || !b.is_ascii()
+}
+
+impl Metric for CountUrls {
+    fn update(&mut self, chunk: &[u8]) {
+        let mut i = 0;
+        while i < chunk.len() {
+            if self.matched == 0 {
+                match chunk[i..].iter().position(|&b| b | 0x20 == b'h') {
+                    Some(n) if n > 0 => {
+                        self.in_word = chunk[i + n - 1].is_ascii_alphanumeric();
+                        i += n;
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.in_word = chunk[chunk.len() - 1].is_ascii_alphanumeric();
+                        return;
+                    }
+                }
+            }
+            let b = chunk[i];
+            self.matched = if self.matched == SCHEME.len() {
+                self.urls += u64::from(starts_host(b));
+                0
+            } else if self.matched == SECURE && b == b':' {
+                // past the `s` and the `:`
+                SECURE + 2
+            } else if b.to_ascii_lowercase() == SCHEME[self.matched]
+                && (self.matched > 0 || !self.in_word)
+            {
+                self.matched + 1
+            } else {
+                0
+            };
+            self.in_word = b.is_ascii_alphanumeric();
+            i += 1;
+        }
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        MetricValue::Count(self.urls)
+    }
+}
+
+/// The `URLS` column.
+pub fn count_urls_metric() -> MetricFactory {
+    MetricFactory::new(URLS, CountUrls::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(input: &str, chunk: usize) -> MetricValue {
+        let mut metric = CountUrls::default();
+        for chunk in input.as_bytes().chunks(chunk) {
+            metric.update(chunk);
+        }
+        metric.finalize()
+    }
+
+    #[test]
+    fn test_count_urls() {
+        let input = "See https://example.com and <HTTP://a.b/c>, [docs](http://[::1]:80/).\n\
+                     Not xhttp://a, http:// alone, https:/x, httpss://x or ftp://x.\n\
+                     hhttps://no http://ok https://例え.jp";
+        for chunk in 1..input.len() {
+            assert_eq!(MetricValue::Count(5), count(input, chunk));
+        }
+        assert_eq!(MetricValue::Count(0), count("", 64));
+    }
+}
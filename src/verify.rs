@@ -0,0 +1,215 @@
+//! `rwc verify`, which counts files with each of rwc's counting paths and optionally the system
+//! wc and reports any counts which differ.
+
+use crate::console;
+use crate::count::{count_bytes, count_pass, Count, CountOptions, Counts, BUFFER_SIZE};
+use crate::error::Error;
+use colored::*;
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const METRICS: [&str; 4] = ["bytes", "chars", "words", "lines"];
+
+/// A count of one metric of a file and how it was computed.
+#[derive(Debug)]
+struct Sample {
+    source: &'static str,
+    metric: &'static str,
+    val: Result<u64, String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Discrepancy {
+    pub path: PathBuf,
+    pub metric: &'static str,
+    /// Each source whose count differs from rwc's, with its count or error.
+    pub expected: String,
+    pub actual: Vec<(&'static str, String)>,
+}
+
+/// Known reasons for wc to disagree with rwc.
+fn explanation(metric: &str) -> Option<&'static str> {
+    match metric {
+        "chars" => Some("wc -m depends on the locale (LANG, LC_ALL), in a non-UTF-8 locale it counts bytes, and it counts invalid UTF-8 which rwc rejects"),
+        "words" => Some("rwc only splits words on ascii whitespace (see --tokenizer) while wc splits on the locale's whitespace and may skip words without printable characters in the locale"),
+        _ => None,
+    }
+}
+
+fn field(counts: &Counts, metric: &str) -> Count {
+    match metric {
+        "bytes" => counts.bytes,
+        "chars" => counts.chars,
+        "words" => counts.words,
+        _ => counts.lines,
+    }
+}
+
+fn samples(
+    source: &'static str,
+    res: Result<Counts, Error>,
+    metrics: &[&'static str],
+) -> Vec<Sample> {
+    metrics
+        .iter()
+        .filter_map(|&metric| {
+            let val = match &res {
+                Ok(counts) => Ok(field(counts, metric).val?),
+                Err(err) => Err(err.plain_report()),
+            };
+            Some(Sample {
+                source,
+                metric,
+                val,
+            })
+        })
+        .collect()
+}
+
+fn count_reader(path: &Path, opts: CountOptions) -> Result<Counts, Error> {
+    let reader = BufReader::with_capacity(BUFFER_SIZE, File::open(path)?);
+    count_pass(reader, &opts, &mut [], |_| {})
+}
+
+fn count_wc(wc: &str, path: &Path, metric: &'static str) -> Sample {
+    let flag = match metric {
+        "bytes" => "-c",
+        "chars" => "-m",
+        "words" => "-w",
+        _ => "-l",
+    };
+    let val = File::open(path)
+        .and_then(|f| {
+            Command::new(wc)
+                .arg(flag)
+                .stdin(f)
+                .stderr(Stdio::null())
+                .output()
+        })
+        .map_err(|err| err.to_string())
+        .and_then(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("unexpected output from {} {}", wc, flag))
+        });
+    Sample {
+        source: "wc",
+        metric,
+        val,
+    }
+}
+
+/// Count path every way rwc can, and with the wc binary if given, and return the counts which
+/// differ from rwc's full count.
+pub fn verify_path(path: &Path, wc: Option<&str>) -> Vec<Discrepancy> {
+    // the first sample of each metric is the reference, which is what the cli prints
+    let mut all = samples(
+        "rwc",
+        count_reader(path, CountOptions::new().bytes().words().lines()),
+        &["bytes", "words", "lines"],
+    );
+    // only invalid utf-8 in the chars count is worth reporting
+    let chars = count_reader(path, CountOptions::new().chars().words().lines());
+    let metrics: &[_] = if chars.is_ok() { &METRICS } else { &["chars"] };
+    all.extend(samples("rwc chars", chars, metrics));
+    all.extend(samples(
+        "rwc metadata",
+        count_bytes(path, |_| {}),
+        &["bytes"],
+    ));
+    all.extend(samples(
+        "rwc lines",
+        count_reader(path, CountOptions::new().lines()),
+        &["bytes", "lines"],
+    ));
+    if let Some(wc) = wc {
+        all.extend(METRICS.iter().map(|metric| count_wc(wc, path, metric)));
+    }
+
+    fn show(val: &Result<u64, String>) -> String {
+        match val {
+            Ok(n) => n.to_string(),
+            Err(err) => err.clone(),
+        }
+    }
+
+    METRICS
+        .iter()
+        .filter_map(|&metric| {
+            let mut samples = all.iter().filter(|s| s.metric == metric);
+            let reference = samples.next()?;
+            let actual: Vec<_> = samples
+                .filter(|s| s.val != reference.val)
+                .map(|s| (s.source, show(&s.val)))
+                .collect();
+            if actual.is_empty() {
+                return None;
+            }
+            Some(Discrepancy {
+                path: path.to_path_buf(),
+                metric,
+                expected: show(&reference.val),
+                actual,
+            })
+        })
+        .collect()
+}
+
+/// Verify each of paths and print the discrepancies to w. Returns whether every count agreed.
+pub fn run<W: Write>(paths: &[PathBuf], wc: Option<&str>, mut w: W) -> Result<bool, Error> {
+    let discrepancies: Vec<_> = paths.par_iter().map(|path| verify_path(path, wc)).collect();
+    let mut n = 0;
+    for d in discrepancies.iter().flatten() {
+        n += 1;
+        let actual: Vec<_> = d
+            .actual
+            .iter()
+            .map(|(source, val)| format!("{} {}", source, val))
+            .collect();
+        writeln!(
+            w,
+            "{}: {} differ: rwc {}, {}",
+            console::display_path(&d.path).bold(),
+            d.metric.red(),
+            d.expected,
+            actual.join(", ")
+        )?;
+        if d.actual.iter().any(|(source, _)| *source == "wc") {
+            if let Some(why) = explanation(d.metric) {
+                writeln!(w, "  {}: {}", "note".yellow(), why)?;
+            }
+        }
+    }
+    writeln!(w, "{} files verified, {} discrepancies", paths.len(), n)?;
+    Ok(n == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_path() {
+        let path: PathBuf = ["test_data", "default.txt"].iter().collect();
+        assert!(verify_path(&path, None).is_empty());
+
+        // every way of counting fails the same way
+        let path: PathBuf = ["test_data", "does_not_exist.txt"].iter().collect();
+        assert!(verify_path(&path, None).is_empty());
+    }
+
+    #[test]
+    fn test_verify_path_wc() {
+        let path: PathBuf = ["test_data", "default.txt"].iter().collect();
+        // false prints nothing so every metric differs
+        let discrepancies = verify_path(&path, Some("false"));
+        assert_eq!(4, discrepancies.len());
+        assert_eq!("bytes", discrepancies[0].metric);
+        assert_eq!("1048697", discrepancies[0].expected);
+        assert_eq!("wc", discrepancies[0].actual[0].0);
+    }
+}
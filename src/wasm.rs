@@ -0,0 +1,66 @@
+//! wasm-bindgen API for counting in-memory data, e.g. from JavaScript:
+//!
+//! ```js
+//! const counts = count(bytes, new CountOptions().words().chars());
+//! console.log(counts.words, counts.chars);
+//! ```
+
+use crate::count::{self, Countable};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(js_name = CountOptions)]
+#[derive(Debug, Default, Clone)]
+pub struct JsCountOptions(count::CountOptions);
+
+#[wasm_bindgen(js_class = CountOptions)]
+impl JsCountOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsCountOptions {
+        JsCountOptions::default()
+    }
+
+    pub fn bytes(self) -> JsCountOptions {
+        JsCountOptions(self.0.bytes())
+    }
+
+    pub fn chars(self) -> JsCountOptions {
+        JsCountOptions(self.0.chars())
+    }
+
+    pub fn words(self) -> JsCountOptions {
+        JsCountOptions(self.0.words())
+    }
+
+    pub fn lines(self) -> JsCountOptions {
+        JsCountOptions(self.0.lines())
+    }
+}
+
+/// Counts which weren't computed are `undefined`.
+#[wasm_bindgen(js_name = Counts)]
+#[derive(Debug, Clone, Copy)]
+pub struct JsCounts {
+    pub bytes: Option<u64>,
+    pub chars: Option<u64>,
+    pub words: Option<u64>,
+    pub lines: Option<u64>,
+}
+
+impl From<count::Counts> for JsCounts {
+    fn from(counts: count::Counts) -> JsCounts {
+        JsCounts {
+            bytes: counts.bytes.val,
+            chars: counts.chars.val,
+            words: counts.words.val,
+            lines: counts.lines.val,
+        }
+    }
+}
+
+/// Count `data` (a `Uint8Array` on the JS side) the same way the CLI counts a file.
+#[wasm_bindgen]
+pub fn count(data: &[u8], options: &JsCountOptions) -> Result<JsCounts, JsValue> {
+    data.count_with(options.0.clone())
+        .map(JsCounts::from)
+        .map_err(|err| JsValue::from_str(&err.plain_report()))
+}
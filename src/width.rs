@@ -0,0 +1,101 @@
+//! The terminal columns of the widest line for `-L`, which is what a column limit means for files
+//! with wide chars like CJK.
+
+use std::str;
+use unicode_width::UnicodeWidthChar;
+
+/// Columns a tab advances to the next multiple of, like wc.
+const TAB_WIDTH: u64 = 8;
+
+/// Decodes utf-8 split across chunks, with each invalid sequence as one U+FFFD.
+#[derive(Debug, Default)]
+pub(crate) struct Decoder {
+    /// The start of a utf-8 sequence which may be completed by the next chunk.
+    carry: Vec<u8>,
+}
+
+impl Decoder {
+    pub(crate) fn decode(&mut self, chunk: &[u8], mut f: impl FnMut(char)) {
+        let mut input = std::mem::take(&mut self.carry);
+        input.extend_from_slice(chunk);
+        let mut rest = &input[..];
+        loop {
+            match str::from_utf8(rest) {
+                Ok(s) => {
+                    s.chars().for_each(&mut f);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, after) = rest.split_at(err.valid_up_to());
+                    str::from_utf8(valid).unwrap().chars().for_each(&mut f);
+                    match err.error_len() {
+                        Some(n) => {
+                            f(char::REPLACEMENT_CHARACTER);
+                            rest = &after[n..];
+                        }
+                        None => {
+                            self.carry = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decode a sequence left incomplete by the end of the input.
+    pub(crate) fn finish(&mut self, mut f: impl FnMut(char)) {
+        if !std::mem::take(&mut self.carry).is_empty() {
+            f(char::REPLACEMENT_CHARACTER);
+        }
+    }
+}
+
+/// The columns of the widest line by East Asian width, so wide chars like 東 are 2 columns and
+/// combining marks and control chars are none.
+#[derive(Debug, Default)]
+pub(crate) struct Widest {
+    pub(crate) max: u64,
+    /// Columns of the current line so far.
+    width: u64,
+}
+
+impl Widest {
+    /// Add c to the current line, or start the next one if c ends it.
+    pub(crate) fn add(&mut self, c: char, ends_line: bool) {
+        if ends_line {
+            self.width = 0;
+            return;
+        }
+        match c {
+            '\t' => self.width += TAB_WIDTH - self.width % TAB_WIDTH,
+            _ => self.width += c.width().unwrap_or(0) as u64,
+        }
+        self.max = self.max.max(self.width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn width(input: &[u8], chunk: usize) -> u64 {
+        let (mut decoder, mut widest) = (Decoder::default(), Widest::default());
+        for chunk in input.chunks(chunk) {
+            decoder.decode(chunk, |c| widest.add(c, c == '\n'));
+        }
+        decoder.finish(|c| widest.add(c, false));
+        widest.max
+    }
+
+    #[test]
+    fn test_widest() {
+        let input = "abc\n東京タワー\ne\u{301}\tx\n".as_bytes();
+        for chunk in 1..input.len() {
+            assert_eq!(10, width(input, chunk));
+        }
+        assert_eq!(9, width("a\tb".as_bytes(), 64));
+        assert_eq!(4, width(b"a\xffb\xe6", 64));
+        assert_eq!(0, width(b"", 64));
+    }
+}
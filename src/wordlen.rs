@@ -0,0 +1,164 @@
+//! `--avg-word-length`, the mean chars per word, which is unusually high for minified code or
+//! base64 and differs between languages, and `--word-length-histogram`, the whole distribution.
+
+use crate::metric::{Metric, MetricFactory, MetricValue};
+use crate::tokenize::Tokenizer;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub const AVG_WORD_LENGTH: &str = "avg word length";
+pub const WORD_LENGTHS: &str = "word lengths";
+
+/// Chars of a word, counted without decoding as the bytes which don't continue a utf-8 sequence.
+fn chars(word: &[u8]) -> u64 {
+    word.iter().filter(|&&b| b & 0xc0 != 0x80).count() as u64
+}
+
+/// Words split by a tokenizer like the word count, which is given one line at a time.
+#[derive(Debug)]
+struct Words {
+    tokenizer: Arc<dyn Tokenizer>,
+    /// A line which spans chunks.
+    line: Vec<u8>,
+}
+
+impl Words {
+    fn new(tokenizer: Arc<dyn Tokenizer>) -> Words {
+        Words {
+            tokenizer,
+            line: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8], f: &mut dyn FnMut(&[u8])) {
+        for piece in chunk.split_inclusive(|&b| b == b'\n') {
+            match piece.split_last() {
+                Some((b'\n', line)) if self.line.is_empty() => {
+                    self.tokenizer.for_each_word(line, f);
+                }
+                Some((b'\n', line)) => {
+                    self.line.extend_from_slice(line);
+                    self.tokenizer.for_each_word(&self.line, f);
+                    self.line.clear();
+                }
+                _ => self.line.extend_from_slice(piece),
+            }
+        }
+    }
+
+    /// The words of a last line without a `\n`.
+    fn finish(&mut self, f: &mut dyn FnMut(&[u8])) {
+        let line = std::mem::take(&mut self.line);
+        self.tokenizer.for_each_word(&line, f);
+    }
+}
+
+/// Chars per word.
+#[derive(Debug)]
+pub struct AvgWordLength {
+    words: Words,
+    chars: u64,
+    /// Words so far.
+    count: u64,
+}
+
+impl Metric for AvgWordLength {
+    fn update(&mut self, chunk: &[u8]) {
+        let (total, count) = (&mut self.chars, &mut self.count);
+        self.words.update(chunk, &mut |word| {
+            *total += chars(word);
+            *count += 1;
+        });
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        let (total, count) = (&mut self.chars, &mut self.count);
+        self.words.finish(&mut |word| {
+            *total += chars(word);
+            *count += 1;
+        });
+        if self.count == 0 {
+            MetricValue::Float(0.0)
+        } else {
+            MetricValue::Float(self.chars as f64 / self.count as f64)
+        }
+    }
+}
+
+/// The `AVG_WORD_LENGTH` column with words split by tokenizer.
+pub fn avg_word_length_metric(tokenizer: Arc<dyn Tokenizer>) -> MetricFactory {
+    MetricFactory::new(AVG_WORD_LENGTH, move || AvgWordLength {
+        words: Words::new(tokenizer.clone()),
+        chars: 0,
+        count: 0,
+    })
+}
+
+/// Words by their length in chars, split and counted like `AvgWordLength`.
+#[derive(Debug)]
+pub struct WordLengths {
+    words: Words,
+    lengths: BTreeMap<u64, u64>,
+}
+
+impl Metric for WordLengths {
+    fn update(&mut self, chunk: &[u8]) {
+        let lengths = &mut self.lengths;
+        self.words.update(chunk, &mut |word| {
+            *lengths.entry(chars(word)).or_insert(0) += 1
+        });
+    }
+
+    fn finalize(&mut self) -> MetricValue {
+        let lengths = &mut self.lengths;
+        self.words
+            .finish(&mut |word| *lengths.entry(chars(word)).or_insert(0) += 1);
+        MetricValue::Histogram(std::mem::take(&mut self.lengths))
+    }
+}
+
+/// The `WORD_LENGTHS` column with words split by tokenizer.
+pub fn word_length_histogram_metric(tokenizer: Arc<dyn Tokenizer>) -> MetricFactory {
+    MetricFactory::new(WORD_LENGTHS, move || WordLengths {
+        words: Words::new(tokenizer.clone()),
+        lengths: BTreeMap::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize::{AsciiWhitespace, UnicodeWords};
+
+    fn metric(factory: MetricFactory, input: &str, chunk: usize) -> MetricValue {
+        let mut metric = factory.create();
+        for chunk in input.as_bytes().chunks(chunk) {
+            metric.update(chunk);
+        }
+        metric.finalize()
+    }
+
+    #[test]
+    fn test_avg_word_length() {
+        let ascii = || avg_word_length_metric(Arc::new(AsciiWhitespace));
+        let input = "a bb\ncafé  東京\n";
+        // a word split between chunks is one word
+        for chunk in 1..input.len() {
+            assert_eq!(MetricValue::Float(2.25), metric(ascii(), input, chunk));
+        }
+        assert_eq!(MetricValue::Float(0.0), metric(ascii(), "", 1));
+        // punctuation isn't part of unicode words
+        let unicode = avg_word_length_metric(Arc::new(UnicodeWords));
+        assert_eq!(MetricValue::Float(2.0), metric(unicode, "ab, cd.\nef", 3));
+    }
+
+    #[test]
+    fn test_word_lengths() {
+        let ascii = || word_length_histogram_metric(Arc::new(AsciiWhitespace));
+        let value = metric(ascii(), "a bb\ncafé  東京 xy", 3);
+        assert_eq!("1:1 2:3 4:1", value.to_string());
+        assert_eq!("", metric(ascii(), "", 1).to_string());
+        let unicode = word_length_histogram_metric(Arc::new(UnicodeWords));
+        assert_eq!("2:2 3:1", metric(unicode, "ab, cd.\nefg", 3).to_string());
+    }
+}
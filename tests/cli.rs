@@ -0,0 +1,266 @@
+//! End to end runs of the `rwc` binary, on every platform CI builds for.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn rwc(args: &[&str], stdin: &[u8]) -> Output {
+    rwc_env(args, stdin, &[])
+}
+
+/// Run rwc without colors unless envs like `CLICOLOR_FORCE=1` force them.
+fn rwc_env(args: &[&str], stdin: &[u8], envs: &[(&str, &str)]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .envs(envs.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_files_csv() {
+    let output = rwc(&["--format", "csv", "test_data/default.txt"], b"");
+    assert!(output.status.success());
+    assert_eq!(
+        "path,bytes,words,lines\ntest_data/default.txt,1048697,183155,20681",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn test_stdin_crlf() {
+    let output = rwc(&["--format", "csv"], b"one two\r\nthree\r\n");
+    assert!(output.status.success());
+    assert_eq!(
+        "path,bytes,words,lines\nStdin,16,3,2\nTotals,16,3,2",
+        stdout(&output)
+    );
+
+    let output = rwc(
+        &["--format", "csv", "--eol", "crlf"],
+        b"one two\r\nthree\r\n",
+    );
+    assert_eq!(
+        "path,bytes,words,lines\nStdin,16,3,2\nTotals,16,3,2",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn test_files0_from_stdin() {
+    let output = rwc(
+        &["--format", "csv", "--files0-from", "-"],
+        b"test_data/default.txt\r\n",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        "path,bytes,words,lines\ntest_data/default.txt,1048697,183155,20681",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn test_table() {
+    let output = rwc(&["test_data/default.txt"], b"");
+    assert!(output.status.success());
+    let table = stdout(&output);
+    assert!(table.contains("test_data/default.txt"));
+    assert!(table.contains("1048697"));
+    assert!(!table.contains('\u{1b}'));
+}
+
+#[test]
+fn test_missing_file() {
+    let output = rwc(&["--format", "csv", "test_data/missing.txt"], b"");
+    assert_eq!(Some(4), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("test_data/missing.txt"));
+}
+
+#[test]
+fn test_output_without_colors() {
+    let dir = std::env::temp_dir().join(format!("rwc-cli-output-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("report.txt");
+    let output = rwc_env(
+        &[
+            "--output",
+            table.to_str().unwrap(),
+            "test_data/default.txt",
+            "test_data/missing.txt",
+        ],
+        b"",
+        &[("CLICOLOR_FORCE", "1")],
+    );
+    assert_eq!(Some(2), output.status.code());
+    let table = std::fs::read_to_string(&table).unwrap();
+    assert!(table.contains("1048697"));
+    assert!(table.contains("IO Error"));
+    assert!(!table.contains('\u{1b}'));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_output_and_tee() {
+    let dir = std::env::temp_dir().join(format!("rwc-cli-tee-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("table.txt");
+    let csv = dir.join("report.csv");
+    let wc = dir.join("report.txt");
+    let tee_csv = format!("csv={}", csv.display());
+    let tee_wc = format!("wc={}", wc.display());
+    // the tees are written first and mustn't turn colors back on for the --output file
+    let output = rwc_env(
+        &[
+            "--output",
+            table.to_str().unwrap(),
+            "--tee",
+            &tee_csv,
+            "--tee",
+            &tee_wc,
+            "test_data/default.txt",
+        ],
+        b"",
+        &[("CLICOLOR_FORCE", "1")],
+    );
+    assert!(output.status.success());
+    assert_eq!("", stdout(&output));
+    let table = std::fs::read_to_string(&table).unwrap();
+    assert!(table.contains("1048697"));
+    assert!(!table.contains('\u{1b}'));
+    assert_eq!(
+        "path,bytes,words,lines\ntest_data/default.txt,1048697,183155,20681",
+        std::fs::read_to_string(&csv).unwrap()
+    );
+    assert_eq!(
+        "  20681  183155 1048697 test_data/default.txt\n",
+        std::fs::read_to_string(&wc).unwrap()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_files_named_like_subcommands() {
+    let dir = std::env::temp_dir().join(format!("rwc-cli-hook-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("hook"), "a b\n").unwrap();
+    std::fs::write(dir.join("hooks"), "c\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .args(&["--format", "csv", "-w", "hooks", "--", "hook"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!("path,words\nhook,2\nhooks,1", stdout(&output));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_batch_after_interrupt() {
+    use std::io::{BufRead, BufReader};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .arg("--batch")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!("path,bytes,words,lines,partial\n", line);
+
+    // SAFETY: kill has no preconditions, the child is still running
+    assert_eq!(0, unsafe { libc::kill(child.id() as i32, libc::SIGINT) });
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    stdin.write_all(b"test_data/default.txt\n").unwrap();
+    line.clear();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!("test_data/default.txt,1048697,183155,20681,\n", line);
+
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_chars_histogram() {
+    let output = rwc(&["--format", "csv", "chars", "--histogram"], b"abca\n");
+    assert!(output.status.success());
+    assert_eq!(
+        "char,code,count\na,U+0061,2\n\\n,U+000A,1\nb,U+0062,1\nc,U+0063,1",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn test_zero_terminated() {
+    let output = rwc(&["--format", "csv", "-l", "-z"], b"a\nb\0c\0");
+    assert!(output.status.success());
+    assert_eq!("path,lines\nStdin,2\nTotals,2", stdout(&output));
+}
+
+#[test]
+fn test_count_matches_columns() {
+    let output = rwc(
+        &[
+            "--format",
+            "csv",
+            "-l",
+            "--count-matches",
+            "TODO",
+            "--count-matches",
+            "FIX(ME)?",
+            "--count-matches",
+            "TODO",
+        ],
+        b"TODO a\nFIX TODO\n",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        "path,lines,TODO,FIX(ME)?\nStdin,2,2,1\nTotals,2,2,1",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn test_max_display_width_is_max_line_length() {
+    let input = "abc\n東京タワー\r\ne\u{301}\tx\n".as_bytes();
+    let output = rwc(&["--format", "csv", "--max-display-width"], input);
+    assert!(output.status.success());
+    assert_eq!("path,max line length\nStdin,10\nTotals,10", stdout(&output));
+    assert_eq!(
+        stdout(&rwc(&["--format", "csv", "-L"], input)),
+        stdout(&output)
+    );
+}
+
+#[test]
+fn test_line_stats_totals() {
+    let dir = std::env::temp_dir().join(format!("rwc-cli-line-stats-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a"), "a\nabc\n").unwrap();
+    std::fs::write(dir.join("b"), "ab\rcd\r\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rwc"))
+        .args(&["--format", "csv", "-l", "--line-stats", "a", "b"])
+        .current_dir(&dir)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        "path,lines,min line length,mean line length,median line length\n\
+         a,2,1,2.000,2.000\nb,1,5,5.000,5.000\nTotals,3,1,3.000,3.000",
+        stdout(&output)
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}